@@ -0,0 +1,153 @@
+//! Frame times of drawing many cubes with one instanced draw call against one
+//! draw call per cube, rendered headless at a fixed size.
+//!
+//! ```shell
+//! cargo bench --bench instancing
+//! WGPU_BOOK_FALLBACK=1 cargo bench --bench instancing -- 1000 50000
+//! ```
+//!
+//! The counts of cubes may be given as arguments. Each frame is waited for:
+//! the record time covers encoding the render pass, which is where one draw
+//! call per object costs the most; the frame time includes submitting it and
+//! the GPU work as well.
+
+use std::iter;
+use std::time::{Duration, Instant};
+use wgpu_book::colormap::Colormap;
+use wgpu_book::depth::{DepthConfig, DepthTarget};
+use wgpu_book::headless;
+use wgpu_book::instance::{DrawMode, InstanceRenderer, Layout};
+use wgpu_book::transforms::{self, InitWgpu};
+use wgpu_book::vertex_data;
+
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 480;
+const WARMUP_FRAMES: u32 = 3;
+const FRAMES: u32 = 20;
+const SIZE: f32 = 4.0;
+
+/// Renders a frame and returns the time spent recording its commands.
+fn render_frame(
+    init: &InitWgpu,
+    renderer: &InstanceRenderer,
+    depth: &DepthTarget,
+    mode: DrawMode,
+) -> Duration {
+    let start = Instant::now();
+    let frame = init.get_current_frame().unwrap();
+    let mut encoder = init
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Render Encoder"),
+        });
+    {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(
+                init.color_attachment(&frame, wgpu::LoadOp::Clear(wgpu::Color::BLACK)),
+            )],
+            depth_stencil_attachment: Some(depth.attachment()),
+        });
+        renderer.draw(&mut render_pass, mode);
+    }
+    let commands = encoder.finish();
+    let recorded = start.elapsed();
+    init.queue.submit(iter::once(commands));
+    init.device.poll(wgpu::Maintain::Wait);
+    recorded
+}
+
+/// Average record and frame time in milliseconds.
+fn frame_time(init: &InitWgpu, count: usize, layout: Layout, mode: DrawMode) -> (f64, f64) {
+    let (pos, _col, _uv, normal) = vertex_data::cube_data();
+    let to_f32 = |v: &[i8; 3]| v.map(|x| x as f32);
+    let positions: Vec<[f32; 3]> = pos.iter().map(to_f32).collect();
+    let normals: Vec<[f32; 3]> = normal.iter().map(to_f32).collect();
+    let instances = layout.instances(count, SIZE, &Colormap::from_name("jet").unwrap());
+
+    let depth = DepthTarget::new(
+        &init.device,
+        WIDTH,
+        HEIGHT,
+        init.sample_count(),
+        DepthConfig::default(),
+    );
+    let renderer = InstanceRenderer::new(
+        &init.device,
+        init.config.format,
+        depth.config,
+        init.sample_count(),
+        &positions,
+        &normals,
+        &instances,
+    );
+    let (_, _, view_project_mat) = transforms::create_view_projection(
+        (1.4 * SIZE, 0.9 * SIZE, 1.4 * SIZE).into(),
+        (0.0, 0.0, 0.0).into(),
+        cgmath::Vector3::unit_y(),
+        WIDTH as f32 / HEIGHT as f32,
+        true,
+    );
+    renderer.update(
+        &init.queue,
+        view_project_mat,
+        transforms::create_transforms([0.0; 3], [0.0; 3], [1.0; 3]),
+    );
+
+    for _ in 0..WARMUP_FRAMES {
+        render_frame(init, &renderer, &depth, mode);
+    }
+    let start = Instant::now();
+    let mut record = Duration::ZERO;
+    for _ in 0..FRAMES {
+        record += render_frame(init, &renderer, &depth, mode);
+    }
+    let ms = |d: Duration| d.as_secs_f64() * 1e3 / FRAMES as f64;
+    (ms(record), ms(start.elapsed()))
+}
+
+fn main() {
+    env_logger::init();
+    // cargo bench passes --bench
+    let mut counts: Vec<usize> = std::env::args()
+        .skip(1)
+        .filter_map(|a| a.parse().ok())
+        .collect();
+    if counts.is_empty() {
+        counts = vec![100, 1_000, 10_000];
+    }
+    let fallback = headless::env_flag("WGPU_BOOK_FALLBACK");
+    let init = match pollster::block_on(InitWgpu::init_headless(WIDTH, HEIGHT, fallback)) {
+        Ok(init) => init,
+        Err(e) => {
+            eprintln!("skipping the instancing benchmark: {:#}", e);
+            return;
+        }
+    };
+
+    println!(
+        "{:>8} {:>6} {:>10} {:>10} {:>10} {:>10}",
+        "", "", "instanced", "", "per-object", ""
+    );
+    println!(
+        "{:>8} {:>6} {:>10} {:>10} {:>10} {:>10}",
+        "cubes", "layout", "record ms", "frame ms", "record ms", "frame ms"
+    );
+    for &count in &counts {
+        for layout in [Layout::Grid, Layout::Cloud] {
+            let (instanced_record, instanced) =
+                frame_time(&init, count, layout, DrawMode::Instanced);
+            let (per_object_record, per_object) =
+                frame_time(&init, count, layout, DrawMode::PerObject);
+            println!(
+                "{:>8} {:>6} {:>10.3} {:>10.3} {:>10.3} {:>10.3}",
+                count,
+                format!("{:?}", layout).to_lowercase(),
+                instanced_record,
+                instanced,
+                per_object_record,
+                per_object
+            );
+        }
+    }
+}
@@ -13,11 +13,13 @@ fn main() {
     window.set_title("window");
     env_logger::init();
 
-    let _ = event_loop.run(move |event, elwt| match event {
-        Event::WindowEvent {
+    let _ = event_loop.run(move |event, elwt| {
+        if let Event::WindowEvent {
             event: WindowEvent::CloseRequested,
             ..
-        } => elwt.exit(),
-        _ => {}
+        } = event
+        {
+            elwt.exit()
+        }
     });
 }
@@ -1,5 +1,5 @@
-#[path = "../common/common.rs"]
-mod common;
+use wgpu_book::common;
+use wgpu_book::headless::Headless;
 
 use std::borrow::Cow;
 use winit::event_loop::EventLoop;
@@ -27,10 +27,15 @@ fn main() {
         strip_index_format: index_format,
     };
 
+    if let Some(headless) = Headless::from_env() {
+        pollster::block_on(common::render_png(inputs, 6, &headless)).unwrap();
+        return;
+    }
+
     let event_loop = EventLoop::new().unwrap();
     let window = winit::window::Window::new(&event_loop).unwrap();
 
-    window.set_title(&*format!("{}: {}", "Primitive", primitive_type));
+    window.set_title(&format!("{}: {}", "Primitive", primitive_type));
     env_logger::init();
     pollster::block_on(common::run(event_loop, window, inputs, 6));
 }
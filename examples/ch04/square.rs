@@ -2,10 +2,12 @@ use std::{iter, mem};
 
 use bytemuck::{cast_slice, Pod, Zeroable};
 use wgpu::util::DeviceExt;
+use wgpu_book::headless::Headless;
+use wgpu_book::transforms;
 use winit::{
     event::*,
     event_loop::{ControlFlow, EventLoop},
-    window::{Window, WindowBuilder},
+    window::WindowBuilder,
 };
 
 #[repr(C)]
@@ -61,118 +63,70 @@ impl Vertex {
 }
 
 struct State {
-    surface: wgpu::Surface,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    config: wgpu::SurfaceConfiguration,
-    size: winit::dpi::PhysicalSize<u32>,
+    init: transforms::InitWgpu,
     pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
 }
 
 impl State {
-    async fn new(window: &Window) -> Self {
-        let size = window.inner_size();
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::VULKAN,
-            dx12_shader_compiler: wgpu::Dx12Compiler::default(),
-        });
+    fn new(init: transforms::InitWgpu) -> Self {
+        let shader = init
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Shader"),
+                source: wgpu::ShaderSource::Wgsl(include_str!("triangle.wgsl").into()),
+            });
 
-        let surface = unsafe { instance.create_surface(window) }.unwrap();
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                force_fallback_adapter: false,
-                compatible_surface: Some(&surface),
-            })
-            .await
-            .expect("Failed to find an appropriate adapter");
+        let pipeline_layout = init
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[],
+                push_constant_ranges: &[],
+            });
 
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: None,
-                    features: wgpu::Features::empty(),
-                    limits: wgpu::Limits::default(),
+        let pipeline = init
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Render Pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[Vertex::desc()],
                 },
-                None,
-            )
-            .await
-            .expect("Failed to create device");
-
-        let swapchain_capabilities = surface.get_capabilities(&adapter);
-        let swapchain_format = swapchain_capabilities.formats[0];
-
-        let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: swapchain_format,
-            width: size.width,
-            height: size.height,
-            present_mode: wgpu::PresentMode::Fifo,
-            alpha_mode: swapchain_capabilities.alpha_modes[0],
-            view_formats: vec![],
-        };
-
-        surface.configure(&device, &config);
-
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("triangle.wgsl").into()),
-        });
-
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: None,
-            bind_group_layouts: &[],
-            push_constant_ranges: &[],
-        });
-
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[Vertex::desc()],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[Some(swapchain_format.into())],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                ..Default::default()
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview: None,
-        });
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(init.config.format.into())],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                multisample: init.multisample_state(),
+                multiview: None,
+            });
 
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Vertex Buffer"),
-            contents: cast_slice(VERTICES),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
+        let vertex_buffer = init
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Vertex Buffer"),
+                contents: cast_slice(VERTICES),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
 
         State {
-            surface,
-            device,
-            queue,
-            config,
-            size,
+            init,
             pipeline,
             vertex_buffer,
         }
     }
 
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
-        if new_size.width > 0 && new_size.height > 0 {
-            self.size = new_size;
-            self.config.width = new_size.width;
-            self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
-        }
+        self.init.resize(new_size);
     }
 
     #[allow(dead_code)]
@@ -183,32 +137,26 @@ impl State {
     fn update(&mut self) {}
 
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.surface.get_current_texture()?;
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Render Encoder"),
-            });
+        let frame = self.init.get_current_frame()?;
+
+        let mut encoder =
+            self.init
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                });
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.2,
-                            g: 0.247,
-                            b: 0.314,
-                            a: 1.0,
-                        }),
-                        store: true,
-                    },
-                })],
+                color_attachments: &[Some(self.init.color_attachment(
+                    &frame,
+                    wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.2,
+                        g: 0.247,
+                        b: 0.314,
+                        a: 1.0,
+                    }),
+                ))],
                 depth_stencil_attachment: None,
             });
 
@@ -217,8 +165,8 @@ impl State {
             render_pass.draw(0..6, 0..1);
         }
 
-        self.queue.submit(iter::once(encoder.finish()));
-        output.present();
+        self.init.queue.submit(iter::once(encoder.finish()));
+        frame.present();
 
         Ok(())
     }
@@ -226,10 +174,19 @@ impl State {
 
 fn main() {
     env_logger::init();
+    if let Some(headless) = Headless::from_env() {
+        let init = pollster::block_on(headless.init_wgpu()).unwrap();
+        let mut state = State::new(init);
+        state.update();
+        state.render().unwrap();
+        state.init.save_png(&headless.path).unwrap();
+        return;
+    }
     let event_loop = EventLoop::new().unwrap();
     let window = WindowBuilder::new().build(&event_loop).unwrap();
-    window.set_title(&*format!("{}", "ch04-triangle"));
-    let mut state = pollster::block_on(State::new(&window));
+    window.set_title("ch04-triangle");
+    let init = pollster::block_on(transforms::InitWgpu::init_wgpu(&window));
+    let mut state = State::new(init);
 
     event_loop.set_control_flow(ControlFlow::Wait);
     let _ = event_loop.run(move |event, elwt| match event {
@@ -237,18 +194,25 @@ fn main() {
             event: WindowEvent::Resized(size),
             ..
         } => {
-            state.config.width = size.width;
-            state.config.height = size.height;
-            state.surface.configure(&state.device, &state.config);
+            state.resize(size);
         }
         Event::WindowEvent {
-            event: WindowEvent::RedrawRequested,
+            event: WindowEvent::ScaleFactorChanged { .. },
             ..
         } => {
+            state.resize(window.inner_size());
+        }
+        Event::WindowEvent {
+            event: WindowEvent::RedrawRequested,
+            ..
+        } if !state.init.is_minimized() => {
             state.update();
             match state.render() {
                 Ok(_) => {}
-                Err(wgpu::SurfaceError::Lost) => state.resize(state.size),
+                // the surface has to be configured again, e.g. after a mode switch
+                Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                    state.resize(state.init.size)
+                }
                 Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
                 Err(e) => eprintln!("{:?}", e),
             }
@@ -257,7 +221,8 @@ fn main() {
             event: WindowEvent::CloseRequested,
             ..
         } => elwt.exit(),
-        Event::AboutToWait => {
+        // rendering pauses while the window is minimized
+        Event::AboutToWait if !state.init.is_minimized() => {
             window.request_redraw();
         }
         _ => {}
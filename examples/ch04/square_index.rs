@@ -8,6 +8,8 @@ use winit::{
     window::{Window, WindowBuilder},
 };
 
+use wgpu_book::msaa::{self, MsaaTarget};
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
 struct Vertex {
@@ -58,6 +60,7 @@ struct State {
     queue: wgpu::Queue,
     config: wgpu::SurfaceConfiguration,
     size: winit::dpi::PhysicalSize<u32>,
+    msaa: MsaaTarget,
     pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
@@ -86,7 +89,7 @@ impl State {
             .request_device(
                 &wgpu::DeviceDescriptor {
                     label: None,
-                    features: wgpu::Features::empty(),
+                    features: msaa::device_features(&adapter),
                     limits: wgpu::Limits::default(),
                 },
                 None,
@@ -109,6 +112,21 @@ impl State {
 
         surface.configure(&device, &config);
 
+        let sample_count = msaa::supported_sample_count(
+            &adapter,
+            &device,
+            swapchain_format,
+            &[],
+            msaa::sample_count_from_env(),
+        );
+        let msaa = MsaaTarget::new(
+            &device,
+            swapchain_format,
+            size.width,
+            size.height,
+            sample_count,
+        );
+
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Shader"),
             source: wgpu::ShaderSource::Wgsl(include_str!("triangle.wgsl").into()),
@@ -139,7 +157,7 @@ impl State {
                 ..Default::default()
             },
             depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
+            multisample: msaa.multisample_state(),
             multiview: None,
         });
 
@@ -162,6 +180,7 @@ impl State {
             queue,
             config,
             size,
+            msaa,
             pipeline,
             vertex_buffer,
             index_buffer,
@@ -170,14 +189,20 @@ impl State {
     }
 
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
-        if new_size.width > 0 && new_size.height > 0 {
-            self.size = new_size;
+        self.size = new_size;
+        if !self.is_minimized() {
             self.config.width = new_size.width;
             self.config.height = new_size.height;
             self.surface.configure(&self.device, &self.config);
+            self.msaa
+                .resize(&self.device, new_size.width, new_size.height);
         }
     }
 
+    fn is_minimized(&self) -> bool {
+        self.size.width == 0 || self.size.height == 0
+    }
+
     #[allow(dead_code)]
     fn input(&mut self, _event: &WindowEvent) -> bool {
         false
@@ -199,19 +224,15 @@ impl State {
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.2,
-                            g: 0.247,
-                            b: 0.314,
-                            a: 1.0,
-                        }),
-                        store: true,
-                    },
-                })],
+                color_attachments: &[Some(self.msaa.color_attachment(
+                    &view,
+                    wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.2,
+                        g: 0.247,
+                        b: 0.314,
+                        a: 1.0,
+                    }),
+                ))],
                 depth_stencil_attachment: None,
             });
 
@@ -232,7 +253,7 @@ fn main() {
     env_logger::init();
     let event_loop = EventLoop::new().unwrap();
     let window = WindowBuilder::new().build(&event_loop).unwrap();
-    window.set_title(&*format!("{}", "ch04-triangle"));
+    window.set_title("ch04-triangle");
     let mut state = pollster::block_on(State::new(&window));
 
     event_loop.set_control_flow(ControlFlow::Wait);
@@ -241,18 +262,25 @@ fn main() {
             event: WindowEvent::Resized(size),
             ..
         } => {
-            state.config.width = size.width;
-            state.config.height = size.height;
-            state.surface.configure(&state.device, &state.config);
+            state.resize(size);
         }
         Event::WindowEvent {
-            event: WindowEvent::RedrawRequested,
+            event: WindowEvent::ScaleFactorChanged { .. },
             ..
         } => {
+            state.resize(window.inner_size());
+        }
+        Event::WindowEvent {
+            event: WindowEvent::RedrawRequested,
+            ..
+        } if !state.is_minimized() => {
             state.update();
             match state.render() {
                 Ok(_) => {}
-                Err(wgpu::SurfaceError::Lost) => state.resize(state.size),
+                // the surface has to be configured again, e.g. after a mode switch
+                Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                    state.resize(state.size)
+                }
                 Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
                 Err(e) => eprintln!("{:?}", e),
             }
@@ -261,7 +289,8 @@ fn main() {
             event: WindowEvent::CloseRequested,
             ..
         } => elwt.exit(),
-        Event::AboutToWait => {
+        // rendering pauses while the window is minimized
+        Event::AboutToWait if !state.is_minimized() => {
             window.request_redraw();
         }
         _ => {}
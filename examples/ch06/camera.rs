@@ -1,5 +1,8 @@
 use cgmath::*;
 use std::f32::consts::PI;
+use std::time::Duration;
+use wgpu_book::keybindings::{Action, KeyBindings};
+use winit::event::{ElementState, KeyEvent};
 
 pub struct Camera {
     pub position: Point3<f32>,
@@ -20,17 +23,18 @@ impl Camera {
         }
     }
 
-    pub fn view_mat(&self) -> Matrix4<f32> {
-        Matrix4::look_to_rh(
-            self.position,
-            Vector3::new(
-                self.pitch.0.cos() * self.yaw.0.cos(),
-                self.pitch.0.sin(),
-                self.pitch.0.cos() * self.yaw.0.sin(),
-            )
-            .normalize(),
-            Vector3::unit_y(),
+    /// Unit vector in the viewing direction.
+    pub fn forward(&self) -> Vector3<f32> {
+        Vector3::new(
+            self.pitch.0.cos() * self.yaw.0.cos(),
+            self.pitch.0.sin(),
+            self.pitch.0.cos() * self.yaw.0.sin(),
         )
+        .normalize()
+    }
+
+    pub fn view_mat(&self) -> Matrix4<f32> {
+        Matrix4::look_to_rh(self.position, self.forward(), Vector3::unit_y())
     }
 }
 
@@ -38,6 +42,12 @@ pub struct CameraController {
     rotatex: f32,
     rotatey: f32,
     speed: f32,
+    /// Movement in units per second.
+    pub move_speed: f32,
+    /// Factor on `move_speed` while [`Action::Boost`] is held.
+    pub boost: f32,
+    bindings: KeyBindings,
+    pressed: Vec<Action>,
 }
 
 impl CameraController {
@@ -46,18 +56,42 @@ impl CameraController {
             rotatex: 0.0,
             rotatey: 0.0,
             speed,
+            move_speed: 2.0,
+            boost: 4.0,
+            bindings: KeyBindings::default(),
+            pressed: Vec::new(),
         }
     }
 
+    pub fn with_bindings(mut self, bindings: KeyBindings) -> Self {
+        self.bindings = bindings;
+        self
+    }
+
     pub fn mouse_move(&mut self, mousex: f64, mousey: f64) {
         self.rotatex = mousex as f32;
         self.rotatey = mousey as f32;
     }
 
-    pub fn update_camera(&mut self, camera: &mut Camera) {
+    /// Tracks the bound keys; returns `true` if the key is bound.
+    pub fn process_keyboard(&mut self, event: &KeyEvent) -> bool {
+        let Some(action) = self.bindings.action(&event.logical_key) else {
+            return false;
+        };
+        self.pressed.retain(|&a| a != action);
+        if event.state == ElementState::Pressed {
+            self.pressed.push(action);
+        }
+        true
+    }
+
+    /// Turns the camera by the last mouse motion and moves it by the held
+    /// keys; `dt` is the time since the previous frame, so the movement does
+    /// not depend on the frame rate.
+    pub fn update_camera(&mut self, camera: &mut Camera, dt: Duration) {
         camera.yaw += Rad(self.rotatex) * self.speed;
         camera.pitch += Rad(self.rotatey) * self.speed;
-        
+
         self.rotatex = 0.0;
         self.rotatey = 0.0;
 
@@ -66,5 +100,20 @@ impl CameraController {
         } else if camera.pitch > Rad(89.0 * PI / 180.0) {
             camera.pitch = Rad(89.0 * PI / 180.0);
         }
+
+        let held = |action| self.pressed.contains(&action) as i32 as f32;
+        let forward = camera.forward();
+        let right = forward.cross(Vector3::unit_y()).normalize();
+        let direction = forward * (held(Action::Forward) - held(Action::Backward))
+            + right * (held(Action::Right) - held(Action::Left))
+            + Vector3::unit_y() * (held(Action::Up) - held(Action::Down));
+        if direction.magnitude2() > 0.0 {
+            let boost = if self.pressed.contains(&Action::Boost) {
+                self.boost
+            } else {
+                1.0
+            };
+            camera.position += direction.normalize() * self.move_speed * boost * dt.as_secs_f32();
+        }
     }
 }
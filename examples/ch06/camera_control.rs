@@ -1,18 +1,15 @@
 use bytemuck::{cast_slice, Pod, Zeroable};
 use cgmath::*;
-use std::{iter, mem};
+use std::{iter, mem, time::Duration};
 use wgpu::util::DeviceExt;
-use winit::{
-    event::*,
-    event_loop::{ControlFlow, EventLoop},
-    window::{Window, WindowBuilder},
-};
+use winit::{dpi::PhysicalSize, event::*};
 
 mod camera;
-#[path = "../common/transforms.rs"]
-mod transforms;
-#[path = "../common/vertex_data.rs"]
-mod vertex_data;
+use wgpu_book::app::{self, App};
+use wgpu_book::depth::{DepthConfig, DepthTarget};
+use wgpu_book::keybindings::KeyBindings;
+use wgpu_book::transforms::{self, Frame, InitWgpu};
+use wgpu_book::vertex_data;
 
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
@@ -69,7 +66,6 @@ impl Vertex {
 }
 
 struct State {
-    init: transforms::InitWgpu,
     pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
 
@@ -80,11 +76,13 @@ struct State {
     camera_buffer: wgpu::Buffer,
     camera_bind_group: wgpu::BindGroup,
     mouse_pressed: bool,
+    depth: DepthTarget,
 }
 
-impl State {
-    async fn new(window: &Window) -> Self {
-        let init = transforms::InitWgpu::init_wgpu(window).await;
+impl App for State {
+    type Scene<'a> = KeyBindings;
+
+    fn init(init: &InitWgpu, bindings: KeyBindings) -> Self {
         let shader = init
             .device
             .create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -93,11 +91,18 @@ impl State {
             });
 
         let camera = camera::Camera::new((2.0, 3.0, 5.0), cgmath::Deg(-80.0), cgmath::Deg(-30.0));
-        let projection = transforms::create_projection(
+        let depth = DepthTarget::new(
+            &init.device,
+            init.config.width,
+            init.config.height,
+            init.sample_count(),
+            DepthConfig::from_env(),
+        );
+        let projection = depth.config.projection(transforms::create_projection(
             init.config.width as f32 / init.config.height as f32,
             true,
-        );
-        let camera_controller = camera::CameraController::new(0.005);
+        ));
+        let camera_controller = camera::CameraController::new(0.005).with_bindings(bindings);
 
         let mut camera_uniform = CameraUniform::new();
         camera_uniform.update_view_project(&camera, projection);
@@ -171,14 +176,8 @@ impl State {
                     ..Default::default()
                 },
                 //depth_stencil: None,
-                depth_stencil: Some(wgpu::DepthStencilState {
-                    format: wgpu::TextureFormat::Depth24Plus,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::LessEqual,
-                    stencil: wgpu::StencilState::default(),
-                    bias: wgpu::DepthBiasState::default(),
-                }),
-                multisample: wgpu::MultisampleState::default(),
+                depth_stencil: Some(depth.config.depth_stencil_state()),
+                multisample: init.multisample_state(),
                 multiview: None,
             });
 
@@ -191,7 +190,6 @@ impl State {
             });
 
         Self {
-            init,
             pipeline,
             vertex_buffer,
             camera,
@@ -201,106 +199,74 @@ impl State {
             camera_bind_group,
             camera_uniform,
             mouse_pressed: false,
+            depth,
         }
     }
 
-    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
-        if new_size.width > 0 && new_size.height > 0 {
-            self.projection =
-                transforms::create_projection(new_size.width as f32 / new_size.height as f32, true);
-            self.init.size = new_size;
-            self.init.config.width = new_size.width;
-            self.init.config.height = new_size.height;
-            self.init
-                .surface
-                .configure(&self.init.device, &self.init.config);
+    fn resize(&mut self, init: &InitWgpu, new_size: PhysicalSize<u32>) {
+        self.projection = self.depth.config.projection(transforms::create_projection(
+            new_size.width as f32 / new_size.height as f32,
+            true,
+        ));
+        self.depth
+            .resize(&init.device, new_size.width, new_size.height);
+    }
+
+    fn input(&mut self, _init: &InitWgpu, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => {
+                self.camera_controller.process_keyboard(event)
+            }
+            _ => false,
         }
     }
 
-    #[allow(dead_code)]
-    fn input(&mut self, event: &DeviceEvent) -> bool {
+    fn device_input(&mut self, _init: &InitWgpu, event: &DeviceEvent) {
         match event {
             DeviceEvent::Button {
                 button: 1, // Left Mouse Button
                 state,
             } => {
                 self.mouse_pressed = *state == ElementState::Pressed;
-                true
             }
-            DeviceEvent::MouseMotion { delta } => {
-                if self.mouse_pressed {
-                    self.camera_controller.mouse_move(delta.0, delta.1);
-                }
-                true
+            DeviceEvent::MouseMotion { delta } if self.mouse_pressed => {
+                self.camera_controller.mouse_move(delta.0, delta.1);
             }
-            _ => false,
+            _ => {}
         }
     }
 
-    fn update(&mut self) {
-        self.camera_controller.update_camera(&mut self.camera);
+    fn update(&mut self, init: &InitWgpu, _elapsed: Duration, dt: Duration) {
+        self.camera_controller.update_camera(&mut self.camera, dt);
         self.camera_uniform
             .update_view_project(&self.camera, self.projection);
-        self.init.queue.write_buffer(
+        init.queue.write_buffer(
             &self.camera_buffer,
             0,
             bytemuck::cast_slice(&[self.camera_uniform]),
         );
     }
 
-    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.init.surface.get_current_texture()?;
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-
-        let depth_texture = self.init.device.create_texture(&wgpu::TextureDescriptor {
-            size: wgpu::Extent3d {
-                width: self.init.config.width,
-                height: self.init.config.height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Depth24Plus,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            label: None,
-            view_formats: &[],
-        });
-        let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-        let mut encoder =
-            self.init
-                .device
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("Render Encoder"),
-                });
+    fn render(&mut self, init: &InitWgpu, frame: &mut Frame) {
+        let mut encoder = init
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.2,
-                            g: 0.247,
-                            b: 0.314,
-                            a: 1.0,
-                        }),
-                        store: true,
-                    },
-                })],
-                // depth_stencil_attachment: None,
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: &depth_view,
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
-                        store: false,
+                color_attachments: &[Some(init.color_attachment(
+                    frame,
+                    wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.2,
+                        g: 0.247,
+                        b: 0.314,
+                        a: 1.0,
                     }),
-                    stencil_ops: None,
-                }),
+                ))],
+                // depth_stencil_attachment: None,
+                depth_stencil_attachment: Some(self.depth.attachment()),
             });
 
             render_pass.set_pipeline(&self.pipeline);
@@ -309,55 +275,15 @@ impl State {
             render_pass.draw(0..36, 0..1);
         }
 
-        self.init.queue.submit(iter::once(encoder.finish()));
-        output.present();
-
-        Ok(())
+        init.queue.submit(iter::once(encoder.finish()));
     }
 }
 
 fn main() {
-    env_logger::init();
-    let event_loop = EventLoop::new().unwrap();
-    let window = WindowBuilder::new().build(&event_loop).unwrap();
-    window.set_title(&*format!("{}", "ch06_camera_control"));
-    let mut state = pollster::block_on(State::new(&window));
-
-    event_loop.set_control_flow(ControlFlow::Poll);
-    let _ = event_loop.run(move |event, elwt| match event {
-        Event::DeviceEvent { ref event, .. } => {
-            state.input(event);
-        }
-        Event::WindowEvent {
-            event: WindowEvent::Resized(size),
-            ..
-        } => {
-            state.init.config.width = size.width;
-            state.init.config.height = size.height;
-            state
-                .init
-                .surface
-                .configure(&state.init.device, &state.init.config);
-        }
-        Event::WindowEvent {
-            event: WindowEvent::RedrawRequested,
-            ..
-        } => {
-            state.update();
-            match state.render() {
-                Ok(_) => {}
-                Err(wgpu::SurfaceError::Lost) => state.resize(state.init.size),
-                Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
-                Err(e) => eprintln!("{:?}", e),
-            }
-        }
-        Event::WindowEvent {
-            event: WindowEvent::CloseRequested,
-            ..
-        } => elwt.exit(),
-        Event::AboutToWait => {
-            window.request_redraw();
-        }
-        _ => {}
-    });
+    let args: Vec<String> = std::env::args().collect();
+    let bindings = match args.get(1) {
+        Some(path) => KeyBindings::from_file(path).unwrap(),
+        None => KeyBindings::default(),
+    };
+    app::run::<State>("ch06_camera_control", bindings);
 }
@@ -0,0 +1,166 @@
+use cgmath::*;
+use std::{iter, time::Duration};
+use winit::dpi::PhysicalSize;
+
+use wgpu_book::app::{self, App};
+use wgpu_book::colormap::Colormap;
+use wgpu_book::depth::{DepthConfig, DepthTarget};
+use wgpu_book::instance::{DrawMode, InstanceRenderer, Layout};
+use wgpu_book::transforms::{self, Frame, InitWgpu};
+use wgpu_book::vertex_data;
+
+const IS_PERSPECTIVE: bool = true;
+const ANIMATION_SPEED: f32 = 0.3;
+// radius of the ball the cubes fit in
+const SIZE: f32 = 4.0;
+
+struct Scene {
+    layout: Layout,
+    count: usize,
+    mode: DrawMode,
+}
+
+struct State {
+    renderer: InstanceRenderer,
+    mode: DrawMode,
+    view_mat: Matrix4<f32>,
+    project_mat: Matrix4<f32>,
+    depth: DepthTarget,
+    // frames since `last_report`, for the frame rate in the log
+    frames: u32,
+    last_report: Duration,
+}
+
+impl App for State {
+    type Scene<'a> = Scene;
+
+    fn init(init: &InitWgpu, scene: Scene) -> Self {
+        let (pos, _col, _uv, normal) = vertex_data::cube_data();
+        let to_f32 = |v: &[i8; 3]| v.map(|x| x as f32);
+        let positions: Vec<[f32; 3]> = pos.iter().map(to_f32).collect();
+        let normals: Vec<[f32; 3]> = normal.iter().map(to_f32).collect();
+
+        let colormap = Colormap::from_name("jet").unwrap();
+        let instances = scene.layout.instances(scene.count, SIZE, &colormap);
+        let depth = DepthTarget::new(
+            &init.device,
+            init.config.width,
+            init.config.height,
+            init.sample_count(),
+            DepthConfig::from_env(),
+        );
+        let renderer = InstanceRenderer::new(
+            &init.device,
+            init.config.format,
+            depth.config,
+            init.sample_count(),
+            &positions,
+            &normals,
+            &instances,
+        );
+
+        let camera_position = (1.4 * SIZE, 0.9 * SIZE, 1.4 * SIZE).into();
+        let look_direction = (0.0, 0.0, 0.0).into();
+        let up_direction = cgmath::Vector3::unit_y();
+        let (view_mat, project_mat, _) = transforms::create_view_projection(
+            camera_position,
+            look_direction,
+            up_direction,
+            init.config.width as f32 / init.config.height as f32,
+            IS_PERSPECTIVE,
+        );
+        let project_mat = depth.config.projection(project_mat);
+
+        Self {
+            renderer,
+            mode: scene.mode,
+            view_mat,
+            project_mat,
+            depth,
+            frames: 0,
+            last_report: Duration::ZERO,
+        }
+    }
+
+    fn resize(&mut self, init: &InitWgpu, new_size: PhysicalSize<u32>) {
+        self.depth
+            .resize(&init.device, new_size.width, new_size.height);
+        self.project_mat = self.depth.config.projection(transforms::create_projection(
+            new_size.width as f32 / new_size.height as f32,
+            IS_PERSPECTIVE,
+        ));
+    }
+
+    fn update(&mut self, init: &InitWgpu, elapsed: Duration, _dt: Duration) {
+        // the whole layout turns; the instance data stays the same
+        let t = ANIMATION_SPEED * elapsed.as_secs_f32();
+        let model_mat = transforms::create_transforms([0.0; 3], [0.0, t, 0.5 * t.sin()], [1.0; 3]);
+        self.renderer
+            .update(&init.queue, self.project_mat * self.view_mat, model_mat);
+
+        self.frames += 1;
+        let since_report = elapsed - self.last_report;
+        if since_report >= Duration::from_secs(2) {
+            log::info!(
+                "{} cubes, {:?}: {:.1} frames per second",
+                self.renderer.num_instances(),
+                self.mode,
+                self.frames as f32 / since_report.as_secs_f32()
+            );
+            self.frames = 0;
+            self.last_report = elapsed;
+        }
+    }
+
+    fn render(&mut self, init: &InitWgpu, frame: &mut Frame) {
+        let mut encoder = init
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass"),
+                color_attachments: &[Some(init.color_attachment(
+                    frame,
+                    wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.2,
+                        g: 0.247,
+                        b: 0.314,
+                        a: 1.0,
+                    }),
+                ))],
+                depth_stencil_attachment: Some(self.depth.attachment()),
+            });
+            self.renderer.draw(&mut render_pass, self.mode);
+        }
+        init.queue.submit(iter::once(encoder.finish()));
+    }
+
+    fn title(&self) -> Option<String> {
+        Some(format!(
+            "ch06_instances: {} cubes, {:?}",
+            self.renderer.num_instances(),
+            self.mode
+        ))
+    }
+}
+
+fn main() {
+    let mut scene = Scene {
+        layout: Layout::Grid,
+        count: 10_000,
+        mode: DrawMode::Instanced,
+    };
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 {
+        scene.layout = args[1].parse().unwrap();
+    }
+    if args.len() > 2 {
+        scene.count = args[2].parse().unwrap();
+    }
+    if args.len() > 3 {
+        scene.mode = args[3].parse().unwrap();
+    }
+    app::run::<State>("ch06_instances", scene);
+}
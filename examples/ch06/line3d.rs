@@ -1,14 +1,10 @@
 use bytemuck::{cast_slice, Pod, Zeroable};
 use cgmath::*;
-use std::{iter, mem};
+use std::{iter, mem, time::Duration};
 use wgpu::util::DeviceExt;
-use winit::{
-    event::*,
-    event_loop::{ControlFlow, EventLoop},
-    window::{Window, WindowBuilder},
-};
-#[path = "../common/transforms.rs"]
-mod transforms;
+use wgpu_book::app::{self, App};
+use wgpu_book::transforms::{self, Frame, InitWgpu};
+use winit::dpi::PhysicalSize;
 
 const IS_PERSPECTIVE: bool = true;
 
@@ -22,12 +18,12 @@ fn create_vertices() -> [Vertex; 300] {
     let mut vertices = [Vertex {
         position: [0.0, 0.0, 0.0],
     }; 300];
-    for i in 0..300 {
+    for (i, vertex) in vertices.iter_mut().enumerate() {
         let t = 0.1 * (i as f32) / 30.0;
         let x = (-t).exp() * (30.0 * t).sin();
         let z = (-t).exp() * (30.0 * t).cos();
         let y = 2.0 * t - 1.0;
-        vertices[i] = Vertex {
+        *vertex = Vertex {
             position: [x, y, z],
         };
     }
@@ -46,7 +42,6 @@ impl Vertex {
 }
 
 struct State {
-    init: transforms::InitWgpu,
     pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
     uniform_buffer: wgpu::Buffer,
@@ -56,9 +51,10 @@ struct State {
     project_mat: Matrix4<f32>,
 }
 
-impl State {
-    async fn new(window: &Window) -> Self {
-        let init = transforms::InitWgpu::init_wgpu(window).await;
+impl App for State {
+    type Scene<'a> = ();
+
+    fn init(init: &InitWgpu, _scene: ()) -> Self {
         let shader = init
             .device
             .create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -149,7 +145,7 @@ impl State {
                     ..Default::default()
                 },
                 depth_stencil: None,
-                multisample: wgpu::MultisampleState::default(),
+                multisample: init.multisample_state(),
                 multiview: None,
             });
 
@@ -162,7 +158,6 @@ impl State {
             });
 
         Self {
-            init,
             pipeline,
             vertex_buffer,
             uniform_buffer,
@@ -173,61 +168,37 @@ impl State {
         }
     }
 
-    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
-        if new_size.width > 0 && new_size.height > 0 {
-            self.init.size = new_size;
-            self.init.config.width = new_size.width;
-            self.init.config.height = new_size.height;
-            self.init
-                .surface
-                .configure(&self.init.device, &self.init.config);
-            self.project_mat = transforms::create_projection(
-                new_size.width as f32 / new_size.height as f32,
-                IS_PERSPECTIVE,
-            );
-            let mvp_mat = self.project_mat * self.view_mat * self.model_mat;
-            let mvp_ref: &[f32; 16] = mvp_mat.as_ref();
-            self.init
-                .queue
-                .write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(mvp_ref));
-        }
-    }
-
-    #[allow(dead_code)]
-    fn input(&mut self, _event: &WindowEvent) -> bool {
-        false
+    fn resize(&mut self, init: &InitWgpu, new_size: PhysicalSize<u32>) {
+        self.project_mat = transforms::create_projection(
+            new_size.width as f32 / new_size.height as f32,
+            IS_PERSPECTIVE,
+        );
+        let mvp_mat = self.project_mat * self.view_mat * self.model_mat;
+        let mvp_ref: &[f32; 16] = mvp_mat.as_ref();
+        init.queue
+            .write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(mvp_ref));
     }
 
-    fn update(&mut self) {}
+    fn update(&mut self, _init: &InitWgpu, _elapsed: Duration, _dt: Duration) {}
 
-    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.init.surface.get_current_texture()?;
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-
-        let mut encoder =
-            self.init
-                .device
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("Render Encoder"),
-                });
+    fn render(&mut self, init: &InitWgpu, frame: &mut Frame) {
+        let mut encoder = init
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.2,
-                            g: 0.247,
-                            b: 0.314,
-                            a: 1.0,
-                        }),
-                        store: true,
-                    },
-                })],
+                color_attachments: &[Some(init.color_attachment(
+                    frame,
+                    wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.2,
+                        g: 0.247,
+                        b: 0.314,
+                        a: 1.0,
+                    }),
+                ))],
                 depth_stencil_attachment: None,
             });
 
@@ -237,52 +208,10 @@ impl State {
             render_pass.draw(0..300, 0..1);
         }
 
-        self.init.queue.submit(iter::once(encoder.finish()));
-        output.present();
-
-        Ok(())
+        init.queue.submit(iter::once(encoder.finish()));
     }
 }
 
 fn main() {
-    env_logger::init();
-    let event_loop = EventLoop::new().unwrap();
-    let window = WindowBuilder::new().build(&event_loop).unwrap();
-    window.set_title(&*format!("{}", "ch06-3d-line"));
-    let mut state = pollster::block_on(State::new(&window));
-
-    event_loop.set_control_flow(ControlFlow::Wait);
-    let _ = event_loop.run(move |event, elwt| match event {
-        Event::WindowEvent {
-            event: WindowEvent::Resized(size),
-            ..
-        } => {
-            state.init.config.width = size.width;
-            state.init.config.height = size.height;
-            state
-                .init
-                .surface
-                .configure(&state.init.device, &state.init.config);
-        }
-        Event::WindowEvent {
-            event: WindowEvent::RedrawRequested,
-            ..
-        } => {
-            state.update();
-            match state.render() {
-                Ok(_) => {}
-                Err(wgpu::SurfaceError::Lost) => state.resize(state.init.size),
-                Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
-                Err(e) => eprintln!("{:?}", e),
-            }
-        }
-        Event::WindowEvent {
-            event: WindowEvent::CloseRequested,
-            ..
-        } => elwt.exit(),
-        Event::AboutToWait => {
-            window.request_redraw();
-        }
-        _ => {}
-    });
+    app::run::<State>("ch06-3d-line", ());
 }
@@ -1,17 +1,13 @@
 use bytemuck::{cast_slice, Pod, Zeroable};
 use cgmath::*;
-use std::{iter, mem};
+use std::{iter, mem, time::Duration};
 use wgpu::util::DeviceExt;
-use winit::{
-    event::*,
-    event_loop::{ControlFlow, EventLoop},
-    window::{Window, WindowBuilder},
-};
+use winit::dpi::PhysicalSize;
 
-#[path = "../common/transforms.rs"]
-mod transforms;
-#[path = "../common/vertex_data.rs"]
-mod vertex_data;
+use wgpu_book::app::{self, App};
+use wgpu_book::depth::{DepthConfig, DepthTarget};
+use wgpu_book::transforms::{self, Frame, InitWgpu};
+use wgpu_book::vertex_data;
 
 const IS_PERSPECTIVE: bool = true;
 const ANIMATION_SPEED: f32 = 1.0;
@@ -53,7 +49,6 @@ impl Vertex {
 }
 
 struct State {
-    init: transforms::InitWgpu,
     pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
     uniform_buffer: wgpu::Buffer,
@@ -61,11 +56,13 @@ struct State {
     model_mat: Matrix4<f32>,
     view_mat: Matrix4<f32>,
     project_mat: Matrix4<f32>,
+    depth: DepthTarget,
 }
 
-impl State {
-    async fn new(window: &Window) -> Self {
-        let init = transforms::InitWgpu::init_wgpu(window).await;
+impl App for State {
+    type Scene<'a> = ();
+
+    fn init(init: &InitWgpu, _scene: ()) -> Self {
         let shader = init
             .device
             .create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -79,7 +76,7 @@ impl State {
 
         let model_mat =
             transforms::create_transforms([0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [1.0, 1.0, 1.0]);
-        let (view_mat, project_mat, view_project_mat) = transforms::create_view_projection(
+        let (view_mat, project_mat, _) = transforms::create_view_projection(
             camera_position,
             look_direction,
             up_direction,
@@ -87,7 +84,16 @@ impl State {
             IS_PERSPECTIVE,
         );
 
-        let mvp_mat = view_project_mat * model_mat;
+        let depth = DepthTarget::new(
+            &init.device,
+            init.config.width,
+            init.config.height,
+            init.sample_count(),
+            DepthConfig::from_env(),
+        );
+        let project_mat = depth.config.projection(project_mat);
+
+        let mvp_mat = project_mat * view_mat * model_mat;
         let mvp_ref: &[f32; 16] = mvp_mat.as_ref();
         let uniform_buffer = init
             .device
@@ -159,14 +165,8 @@ impl State {
                     ..Default::default()
                 },
                 // depth_stencil: None,
-                depth_stencil: Some(wgpu::DepthStencilState {
-                    format: wgpu::TextureFormat::Depth24Plus,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::LessEqual,
-                    stencil: wgpu::StencilState::default(),
-                    bias: wgpu::DepthBiasState::default(),
-                }),
-                multisample: wgpu::MultisampleState::default(),
+                depth_stencil: Some(depth.config.depth_stencil_state()),
+                multisample: init.multisample_state(),
                 multiview: None,
             });
 
@@ -179,7 +179,6 @@ impl State {
             });
 
         Self {
-            init,
             pipeline,
             vertex_buffer,
             uniform_buffer,
@@ -187,102 +186,56 @@ impl State {
             model_mat,
             view_mat,
             project_mat,
+            depth,
         }
     }
 
-    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
-        if new_size.width > 0 && new_size.height > 0 {
-            self.init.size = new_size;
-            self.init.config.width = new_size.width;
-            self.init.config.height = new_size.height;
-            self.init
-                .surface
-                .configure(&self.init.device, &self.init.config);
-            self.project_mat = transforms::create_projection(
-                new_size.width as f32 / new_size.height as f32,
-                IS_PERSPECTIVE,
-            );
-            let mvp_mat = self.project_mat * self.view_mat * self.model_mat;
-            let mvp_ref: &[f32; 16] = mvp_mat.as_ref();
-            self.init
-                .queue
-                .write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(mvp_ref));
-        }
-    }
-
-    #[allow(dead_code)]
-    fn input(&mut self, _event: &WindowEvent) -> bool {
-        false
+    fn resize(&mut self, init: &InitWgpu, new_size: PhysicalSize<u32>) {
+        self.depth
+            .resize(&init.device, new_size.width, new_size.height);
+        self.project_mat = self.depth.config.projection(transforms::create_projection(
+            new_size.width as f32 / new_size.height as f32,
+            IS_PERSPECTIVE,
+        ));
+        let mvp_mat = self.project_mat * self.view_mat * self.model_mat;
+        let mvp_ref: &[f32; 16] = mvp_mat.as_ref();
+        init.queue
+            .write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(mvp_ref));
     }
 
-    fn update(&mut self, dt: std::time::Duration) {
-        let dt = ANIMATION_SPEED * dt.as_secs_f32();
+    fn update(&mut self, init: &InitWgpu, elapsed: Duration, _dt: Duration) {
+        let t = ANIMATION_SPEED * elapsed.as_secs_f32();
         let model_mat = transforms::create_transforms(
             [0.0, 0.0, 0.0],
-            [dt.sin(), dt.cos(), 0.0],
+            [t.sin(), t.cos(), 0.0],
             [1.0, 1.0, 1.0],
         );
         let mvp_mat = self.project_mat * self.view_mat * model_mat;
         let mvp_ref: &[f32; 16] = mvp_mat.as_ref();
-        self.init
-            .queue
+        init.queue
             .write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(mvp_ref));
     }
 
-    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.init.surface.get_current_texture()?;
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-
-        let depth_texture = self.init.device.create_texture(&wgpu::TextureDescriptor {
-            size: wgpu::Extent3d {
-                width: self.init.config.width,
-                height: self.init.config.height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Depth24Plus,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            label: None,
-            view_formats: &[],
-        });
-
-        let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-        let mut encoder =
-            self.init
-                .device
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("Render Encoder"),
-                });
+    fn render(&mut self, init: &InitWgpu, frame: &mut Frame) {
+        let mut encoder = init
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.2,
-                            g: 0.247,
-                            b: 0.314,
-                            a: 1.0,
-                        }),
-                        store: true,
-                    },
-                })],
-                // depth_stencil_attachment: None,
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: &depth_view,
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
-                        store: false,
+                color_attachments: &[Some(init.color_attachment(
+                    frame,
+                    wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.2,
+                        g: 0.247,
+                        b: 0.314,
+                        a: 1.0,
                     }),
-                    stencil_ops: None,
-                }),
+                ))],
+                // depth_stencil_attachment: None,
+                depth_stencil_attachment: Some(self.depth.attachment()),
             });
 
             render_pass.set_pipeline(&self.pipeline);
@@ -291,58 +244,10 @@ impl State {
             render_pass.draw(0..36, 0..1);
         }
 
-        self.init.queue.submit(iter::once(encoder.finish()));
-        output.present();
-
-        Ok(())
+        init.queue.submit(iter::once(encoder.finish()));
     }
 }
 
 fn main() {
-    env_logger::init();
-    let event_loop = EventLoop::new().unwrap();
-    let window = WindowBuilder::new().build(&event_loop).unwrap();
-    window.set_title(&*format!("{}", "ch06-rotate_cube"));
-    let mut state = pollster::block_on(State::new(&window));
-
-    let render_start_time = std::time::Instant::now();
-
-    event_loop.set_control_flow(ControlFlow::Wait);
-    let _ = event_loop.run(move |event, elwt| match event {
-        Event::WindowEvent {
-            event: WindowEvent::Resized(size),
-            ..
-        } => {
-            state.init.config.width = size.width;
-            state.init.config.height = size.height;
-            state
-                .init
-                .surface
-                .configure(&state.init.device, &state.init.config);
-        }
-        Event::WindowEvent {
-            event: WindowEvent::RedrawRequested,
-            ..
-        } => {
-            let now = std::time::Instant::now();
-            let dt = now - render_start_time;
-
-            state.update(dt);
-
-            match state.render() {
-                Ok(_) => {}
-                Err(wgpu::SurfaceError::Lost) => state.resize(state.init.size),
-                Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
-                Err(e) => eprintln!("{:?}", e),
-            }
-        }
-        Event::WindowEvent {
-            event: WindowEvent::CloseRequested,
-            ..
-        } => elwt.exit(),
-        Event::AboutToWait => {
-            window.request_redraw();
-        }
-        _ => {}
-    });
+    app::run::<State>("ch06-rotate_cube", ());
 }
@@ -1,15 +1,12 @@
 use bytemuck::{cast_slice, Pod, Zeroable};
 use cgmath::Matrix4;
-use std::{iter, mem};
+use std::{iter, mem, time::Duration};
 use wgpu::util::DeviceExt;
-use winit::{
-    event::{Event, WindowEvent},
-    event_loop::{ControlFlow, EventLoop},
-    window::{Window, WindowBuilder},
-};
+use winit::{dpi::PhysicalSize, event::WindowEvent};
 
-#[path = "../common/transforms.rs"]
-mod transforms;
+use wgpu_book::app::{self, App};
+use wgpu_book::orbit::{OrbitCamera, OrbitController};
+use wgpu_book::transforms::{self, Frame, InitWgpu};
 
 const ANIMATION_SPEED: f32 = 1.0;
 const IS_PERSPECTIVE: bool = true;
@@ -40,19 +37,25 @@ impl Vertex {
 }
 
 struct State {
-    pub init: transforms::InitWgpu,
     pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
     uniform_bind_group: wgpu::BindGroup,
     uniform_buffer: wgpu::Buffer,
-    view_mat: Matrix4<f32>,
+    camera: OrbitCamera,
+    camera_controller: OrbitController,
     project_mat: Matrix4<f32>,
     num_vertices: u32,
 }
 
-impl State {
-    async fn new(window: &Window, mesh_data: &Vec<Vertex>) -> Self {
-        let init = transforms::InitWgpu::init_wgpu(window).await;
+struct Scene<'a> {
+    mesh_data: &'a [Vertex],
+}
+
+impl App for State {
+    type Scene<'a> = Scene<'a>;
+
+    fn init(init: &InitWgpu, scene: Scene<'_>) -> Self {
+        let Scene { mesh_data } = scene;
         let shader = init
             .device
             .create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -64,10 +67,10 @@ impl State {
         let camera_position = (3.0, 1.5, 3.0).into();
         let look_direction = (0.0, 0.0, 0.0).into();
         let up_direction = cgmath::Vector3::unit_y();
-        
+
         let model_mat =
             transforms::create_transforms([0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [1.0, 1.0, 1.0]);
-        let (view_mat, project_mat, view_project_mat) = transforms::create_view_projection(
+        let (_, project_mat, view_project_mat) = transforms::create_view_projection(
             camera_position,
             look_direction,
             up_direction,
@@ -147,7 +150,7 @@ impl State {
                     ..Default::default()
                 },
                 depth_stencil: None,
-                multisample: wgpu::MultisampleState::default(),
+                multisample: init.multisample_state(),
                 multiview: None,
             });
 
@@ -161,82 +164,65 @@ impl State {
         let num_vertices = mesh_data.len() as u32;
 
         Self {
-            init,
             pipeline,
             vertex_buffer,
             uniform_bind_group,
             uniform_buffer,
-            view_mat,
+            camera: OrbitCamera::new(camera_position, look_direction, up_direction),
+            camera_controller: OrbitController::default(),
             project_mat,
             num_vertices,
         }
     }
 
-    fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
-        if new_size.width > 0 && new_size.height > 0 {
-            self.init.size = new_size;
-            self.init.config.width = new_size.width;
-            self.init.config.height = new_size.height;
-            self.init
-                .surface
-                .configure(&self.init.device, &self.init.config);
-            self.project_mat = transforms::create_projection(
-                new_size.width as f32 / new_size.height as f32,
-                IS_PERSPECTIVE,
-            );
-        }
+    fn resize(&mut self, _init: &InitWgpu, new_size: PhysicalSize<u32>) {
+        self.project_mat = transforms::create_projection(
+            new_size.width as f32 / new_size.height as f32,
+            IS_PERSPECTIVE,
+        );
     }
 
-    #[allow(dead_code)]
-    fn input(&mut self, _event: &WindowEvent) -> bool {
-        false
+    fn input(&mut self, init: &InitWgpu, event: &WindowEvent) -> bool {
+        self.camera_controller.process_events(event, init.size)
     }
 
-    fn update(&mut self, dt: std::time::Duration) {
+    fn update(&mut self, init: &InitWgpu, elapsed: Duration, dt: Duration) {
+        self.camera_controller.update_camera(&mut self.camera, dt);
+        let view_mat = self.camera.view_mat();
+
         // update uniform buffer
-        let dt = ANIMATION_SPEED * dt.as_secs_f32();
+        let t = ANIMATION_SPEED * elapsed.as_secs_f32();
         let model_mat = transforms::create_transforms(
             [0.0, 0.0, 0.0],
-            [dt.sin(), dt.cos(), 0.0],
+            [t.sin(), t.cos(), 0.0],
             [1.0, 1.0, 1.0],
         );
 
-        let mvp_mat = self.project_mat * self.view_mat * model_mat;
+        let mvp_mat = self.project_mat * view_mat * model_mat;
         let mvp_ref: &[f32; 16] = mvp_mat.as_ref();
 
-        self.init
-            .queue
+        init.queue
             .write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(mvp_ref));
     }
 
-    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.init.surface.get_current_texture()?;
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-
-        let mut encoder =
-            self.init
-                .device
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("Render Encoder"),
-                });
+    fn render(&mut self, init: &InitWgpu, frame: &mut Frame) {
+        let mut encoder = init
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.2,
-                            g: 0.247,
-                            b: 0.314,
-                            a: 1.0,
-                        }),
-                        store: true,
-                    },
-                })],
+                color_attachments: &[Some(init.color_attachment(
+                    frame,
+                    wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.2,
+                        g: 0.247,
+                        b: 0.314,
+                        a: 1.0,
+                    }),
+                ))],
                 depth_stencil_attachment: None,
             });
 
@@ -246,57 +232,10 @@ impl State {
             render_pass.draw(0..self.num_vertices, 0..1);
         }
 
-        self.init.queue.submit(iter::once(encoder.finish()));
-        output.present();
-        Ok(())
+        init.queue.submit(iter::once(encoder.finish()));
     }
 }
 
-pub fn run(mesh_data: &Vec<Vertex>, title: &str) {
-    env_logger::init();
-    let event_loop = EventLoop::new().unwrap();
-    let window = WindowBuilder::new().build(&event_loop).unwrap();
-    window.set_title(&*format!("ch07_{}", title));
-
-    let mut state = pollster::block_on(State::new(&window, &mesh_data));
-    let render_start_time = std::time::Instant::now();
-
-    event_loop.set_control_flow(ControlFlow::Wait);
-    let _ = event_loop.run(move |event, elwt| match event {
-        Event::WindowEvent {
-            event: WindowEvent::Resized(size),
-            ..
-        } => {
-            state.init.config.width = size.width;
-            state.init.config.height = size.height;
-            state
-                .init
-                .surface
-                .configure(&state.init.device, &state.init.config);
-        }
-        Event::WindowEvent {
-            event: WindowEvent::RedrawRequested,
-            ..
-        } => {
-            let now = std::time::Instant::now();
-            let dt = now - render_start_time;
-
-            state.update(dt);
-
-            match state.render() {
-                Ok(_) => {}
-                Err(wgpu::SurfaceError::Lost) => state.resize(state.init.size),
-                Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
-                Err(e) => eprintln!("{:?}", e),
-            }
-        }
-        Event::WindowEvent {
-            event: WindowEvent::CloseRequested,
-            ..
-        } => elwt.exit(),
-        Event::AboutToWait => {
-            window.request_redraw();
-        }
-        _ => {}
-    });
+pub fn run(mesh_data: &[Vertex], title: &str) {
+    app::run::<State>(&format!("ch07_{}", title), Scene { mesh_data });
 }
@@ -20,8 +20,8 @@ fn create_vertices() -> Vec<common::Vertex> {
         p[0], p[4], p[1], p[5], p[2], p[6], p[3], p[7],
     ];
     let mut data: Vec<common::Vertex> = Vec::with_capacity(lines.len());
-    for i in 0..lines.len() {
-        data.push(common::vertex(lines[i]));
+    for line in lines {
+        data.push(common::vertex(line));
     }
     data.to_vec()
 }
@@ -1,7 +1,6 @@
 use cgmath::*;
 mod common;
-#[path = "../common/math_func.rs"]
-mod math_func;
+use wgpu_book::math_func;
 
 fn create_vertices(rin: f32, rout: f32, height: f32, n: usize) -> Vec<common::Vertex> {
     let h = height / 2.0;
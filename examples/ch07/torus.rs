@@ -1,7 +1,6 @@
 use cgmath::*;
 mod common;
-#[path = "../common/math_func.rs"]
-mod math_func;
+use wgpu_book::math_func;
 
 fn create_vertices(
     r_torus: f32,
@@ -9,8 +8,7 @@ fn create_vertices(
     n_torus: usize,
     n_tube: usize,
 ) -> Vec<common::Vertex> {
-    let mut pts: Vec<common::Vertex> =
-        Vec::with_capacity((4 * (n_torus - 1) * (n_tube - 1)) as usize);
+    let mut pts: Vec<common::Vertex> = Vec::with_capacity(4 * (n_torus - 1) * (n_tube - 1));
     for i in 0..n_torus - 1 {
         for j in 0..n_tube - 1 {
             let u = i as f32 * 360.0 / (n_torus as f32 - 1.0);
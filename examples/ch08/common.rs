@@ -1,22 +1,31 @@
 use bytemuck::{cast_slice, Pod, Zeroable};
 use cgmath::{Matrix, Matrix4, SquareMatrix};
-use std::{iter, mem};
+use std::{iter, mem, time::Duration};
 use wgpu::util::DeviceExt;
-use winit::{
-    event::{Event, WindowEvent},
-    event_loop::{ControlFlow, EventLoop},
-    window::{Window, WindowBuilder},
-};
+use winit::{dpi::PhysicalSize, event::WindowEvent};
 
-#[path = "../common/transforms.rs"]
-mod transforms;
+use wgpu_book::app::{self, App};
+use wgpu_book::depth::{DepthConfig, DepthTarget};
+use wgpu_book::export;
+use wgpu_book::ground::GroundPlane;
+use wgpu_book::light::{self, Light, LightsUniform};
+use wgpu_book::mesh::Mesh;
+use wgpu_book::orbit::{OrbitCamera, OrbitController};
+use wgpu_book::shadow::ShadowMap;
+use wgpu_book::transforms::{self, Frame, InitWgpu};
 
 const ANIMATION_SPEED: f32 = 1.0;
 const IS_PERSPECTIVE: bool = true;
+const CAMERA_POSITION: [f32; 3] = [3.0, 1.5, 3.0];
+// the ground plane receiving the shadows and the extent of the shadow casters
+const GROUND_Y: f32 = -2.5;
+const GROUND_HALF_SIZE: f32 = 8.0;
+const SCENE_RADIUS: f32 = 2.5;
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
-pub struct Light {
+/// Blinn-Phong material parameters.
+pub struct Material {
     color: [f32; 4],
     specular_color: [f32; 4],
     ambient_intensity: f32,
@@ -25,8 +34,8 @@ pub struct Light {
     specular_shininess: f32,
 }
 
-pub fn light(c: [f32; 3], sc: [f32; 3], ai: f32, di: f32, si: f32, ss: f32) -> Light {
-    Light {
+pub fn material(c: [f32; 3], sc: [f32; 3], ai: f32, di: f32, si: f32, ss: f32) -> Material {
+    Material {
         color: [c[0], c[1], c[2], 1.0],
         specular_color: [sc[0], sc[1], sc[2], 1.0],
         ambient_intensity: ai,
@@ -36,6 +45,16 @@ pub fn light(c: [f32; 3], sc: [f32; 3], ai: f32, di: f32, si: f32, ss: f32) -> L
     }
 }
 
+/// A white point light at the camera and a directional light from above
+/// that casts the shadows on the ground.
+#[allow(dead_code)]
+pub fn default_lights() -> Vec<Light> {
+    vec![
+        Light::directional([0.5, -1.0, -0.3], [1.0, 1.0, 1.0]).with_intensity(0.5),
+        Light::point(CAMERA_POSITION, [1.0, 1.0, 1.0]).with_intensity(0.7),
+    ]
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
 pub struct Vertex {
@@ -64,37 +83,66 @@ impl Vertex {
 }
 
 struct State {
-    pub init: transforms::InitWgpu,
     pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
     uniform_bind_group: wgpu::BindGroup,
     vertex_uniform_buffer: wgpu::Buffer,
-    view_mat: Matrix4<f32>,
+    shadow_map: ShadowMap,
+    ground: GroundPlane,
+    camera: OrbitCamera,
+    camera_controller: OrbitController,
+    fragment_uniform_buffer: wgpu::Buffer,
     project_mat: Matrix4<f32>,
+    depth: DepthTarget,
     num_vertices: u32,
 }
 
-impl State {
-    async fn new(window: &Window, vertex_data: &Vec<Vertex>, light_data: Light) -> Self {
-        let init = transforms::InitWgpu::init_wgpu(window).await;
+struct Scene<'a> {
+    vertex_data: &'a [Vertex],
+    material: Material,
+    lights: &'a [Light],
+}
+
+impl App for State {
+    type Scene<'a> = Scene<'a>;
+
+    fn init(init: &InitWgpu, scene: Scene<'_>) -> Self {
+        let Scene {
+            vertex_data,
+            material,
+            lights,
+        } = scene;
         let shader = init
             .device
             .create_shader_module(wgpu::ShaderModuleDescriptor {
                 label: Some("Shader"),
-                source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
+                // the light array and its evaluation are shared with the other lit chapters
+                source: wgpu::ShaderSource::Wgsl(
+                    [light::LIGHT_WGSL, include_str!("shader.wgsl")]
+                        .join("\n")
+                        .into(),
+                ),
             });
 
         // uniform data
-        let camera_position = (3.0, 1.5, 3.0).into();
+        let camera_position = CAMERA_POSITION.into();
         let look_direction = (0.0, 0.0, 0.0).into();
         let up_direction = cgmath::Vector3::unit_y();
-        let (view_mat, project_mat, _view_project_mat) = transforms::create_view_projection(
+        let (_, project_mat, _) = transforms::create_view_projection(
             camera_position,
             look_direction,
             up_direction,
             init.config.width as f32 / init.config.height as f32,
             IS_PERSPECTIVE,
         );
+        let depth = DepthTarget::new(
+            &init.device,
+            init.config.width,
+            init.config.height,
+            init.sample_count(),
+            DepthConfig::from_env(),
+        );
+        let project_mat = depth.config.projection(project_mat);
 
         // create vertex uniform buffer
         // model_mat and view_projection_mat will be stored in
@@ -106,44 +154,68 @@ impl State {
             mapped_at_creation: false,
         });
 
-        // create fragment uniform buffer. here we set eye_position = camera_position and
-        // light_position = eye_position
+        // create fragment uniform buffer. here we set eye_position = camera_position
         let fragment_uniform_buffer = init.device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Fragment Uniform Buffer"),
-            size: 32,
+            size: 16,
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
 
-        // store light and eye positions
-        let light_position: &[f32; 3] = camera_position.as_ref();
+        // store eye position
         let eye_position: &[f32; 3] = camera_position.as_ref();
         init.queue.write_buffer(
             &fragment_uniform_buffer,
             0,
-            bytemuck::cast_slice(light_position),
-        );
-        init.queue.write_buffer(
-            &fragment_uniform_buffer,
-            16,
             bytemuck::cast_slice(eye_position),
         );
 
-        // create light uniform buffer
-        let light_uniform_buffer = init.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Light Uniform Buffer"),
+        // create material uniform buffer
+        let material_uniform_buffer = init.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Material Uniform Buffer"),
             size: 48,
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
 
-        // store light parameters
+        // store material parameters
         init.queue.write_buffer(
-            &light_uniform_buffer,
+            &material_uniform_buffer,
             0,
-            bytemuck::cast_slice(&[light_data]),
+            bytemuck::cast_slice(&[material]),
+        );
+
+        // create light uniform buffer holding the light array
+        let light_uniform_buffer =
+            init.device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Light Uniform Buffer"),
+                    contents: bytemuck::cast_slice(&[LightsUniform::new(lights)]),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+
+        // depth of the scene seen from the shadow-casting light, sampled at bindings 4 to 6
+        let shadow_map = ShadowMap::new(
+            &init.device,
+            Vertex::desc(),
+            Some(wgpu::Face::Back),
+            lights,
+            (0.0, 0.0, 0.0).into(),
+            SCENE_RADIUS,
+        );
+        let ground = GroundPlane::new(
+            &init.device,
+            init.config.format,
+            depth.config,
+            init.sample_count(),
+            GROUND_Y,
+            GROUND_HALF_SIZE,
+            CAMERA_POSITION,
+            &light_uniform_buffer,
+            &shadow_map,
         );
 
+        let [shadow_uniform, shadow_texture, shadow_sampler] = ShadowMap::layout_entries();
         let uniform_bind_group_layout =
             init.device
                 .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -178,10 +250,24 @@ impl State {
                             },
                             count: None,
                         },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 3,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Buffer {
+                                ty: wgpu::BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size: None,
+                            },
+                            count: None,
+                        },
+                        shadow_uniform,
+                        shadow_texture,
+                        shadow_sampler,
                     ],
                     label: Some("Uniform Bind Group Layout"),
                 });
 
+        let [shadow_uniform, shadow_texture, shadow_sampler] = shadow_map.bind_group_entries();
         let uniform_bind_group = init.device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: &uniform_bind_group_layout,
             entries: &[
@@ -195,8 +281,15 @@ impl State {
                 },
                 wgpu::BindGroupEntry {
                     binding: 2,
+                    resource: material_uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
                     resource: light_uniform_buffer.as_entire_binding(),
                 },
+                shadow_uniform,
+                shadow_texture,
+                shadow_sampler,
             ],
             label: Some("Uniform Bind Group"),
         });
@@ -238,14 +331,8 @@ impl State {
                     ..Default::default()
                 },
                 //depth_stencil: None,
-                depth_stencil: Some(wgpu::DepthStencilState {
-                    format: wgpu::TextureFormat::Depth24Plus,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::LessEqual,
-                    stencil: wgpu::StencilState::default(),
-                    bias: wgpu::DepthBiasState::default(),
-                }),
-                multisample: wgpu::MultisampleState::default(),
+                depth_stencil: Some(depth.config.depth_stencil_state()),
+                multisample: init.multisample_state(),
                 multiview: None,
             });
 
@@ -260,175 +347,137 @@ impl State {
         let num_vertices = vertex_data.len() as u32;
 
         Self {
-            init,
             pipeline,
             vertex_buffer,
             uniform_bind_group,
             vertex_uniform_buffer,
-            view_mat,
+            shadow_map,
+            ground,
+            camera: OrbitCamera::new(camera_position, look_direction, up_direction),
+            camera_controller: OrbitController::default(),
+            fragment_uniform_buffer,
             project_mat,
+            depth,
             num_vertices,
         }
     }
 
-    fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
-        if new_size.width > 0 && new_size.height > 0 {
-            self.init.size = new_size;
-            self.init.config.width = new_size.width;
-            self.init.config.height = new_size.height;
-            self.init
-                .surface
-                .configure(&self.init.device, &self.init.config);
-            self.project_mat = transforms::create_projection(
-                new_size.width as f32 / new_size.height as f32,
-                IS_PERSPECTIVE,
-            );
-        }
+    fn resize(&mut self, init: &InitWgpu, new_size: PhysicalSize<u32>) {
+        self.depth
+            .resize(&init.device, new_size.width, new_size.height);
+        self.project_mat = self.depth.config.projection(transforms::create_projection(
+            new_size.width as f32 / new_size.height as f32,
+            IS_PERSPECTIVE,
+        ));
     }
 
-    #[allow(dead_code)]
-    fn input(&mut self, _event: &WindowEvent) -> bool {
-        false
+    fn input(&mut self, init: &InitWgpu, event: &WindowEvent) -> bool {
+        self.camera_controller.process_events(event, init.size)
     }
 
-    fn update(&mut self, dt: std::time::Duration) {
+    fn update(&mut self, init: &InitWgpu, elapsed: Duration, dt: Duration) {
+        self.camera_controller.update_camera(&mut self.camera, dt);
+        let view_mat = self.camera.view_mat();
+        let eye_position: [f32; 3] = self.camera.eye_position().into();
+        init.queue.write_buffer(
+            &self.fragment_uniform_buffer,
+            0,
+            bytemuck::cast_slice(&eye_position),
+        );
+        self.ground.set_eye_position(&init.queue, eye_position);
+
         // update uniform buffer
-        let dt = ANIMATION_SPEED * dt.as_secs_f32();
+        let t = ANIMATION_SPEED * elapsed.as_secs_f32();
         let model_mat = transforms::create_transforms(
             [0.0, 0.0, 0.0],
-            [dt.sin(), dt.cos(), 0.0],
+            [t.sin(), t.cos(), 0.0],
             [1.0, 1.0, 1.0],
         );
-        let view_project_mat = self.project_mat * self.view_mat;
+        let view_project_mat = self.project_mat * view_mat;
         let normal_mat = (model_mat.invert().unwrap()).transpose();
         let model_ref: &[f32; 16] = model_mat.as_ref();
         let view_projection_ref: &[f32; 16] = view_project_mat.as_ref();
         let normal_ref: &[f32; 16] = normal_mat.as_ref();
-        self.init.queue.write_buffer(
+        init.queue.write_buffer(
             &self.vertex_uniform_buffer,
             0,
             bytemuck::cast_slice(model_ref),
         );
-        self.init.queue.write_buffer(
+        init.queue.write_buffer(
             &self.vertex_uniform_buffer,
             64,
             bytemuck::cast_slice(view_projection_ref),
         );
-        self.init.queue.write_buffer(
+        init.queue.write_buffer(
             &self.vertex_uniform_buffer,
             128,
             bytemuck::cast_slice(normal_ref),
         );
+        self.shadow_map.update(&init.queue, model_mat);
+        self.ground.update(&init.queue, view_project_mat);
     }
 
-    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.init.surface.get_current_texture()?;
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-        let depth_texture = self.init.device.create_texture(&wgpu::TextureDescriptor {
-            size: wgpu::Extent3d {
-                width: self.init.config.width,
-                height: self.init.config.height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Depth24Plus,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            label: None,
-            view_formats: &[],
-        });
-        let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let mut encoder =
-            self.init
-                .device
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("Render Encoder"),
-                });
+    fn render(&mut self, init: &InitWgpu, frame: &mut Frame) {
+        let mut encoder = init
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
+        {
+            let mut shadow_pass = self.shadow_map.begin_pass(&mut encoder);
+            shadow_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            shadow_pass.draw(0..self.num_vertices, 0..1);
+        }
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.2,
-                            g: 0.247,
-                            b: 0.314,
-                            a: 1.0,
-                        }),
-                        store: true,
-                    },
-                })],
-                //depth_stencil_attachment: None,
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: &depth_view,
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
-                        store: false,
+                color_attachments: &[Some(init.color_attachment(
+                    frame,
+                    wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.2,
+                        g: 0.247,
+                        b: 0.314,
+                        a: 1.0,
                     }),
-                    stencil_ops: None,
-                }),
+                ))],
+                //depth_stencil_attachment: None,
+                depth_stencil_attachment: Some(self.depth.attachment()),
             });
             render_pass.set_pipeline(&self.pipeline);
             render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
             render_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
             render_pass.draw(0..self.num_vertices, 0..1);
+            self.ground.draw(&mut render_pass);
         }
-        self.init.queue.submit(iter::once(encoder.finish()));
-        output.present();
-        Ok(())
+        init.queue.submit(iter::once(encoder.finish()));
     }
 }
 
-pub fn run(vertex_data: &Vec<Vertex>, light_data: Light, title: &str) {
-    env_logger::init();
-    let event_loop = EventLoop::new().unwrap();
-    let window = WindowBuilder::new().build(&event_loop).unwrap();
-    window.set_title(&*format!("ch08_{}", title));
-
-    let mut state = pollster::block_on(State::new(&window, &vertex_data, light_data));
-    let render_start_time = std::time::Instant::now();
-
-    event_loop.set_control_flow(ControlFlow::Wait);
-    let _ = event_loop.run(move |event, elwt| match event {
-        Event::WindowEvent {
-            event: WindowEvent::Resized(size),
-            ..
-        } => {
-            state.init.config.width = size.width;
-            state.init.config.height = size.height;
-            state
-                .init
-                .surface
-                .configure(&state.init.device, &state.init.config);
-        }
-        Event::WindowEvent {
-            event: WindowEvent::RedrawRequested,
-            ..
-        } => {
-            let now = std::time::Instant::now();
-            let dt = now - render_start_time;
-
-            state.update(dt);
-
-            match state.render() {
-                Ok(_) => {}
-                Err(wgpu::SurfaceError::Lost) => state.resize(state.init.size),
-                Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
-                Err(e) => eprintln!("{:?}", e),
-            }
-        }
-        Event::WindowEvent {
-            event: WindowEvent::CloseRequested,
-            ..
-        } => elwt.exit(),
-        Event::AboutToWait => {
-            window.request_redraw();
-        }
-        _ => {}
-    });
+/// With `WGPU_BOOK_EXPORT` set, saves the triangles to that file and ends the
+/// program instead of showing them.
+fn export_from_env(vertex_data: &[Vertex]) {
+    let positions: Vec<[f32; 3]> = vertex_data
+        .iter()
+        .map(|v| [v.position[0], v.position[1], v.position[2]])
+        .collect();
+    let normals: Vec<[f32; 3]> = vertex_data
+        .iter()
+        .map(|v| [v.normal[0], v.normal[1], v.normal[2]])
+        .collect();
+    let mesh = Mesh::from_triangles(&positions, &normals, &[]);
+    if export::save_from_env(&mesh).unwrap() {
+        std::process::exit(0);
+    }
+}
+
+pub fn run(vertex_data: &[Vertex], material: Material, lights: &[Light], title: &str) {
+    export_from_env(vertex_data);
+    app::run::<State>(
+        &format!("ch08_{}", title),
+        Scene {
+            vertex_data,
+            material,
+            lights,
+        },
+    );
 }
@@ -1,6 +1,5 @@
 mod common;
-#[path = "../common/vertex_data.rs"]
-mod vertex_data;
+use wgpu_book::vertex_data;
 
 fn vertex(p: [i8; 3], n: [i8; 3]) -> common::Vertex {
     common::Vertex {
@@ -20,7 +19,7 @@ fn create_vertices() -> Vec<common::Vertex> {
 
 fn main() {
     let vertex_data = create_vertices();
-    let light_data = common::light(
+    let material = common::material(
         [1.0, 0.0, 0.0], // Color
         [1.0, 1.0, 0.0], // Specular Color
         0.1,             // Ambient Intensity
@@ -28,5 +27,5 @@ fn main() {
         0.3,             // Specular Intensity
         30.0,            // Specular Shininess
     );
-    common::run(&vertex_data, light_data, "cube");
+    common::run(&vertex_data, material, &common::default_lights(), "cube");
 }
@@ -0,0 +1,41 @@
+use cgmath::Deg;
+mod common;
+use wgpu_book::light::Light;
+use wgpu_book::vertex_data;
+
+fn create_vertices() -> Vec<common::Vertex> {
+    let (pos, normal, _uv) = vertex_data::torus_data(1.5, 0.4, 40, 15);
+    let mut data: Vec<common::Vertex> = Vec::with_capacity(pos.len());
+    for i in 0..pos.len() {
+        data.push(common::vertex(pos[i], normal[i]));
+    }
+    data.to_vec()
+}
+
+fn main() {
+    let vertex_data = create_vertices();
+    let material = common::material(
+        [1.0, 1.0, 1.0], // Color
+        [1.0, 1.0, 1.0], // Specular Color
+        0.05,            // Ambient Intensity
+        0.8,             // Diffuse Intensity
+        0.4,             // Specular Intensity
+        30.0,            // Specular Shininess
+    );
+    let lights = [
+        // dim white light from above
+        Light::directional([-0.3, -1.0, -0.2], [1.0, 1.0, 1.0]).with_intensity(0.2),
+        // red point light to the right, fading with distance
+        Light::point([3.0, 0.5, -1.0], [1.0, 0.2, 0.1]).with_attenuation(1.0, 0.1, 0.05),
+        // blue spot light shining at the torus from the front left
+        Light::spot(
+            [-1.0, 1.5, 3.0],
+            [0.3, -0.4, -1.0],
+            Deg(8.0),
+            Deg(12.0),
+            [0.2, 0.4, 1.0],
+        )
+        .with_intensity(2.0),
+    ];
+    common::run(&vertex_data, material, &lights, "lights");
+}
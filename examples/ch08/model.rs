@@ -0,0 +1,44 @@
+mod common;
+use wgpu_book::obj::ObjModel;
+
+fn main() {
+    let mut file_name = "examples/ch10/assets/house.obj";
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 {
+        file_name = &args[1];
+    }
+
+    let mut model = ObjModel::load(file_name).unwrap();
+    model.mesh.fit(2.0);
+    let vertex_data: Vec<common::Vertex> = model
+        .mesh
+        .indices
+        .iter()
+        .map(|&i| {
+            let v = &model.mesh.vertices[i as usize];
+            common::vertex(v.position, v.normal)
+        })
+        .collect();
+
+    // the pipeline has a single material, so the one on most faces is used
+    let material = match model.main_material() {
+        Some(m) => {
+            let specular = m.specular.iter().copied().fold(0.0, f32::max);
+            let specular_color = if specular > 0.0 {
+                m.specular.map(|c| c / specular)
+            } else {
+                [1.0, 1.0, 1.0]
+            };
+            common::material(
+                m.diffuse,
+                specular_color,
+                0.1,
+                0.8,
+                specular.min(1.0),
+                m.shininess.max(1.0),
+            )
+        }
+        None => common::material([0.8, 0.8, 0.8], [1.0, 1.0, 1.0], 0.1, 0.8, 0.3, 30.0),
+    };
+    common::run(&vertex_data, material, &common::default_lights(), "model");
+}
@@ -23,12 +23,11 @@ fn vs_main(@location(0) pos: vec4<f32>, @location(1) normal: vec4<f32>) -> Outpu
 }
 
 struct FragUniforms {
-    light_position: vec4<f32>,
     eye_position: vec4<f32>,
 };
 @group(0) @binding(1) var<uniform> frag_uniforms: FragUniforms;
 
-struct LightUniforms {
+struct MaterialUniforms {
     color: vec4<f32>,
     specular_color: vec4<f32>,
     ambient_intensity: f32,
@@ -36,16 +35,13 @@ struct LightUniforms {
     specular_intensity: f32,
     specular_shininess: f32,
 };
-@group(0) @binding(2) var<uniform> light_uniforms: LightUniforms;
+@group(0) @binding(2) var<uniform> material: MaterialUniforms;
 
 @fragment
 fn fs_main(@location(0) v_position: vec4<f32>, @location(1) v_normal: vec4<f32>) -> @location(0) vec4<f32> {
     let N: vec3<f32> = normalize(v_normal.xyz);
-    let L: vec3<f32> = normalize(frag_uniforms.light_position.xyz - v_position.xyz);
     let V: vec3<f32> = normalize(frag_uniforms.eye_position.xyz - v_position.xyz);
-    let H: vec3<f32> = normalize(L + V);
-    let diffuse: f32 = light_uniforms.diffuse_intensity * max(dot(N, L), 0.0);
-    let specular: f32 = light_uniforms.specular_intensity * pow(max(dot(N, H), 0.0), light_uniforms.specular_shininess);
-    let ambient: f32 = light_uniforms.ambient_intensity;
-    return vec4(light_uniforms.color.xyz * (ambient + diffuse) + light_uniforms.specular_color.xyz * specular, 0.0);
-}
\ No newline at end of file
+    let terms = blinn_phong_lights(v_position.xyz, N, V, material.diffuse_intensity, material.specular_intensity, material.specular_shininess, false);
+    let ambient: f32 = material.ambient_intensity;
+    return vec4(material.color.xyz * (ambient + terms.diffuse) + material.specular_color.xyz * terms.specular, 0.0);
+}
@@ -0,0 +1,24 @@
+mod common;
+use wgpu_book::vertex_data;
+
+fn create_vertices() -> Vec<common::Vertex> {
+    let (pos, normal, _uv) = vertex_data::sphere_data(1.5, 15, 20);
+    let mut data: Vec<common::Vertex> = Vec::with_capacity(pos.len());
+    for i in 0..pos.len() {
+        data.push(common::vertex(pos[i], normal[i]));
+    }
+    data.to_vec()
+}
+
+fn main() {
+    let vertex_data = create_vertices();
+    let material = common::material(
+        [1.0, 0.0, 0.0], // Color
+        [1.0, 1.0, 0.0], // Specular Color
+        0.1,             // Ambient Intensity
+        0.6,             // Diffuse Intensity
+        0.3,             // Specular Intensity
+        30.0,            // Specular Shininess
+    );
+    common::run(&vertex_data, material, &common::default_lights(), "sphere");
+}
@@ -1,24 +1,40 @@
 use bytemuck::{cast_slice, Pod, Zeroable};
-use cgmath::{Matrix, Matrix4, SquareMatrix};
-use std::{iter, mem};
+use cgmath::{InnerSpace, Matrix4, SquareMatrix, Vector4};
+use std::{iter, mem, time::Duration};
 use wgpu::util::DeviceExt;
 use winit::{
-    event::{Event, WindowEvent},
-    event_loop::{ControlFlow, EventLoop},
-    window::{Window, WindowBuilder},
+    dpi::PhysicalSize,
+    event::{ElementState, KeyEvent, WindowEvent},
+    keyboard::{Key, NamedKey},
 };
 
-#[path = "../common/surface_data.rs"]
-mod surface;
-#[path = "../common/transforms.rs"]
-mod transforms;
+use wgpu_book::app::{self, App};
+use wgpu_book::colormap::{self, Colormap};
+use wgpu_book::depth::{DepthConfig, DepthTarget};
+use wgpu_book::export;
+use wgpu_book::ground::GroundPlane;
+use wgpu_book::light::{self, Light, LightsUniform};
+use wgpu_book::mesh::{Mesh, MeshVertex, Shading};
+use wgpu_book::orbit::{OrbitCamera, OrbitController};
+use wgpu_book::overlay::{Overlay, OverlayBuilder};
+use wgpu_book::scene::{DrawItem, SceneGraph, SceneNode, Transform};
+use wgpu_book::shadow::ShadowMap;
+use wgpu_book::surface_data::{self, PlotBox};
+use wgpu_book::texture_data;
+use wgpu_book::transforms::{self, Frame, InitWgpu};
 
 const ANIMATION_SPEED: f32 = 1.0;
 const IS_PERSPECTIVE: bool = true;
+const CAMERA_POSITION: [f32; 3] = [3.5, 1.75, 3.5];
+// the ground plane receiving the shadows and the extent of the shadow casters
+const GROUND_Y: f32 = -3.0;
+const GROUND_HALF_SIZE: f32 = 8.0;
+const SCENE_RADIUS: f32 = 3.0;
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
-pub struct Light {
+/// Blinn-Phong material parameters.
+pub struct Material {
     specular_color: [f32; 4],
     ambient_intensity: f32,
     diffuse_intensity: f32,
@@ -27,8 +43,8 @@ pub struct Light {
     is_two_side: i32,
 }
 
-pub fn light(sc: [f32; 3], ai: f32, di: f32, si: f32, ss: f32, two_side: i32) -> Light {
-    Light {
+pub fn material(sc: [f32; 3], ai: f32, di: f32, si: f32, ss: f32, two_side: i32) -> Material {
+    Material {
         specular_color: [sc[0], sc[1], sc[2], 1.0],
         ambient_intensity: ai,
         diffuse_intensity: di,
@@ -38,26 +54,37 @@ pub fn light(sc: [f32; 3], ai: f32, di: f32, si: f32, ss: f32, two_side: i32) ->
     }
 }
 
+/// A white point light at the camera and a directional light from above
+/// that casts the shadows on the ground.
+#[allow(dead_code)]
+pub fn default_lights() -> Vec<Light> {
+    vec![
+        Light::directional([0.5, -1.0, -0.3], [1.0, 1.0, 1.0]).with_intensity(0.5),
+        Light::point(CAMERA_POSITION, [1.0, 1.0, 1.0]).with_intensity(0.7),
+    ]
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
 pub struct Vertex {
     pub position: [f32; 4],
     pub normal: [f32; 4],
-    pub color: [f32; 4],
+    pub value: f32,
 }
 
 #[allow(dead_code)]
-pub fn vertex(p: [f32; 3], n: [f32; 3], c: [f32; 3]) -> Vertex {
+pub fn vertex(p: [f32; 3], n: [f32; 3], value: f32) -> Vertex {
     Vertex {
         position: [p[0], p[1], p[2], 1.0],
         normal: [n[0], n[1], n[2], 1.0],
-        color: [c[0], c[1], c[2], 1.0],
+        value,
     }
 }
 
-pub fn create_vertices(
+#[allow(clippy::too_many_arguments, dead_code)]
+pub fn create_mesh(
     f: &dyn Fn(f32, f32) -> [f32; 3],
-    colormap_name: &str,
+    colormap: &Colormap,
     xmin: f32,
     xmax: f32,
     zmin: f32,
@@ -66,30 +93,27 @@ pub fn create_vertices(
     nz: usize,
     scale: f32,
     scaley: f32,
-) -> Vec<Vertex> {
-    let (pos, normal, color, _uv, _uv1) = surface::simple_surface_data(
-        f,
-        colormap_name,
-        xmin,
-        xmax,
-        zmin,
-        zmax,
-        nx,
-        nz,
-        scale,
-        scaley,
+    shading: Shading,
+) -> (Mesh, PlotBox) {
+    let mesh = surface_data::surface_mesh(
+        f, colormap, xmin, xmax, zmin, zmax, nx, nz, scale, scaley, shading,
     );
+    let plot_box = surface_data::surface_plot_box(f, xmin, xmax, zmin, zmax, nx, nz, scale, scaley);
+    (mesh, plot_box)
+}
 
-    let mut data: Vec<Vertex> = Vec::with_capacity(pos.len());
-    for i in 0..pos.len() {
-        data.push(vertex(pos[i], normal[i], color[i]));
+/// The vertex and index data of `mesh`.
+fn mesh_vertices(mesh: &Mesh) -> (Vec<Vertex>, Vec<u32>) {
+    let mut data: Vec<Vertex> = Vec::with_capacity(mesh.vertices.len());
+    for v in mesh.vertices.iter() {
+        data.push(vertex(v.position, v.normal, v.scalar));
     }
-    data.to_vec()
+    (data, mesh.indices.clone())
 }
 
 impl Vertex {
     const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
-        wgpu::vertex_attr_array![0=>Float32x4, 1=>Float32x4, 2=>Float32x4];
+        wgpu::vertex_attr_array![0=>Float32x4, 1=>Float32x4, 2=>Float32];
 
     fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
         wgpu::VertexBufferLayout {
@@ -100,89 +124,233 @@ impl Vertex {
     }
 }
 
+// the matrices of a node in the vertex uniform buffer
+const NODE_UNIFORM_SIZE: u64 = 192;
+const MATERIAL_UNIFORM_SIZE: u64 = 48;
+
+struct MeshBuffers {
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    indices_len: u32,
+}
+
 struct State {
-    pub init: transforms::InitWgpu,
     pipeline: wgpu::RenderPipeline,
-    vertex_buffer: wgpu::Buffer,
+    meshes: Vec<MeshBuffers>,
+    graph: SceneGraph,
+    animate: fn(&mut SceneGraph, f32),
+    draw_list: Vec<DrawItem>,
     uniform_bind_group: wgpu::BindGroup,
+    // one slot per drawn node and per material, selected with dynamic offsets
     vertex_uniform_buffer: wgpu::Buffer,
-    view_mat: Matrix4<f32>,
+    node_stride: u64,
+    material_stride: u64,
+    shadow_map: ShadowMap,
+    ground: GroundPlane,
+    colormap_texture: texture_data::Texture,
+    colormap_bind_group: wgpu::BindGroup,
+    colormap_name: String,
+    title: String,
+    overlay: Overlay,
+    plot_box: Option<PlotBox>,
+    show_overlay: bool,
+    camera: OrbitCamera,
+    camera_controller: OrbitController,
+    fragment_uniform_buffer: wgpu::Buffer,
     project_mat: Matrix4<f32>,
-    num_vertices: u32,
+    depth: DepthTarget,
 }
 
-impl State {
-    async fn new(window: &Window, vertex_data: &Vec<Vertex>, light_data: Light) -> Self {
-        let init = transforms::InitWgpu::init_wgpu(window).await;
+struct Scene<'a> {
+    meshes: &'a [Mesh],
+    graph: SceneGraph,
+    materials: &'a [Material],
+    animate: fn(&mut SceneGraph, f32),
+    lights: &'a [Light],
+    colormap: &'a Colormap,
+    colormap_name: &'a str,
+    plot_box: Option<PlotBox>,
+    title: &'a str,
+}
+
+impl App for State {
+    type Scene<'a> = Scene<'a>;
+
+    fn init(init: &InitWgpu, scene: Scene<'_>) -> Self {
+        let Scene {
+            meshes,
+            mut graph,
+            materials,
+            animate,
+            lights,
+            colormap,
+            colormap_name,
+            plot_box,
+            title,
+        } = scene;
+        // create colormap texture; the shader looks the vertex colors up in it
+        let colormap_texture =
+            texture_data::Texture::create_colormap_texture(&init.device, &init.queue, colormap);
+
+        // colorbar and axes drawn over the surface; they share the colormap texture
+        let overlay = Overlay::new(
+            &init.device,
+            init.config.format,
+            init.sample_count(),
+            &colormap_texture,
+        );
+
+        let colormap_bind_group_layout =
+            init.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    entries: &[
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Texture {
+                                multisampled: false,
+                                view_dimension: wgpu::TextureViewDimension::D2,
+                                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                            count: None,
+                        },
+                    ],
+                    label: Some("Colormap Bind Group Layout"),
+                });
+
+        let colormap_bind_group = init.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &colormap_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&colormap_texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&colormap_texture.sampler),
+                },
+            ],
+            label: Some("Colormap Bind Group"),
+        });
 
         let shader = init
             .device
             .create_shader_module(wgpu::ShaderModuleDescriptor {
                 label: Some("Shader"),
-                source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
+                // the light array and its evaluation are shared with the other lit chapters
+                source: wgpu::ShaderSource::Wgsl(
+                    [light::LIGHT_WGSL, include_str!("shader.wgsl")]
+                        .join("\n")
+                        .into(),
+                ),
             });
 
         // uniform data
-        let camera_position = (3.5, 1.75, 3.5).into();
+        let camera_position = CAMERA_POSITION.into();
         let look_direction = (0.0, 0.0, 0.0).into();
         let up_direction = cgmath::Vector3::unit_y();
 
-        let (view_mat, project_mat, _view_project_mat) = transforms::create_view_projection(
+        let (_, project_mat, _) = transforms::create_view_projection(
             camera_position,
             look_direction,
             up_direction,
             init.config.width as f32 / init.config.height as f32,
             IS_PERSPECTIVE,
         );
+        let depth = DepthTarget::new(
+            &init.device,
+            init.config.width,
+            init.config.height,
+            init.sample_count(),
+            DepthConfig::from_env(),
+        );
+        let project_mat = depth.config.projection(project_mat);
 
         // create vertex uniform buffer
-        // model_mat and view_projection_mat will be stored in
-        // vertex_uniform_buffer inside the update function
+        // model_mat and view_projection_mat of every drawn node will be stored
+        // in vertex_uniform_buffer inside the update function
+        let alignment = init.device.limits().min_uniform_buffer_offset_alignment as u64;
+        let node_stride = NODE_UNIFORM_SIZE.next_multiple_of(alignment);
+        let material_stride = MATERIAL_UNIFORM_SIZE.next_multiple_of(alignment);
+        let draw_list = graph.draw_list();
         let vertex_uniform_buffer = init.device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Vertex Uniform Buffer"),
-            size: 192,
+            size: node_stride * draw_list.len().max(1) as u64,
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
 
-        // create fragment uniform buffer. here we set eye_position = camera_position and
-        // light_position = eye_position
+        // create fragment uniform buffer. here we set eye_position = camera_position
         let fragment_uniform_buffer = init.device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Fragment Uniform Buffer"),
-            size: 32,
+            size: 16,
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
 
-        // store light and eye positions
-        let light_position: &[f32; 3] = camera_position.as_ref();
+        // store eye position
         let eye_position: &[f32; 3] = camera_position.as_ref();
         init.queue.write_buffer(
             &fragment_uniform_buffer,
             0,
-            bytemuck::cast_slice(light_position),
-        );
-        init.queue.write_buffer(
-            &fragment_uniform_buffer,
-            16,
             bytemuck::cast_slice(eye_position),
         );
 
-        // create light uniform buffer
-        let light_uniform_buffer = init.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Light Uniform Buffer"),
-            size: 48,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+        // create material uniform buffer holding the materials one slot apart
+        let mut material_data = vec![0u8; material_stride as usize * materials.len().max(1)];
+        for (chunk, material) in material_data
+            .chunks_exact_mut(material_stride as usize)
+            .zip(materials)
+        {
+            let bytes: &[u8] = bytemuck::bytes_of(material);
+            chunk[..bytes.len()].copy_from_slice(bytes);
+        }
+        let material_uniform_buffer =
+            init.device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Material Uniform Buffer"),
+                    contents: &material_data,
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
 
-        // store light parameters
-        init.queue.write_buffer(
+        // create light uniform buffer holding the light array
+        let light_uniform_buffer =
+            init.device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Light Uniform Buffer"),
+                    contents: bytemuck::cast_slice(&[LightsUniform::new(lights)]),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+
+        // depth of the scene seen from the shadow-casting light, sampled at bindings 4 to 6
+        let shadow_map = ShadowMap::new(
+            &init.device,
+            Vertex::desc(),
+            None,
+            lights,
+            (0.0, 0.0, 0.0).into(),
+            SCENE_RADIUS,
+        )
+        .with_casters(&init.device, draw_list.len());
+        let ground = GroundPlane::new(
+            &init.device,
+            init.config.format,
+            depth.config,
+            init.sample_count(),
+            GROUND_Y,
+            GROUND_HALF_SIZE,
+            CAMERA_POSITION,
             &light_uniform_buffer,
-            0,
-            bytemuck::cast_slice(&[light_data]),
+            &shadow_map,
         );
 
+        let [shadow_uniform, shadow_texture, shadow_sampler] = ShadowMap::layout_entries();
         let uniform_bind_group_layout =
             init.device
                 .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -192,8 +360,8 @@ impl State {
                             visibility: wgpu::ShaderStages::VERTEX,
                             ty: wgpu::BindingType::Buffer {
                                 ty: wgpu::BufferBindingType::Uniform,
-                                has_dynamic_offset: false,
-                                min_binding_size: None,
+                                has_dynamic_offset: true,
+                                min_binding_size: wgpu::BufferSize::new(NODE_UNIFORM_SIZE),
                             },
                             count: None,
                         },
@@ -210,6 +378,16 @@ impl State {
                         wgpu::BindGroupLayoutEntry {
                             binding: 2,
                             visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Buffer {
+                                ty: wgpu::BufferBindingType::Uniform,
+                                has_dynamic_offset: true,
+                                min_binding_size: wgpu::BufferSize::new(MATERIAL_UNIFORM_SIZE),
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 3,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
                             ty: wgpu::BindingType::Buffer {
                                 ty: wgpu::BufferBindingType::Uniform,
                                 has_dynamic_offset: false,
@@ -217,16 +395,24 @@ impl State {
                             },
                             count: None,
                         },
+                        shadow_uniform,
+                        shadow_texture,
+                        shadow_sampler,
                     ],
                     label: Some("Uniform Bind Group Layout"),
                 });
 
+        let [shadow_uniform, shadow_texture, shadow_sampler] = shadow_map.bind_group_entries();
         let uniform_bind_group = init.device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: &uniform_bind_group_layout,
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
-                    resource: vertex_uniform_buffer.as_entire_binding(),
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &vertex_uniform_buffer,
+                        offset: 0,
+                        size: wgpu::BufferSize::new(NODE_UNIFORM_SIZE),
+                    }),
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
@@ -234,8 +420,19 @@ impl State {
                 },
                 wgpu::BindGroupEntry {
                     binding: 2,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &material_uniform_buffer,
+                        offset: 0,
+                        size: wgpu::BufferSize::new(MATERIAL_UNIFORM_SIZE),
+                    }),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
                     resource: light_uniform_buffer.as_entire_binding(),
                 },
+                shadow_uniform,
+                shadow_texture,
+                shadow_sampler,
             ],
             label: Some("Uniform Bind Group"),
         });
@@ -244,7 +441,7 @@ impl State {
             .device
             .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some("Render Pipeline Layout"),
-                bind_group_layouts: &[&uniform_bind_group_layout],
+                bind_group_layouts: &[&uniform_bind_group_layout, &colormap_bind_group_layout],
                 push_constant_ranges: &[],
             });
 
@@ -275,205 +472,323 @@ impl State {
                     ..Default::default()
                 },
                 //depth_stencil: None,
-                depth_stencil: Some(wgpu::DepthStencilState {
-                    format: wgpu::TextureFormat::Depth24Plus,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::LessEqual,
-                    stencil: wgpu::StencilState::default(),
-                    bias: wgpu::DepthBiasState::default(),
-                }),
-                multisample: wgpu::MultisampleState::default(),
+                depth_stencil: Some(depth.config.depth_stencil_state()),
+                multisample: init.multisample_state(),
                 multiview: None,
             });
 
-        let vertex_buffer = init
-            .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Vertex Buffer"),
-                contents: cast_slice(vertex_data),
-                usage: wgpu::BufferUsages::VERTEX,
-            });
-
-        let num_vertices = vertex_data.len() as u32;
+        let meshes = meshes
+            .iter()
+            .map(|mesh| {
+                let (vertex_data, index_data) = mesh_vertices(mesh);
+                MeshBuffers {
+                    vertex_buffer: init.device.create_buffer_init(
+                        &wgpu::util::BufferInitDescriptor {
+                            label: Some("Vertex Buffer"),
+                            contents: cast_slice(&vertex_data),
+                            usage: wgpu::BufferUsages::VERTEX,
+                        },
+                    ),
+                    index_buffer: init.device.create_buffer_init(
+                        &wgpu::util::BufferInitDescriptor {
+                            label: Some("Index Buffer"),
+                            contents: cast_slice(&index_data),
+                            usage: wgpu::BufferUsages::INDEX,
+                        },
+                    ),
+                    indices_len: index_data.len() as u32,
+                }
+            })
+            .collect();
 
         Self {
-            init,
             pipeline,
-            vertex_buffer,
+            meshes,
+            graph,
+            animate,
+            draw_list,
             uniform_bind_group,
             vertex_uniform_buffer,
-            view_mat,
+            node_stride,
+            material_stride,
+            shadow_map,
+            ground,
+            colormap_texture,
+            colormap_bind_group,
+            colormap_name: colormap_name.to_string(),
+            title: title.to_string(),
+            overlay,
+            plot_box,
+            show_overlay: true,
+            camera: OrbitCamera::new(camera_position, look_direction, up_direction),
+            camera_controller: OrbitController::default(),
+            fragment_uniform_buffer,
             project_mat,
-            num_vertices,
+            depth,
         }
     }
 
-    fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
-        if new_size.width > 0 && new_size.height > 0 {
-            self.init.size = new_size;
-            self.init.config.width = new_size.width;
-            self.init.config.height = new_size.height;
-            self.init
-                .surface
-                .configure(&self.init.device, &self.init.config);
-            self.project_mat = transforms::create_projection(
-                new_size.width as f32 / new_size.height as f32,
-                IS_PERSPECTIVE,
-            );
-        }
+    fn resize(&mut self, init: &InitWgpu, new_size: PhysicalSize<u32>) {
+        self.depth
+            .resize(&init.device, new_size.width, new_size.height);
+        self.project_mat = self.depth.config.projection(transforms::create_projection(
+            new_size.width as f32 / new_size.height as f32,
+            IS_PERSPECTIVE,
+        ));
     }
 
-    #[allow(dead_code)]
-    fn input(&mut self, _event: &WindowEvent) -> bool {
-        false
+    // space switches to the next built-in colormap; only the lookup texture changes.
+    // o toggles the colorbar and axes
+    fn input(&mut self, init: &InitWgpu, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        logical_key: Key::Named(NamedKey::Space),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                let next = colormap::COLORMAP_NAMES
+                    .iter()
+                    .position(|&name| name == self.colormap_name)
+                    .map_or(0, |i| (i + 1) % colormap::COLORMAP_NAMES.len());
+                self.set_colormap(init, colormap::COLORMAP_NAMES[next]);
+                true
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        logical_key: Key::Character(c),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } if c.as_str() == "o" => {
+                self.show_overlay = !self.show_overlay;
+                true
+            }
+            _ => self.camera_controller.process_events(event, init.size),
+        }
     }
 
-    fn update(&mut self, dt: std::time::Duration) {
-        // update uniform buffer
-        let dt = ANIMATION_SPEED * dt.as_secs_f32();
-        let model_mat = transforms::create_transforms(
-            [0.0, 0.0, 0.0],
-            [dt.sin(), dt.cos(), 0.0],
-            [1.0, 1.0, 1.0],
+    fn update(&mut self, init: &InitWgpu, elapsed: Duration, dt: Duration) {
+        self.camera_controller.update_camera(&mut self.camera, dt);
+        let view_mat = self.camera.view_mat();
+        let eye_position: [f32; 3] = self.camera.eye_position().into();
+        init.queue.write_buffer(
+            &self.fragment_uniform_buffer,
+            0,
+            bytemuck::cast_slice(&eye_position),
         );
-        let view_project_mat = self.project_mat * self.view_mat;
+        self.ground.set_eye_position(&init.queue, eye_position);
 
-        let normal_mat = (model_mat.invert().unwrap()).transpose();
+        // move the nodes; only the changed subtrees get new world matrices
+        (self.animate)(&mut self.graph, ANIMATION_SPEED * elapsed.as_secs_f32());
+        self.draw_list = self.graph.draw_list();
+        let view_project_mat = self.project_mat * view_mat;
 
-        let model_ref: &[f32; 16] = model_mat.as_ref();
+        // update uniform buffer
         let view_projection_ref: &[f32; 16] = view_project_mat.as_ref();
-        let normal_ref: &[f32; 16] = normal_mat.as_ref();
-
-        self.init.queue.write_buffer(
-            &self.vertex_uniform_buffer,
-            0,
-            bytemuck::cast_slice(model_ref),
-        );
-        self.init.queue.write_buffer(
-            &self.vertex_uniform_buffer,
-            64,
-            bytemuck::cast_slice(view_projection_ref),
-        );
-        self.init.queue.write_buffer(
-            &self.vertex_uniform_buffer,
-            128,
-            bytemuck::cast_slice(normal_ref),
-        );
+        let mut data = vec![0u8; self.node_stride as usize * self.draw_list.len()];
+        for (chunk, item) in data
+            .chunks_exact_mut(self.node_stride as usize)
+            .zip(&self.draw_list)
+        {
+            let model_ref: &[f32; 16] = item.world.as_ref();
+            let normal_ref: &[f32; 16] = item.normal.as_ref();
+            chunk[..64].copy_from_slice(bytemuck::cast_slice(model_ref));
+            chunk[64..128].copy_from_slice(bytemuck::cast_slice(view_projection_ref));
+            chunk[128..192].copy_from_slice(bytemuck::cast_slice(normal_ref));
+        }
+        init.queue
+            .write_buffer(&self.vertex_uniform_buffer, 0, &data);
+        let model_mats: Vec<Matrix4<f32>> = self.draw_list.iter().map(|item| item.world).collect();
+        self.shadow_map.update_casters(&init.queue, &model_mats);
+        self.ground.update(&init.queue, view_project_mat);
+
+        // rebuilt every frame since the axes rotate with the node of the
+        // first mesh
+        let model_mat = self
+            .draw_list
+            .iter()
+            .find(|item| item.mesh == 0)
+            .map_or(Matrix4::identity(), |item| item.world);
+        let mut overlay = OverlayBuilder::new(init.config.width, init.config.height);
+        if let (Some(plot_box), true) = (&self.plot_box, self.show_overlay) {
+            overlay.axes(plot_box, view_project_mat * model_mat);
+            overlay.colorbar(plot_box.data_min[1], plot_box.data_max[1]);
+        }
+        self.overlay
+            .update(&init.device, &init.queue, overlay.vertices());
     }
 
-    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.init.surface.get_current_texture()?;
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-
-        let depth_texture = self.init.device.create_texture(&wgpu::TextureDescriptor {
-            size: wgpu::Extent3d {
-                width: self.init.config.width,
-                height: self.init.config.height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Depth24Plus,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            label: None,
-            view_formats: &[],
-        });
-
-        let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-        let mut encoder =
-            self.init
-                .device
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("Render Encoder"),
-                });
+    fn render(&mut self, init: &InitWgpu, frame: &mut Frame) {
+        let mut encoder = init
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
+        {
+            let mut shadow_pass = self.shadow_map.begin_pass(&mut encoder);
+            for (i, item) in self.draw_list.iter().enumerate() {
+                let mesh = &self.meshes[item.mesh];
+                self.shadow_map.set_caster(&mut shadow_pass, i);
+                shadow_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                shadow_pass
+                    .set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                shadow_pass.draw_indexed(0..mesh.indices_len, 0, 0..1);
+            }
+        }
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.2,
-                            g: 0.247,
-                            b: 0.314,
-                            a: 1.0,
-                        }),
-                        store: true,
-                    },
-                })],
-                //depth_stencil_attachment: None,
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: &depth_view,
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
-                        store: false,
+                color_attachments: &[Some(init.color_attachment(
+                    frame,
+                    wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.2,
+                        g: 0.247,
+                        b: 0.314,
+                        a: 1.0,
                     }),
-                    stencil_ops: None,
-                }),
+                ))],
+                //depth_stencil_attachment: None,
+                depth_stencil_attachment: Some(self.depth.attachment()),
             });
             render_pass.set_pipeline(&self.pipeline);
-            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-            render_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
-            render_pass.draw(0..self.num_vertices, 0..1);
+            render_pass.set_bind_group(1, &self.colormap_bind_group, &[]);
+            for (i, item) in self.draw_list.iter().enumerate() {
+                let mesh = &self.meshes[item.mesh];
+                let offsets = [
+                    (i as u64 * self.node_stride) as u32,
+                    (item.material.unwrap_or(0) as u64 * self.material_stride) as u32,
+                ];
+                render_pass.set_bind_group(0, &self.uniform_bind_group, &offsets);
+                render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                render_pass
+                    .set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..mesh.indices_len, 0, 0..1);
+            }
+            self.ground.draw(&mut render_pass);
         }
+        self.overlay.render(
+            &mut encoder,
+            init.color_attachment(frame, wgpu::LoadOp::Load),
+        );
 
-        self.init.queue.submit(iter::once(encoder.finish()));
+        init.queue.submit(iter::once(encoder.finish()));
+    }
 
-        output.present();
-        Ok(())
+    fn title(&self) -> Option<String> {
+        Some(format!("ch09_{}: {}", self.title, self.colormap_name))
     }
 }
 
-pub fn run(vertex_data: &Vec<Vertex>, light_data: Light, colormap_name: &str, title: &str) {
-    env_logger::init();
-    let event_loop = EventLoop::new().unwrap();
-    let window = WindowBuilder::new().build(&event_loop).unwrap();
-    window.set_title(&*format!("ch09_{}: {}", title, colormap_name));
-
-    let mut state = pollster::block_on(State::new(&window, &vertex_data, light_data));
-    let render_start_time = std::time::Instant::now();
-
-    event_loop.set_control_flow(ControlFlow::Wait);
-    let _ = event_loop.run(move |event, elwt| match event {
-        Event::WindowEvent {
-            event: WindowEvent::Resized(size),
-            ..
-        } => {
-            state.init.config.width = size.width;
-            state.init.config.height = size.height;
-            state
-                .init
-                .surface
-                .configure(&state.init.device, &state.init.config);
-        }
-        Event::WindowEvent {
-            event: WindowEvent::RedrawRequested,
-            ..
-        } => {
-            let now = std::time::Instant::now();
-            let dt = now - render_start_time;
-
-            state.update(dt);
-
-            match state.render() {
-                Ok(_) => {}
-                Err(wgpu::SurfaceError::Lost) => state.resize(state.init.size),
-                Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
-                Err(e) => eprintln!("{:?}", e),
+impl State {
+    fn set_colormap(&mut self, init: &InitWgpu, colormap_name: &str) {
+        let colormap = Colormap::from_name(colormap_name).unwrap();
+        self.colormap_texture.write_colormap(&init.queue, &colormap);
+        self.colormap_name = colormap_name.to_string();
+    }
+}
+
+/// Tumbles the root nodes the way the single-surface examples rotate.
+pub fn tumble_roots(graph: &mut SceneGraph, t: f32) {
+    for root in graph.roots().to_vec() {
+        graph.set_transform(
+            root,
+            Transform::new([0.0, 0.0, 0.0], [t.sin(), t.cos(), 0.0], [1.0, 1.0, 1.0]),
+        );
+    }
+}
+
+/// With `WGPU_BOOK_EXPORT` set, saves the meshes of all drawn nodes of
+/// `graph`, placed by their world matrices, to that file as one mesh and ends
+/// the program instead of showing them.
+fn export_from_env(meshes: &[Mesh], graph: &mut SceneGraph) {
+    if std::env::var_os("WGPU_BOOK_EXPORT").is_none() {
+        return;
+    }
+    let mut scene = Mesh::default();
+    for item in graph.draw_list() {
+        let mesh = &meshes[item.mesh];
+        let offset = scene.vertices.len() as u32;
+        scene.vertices.extend(mesh.vertices.iter().map(|v| {
+            let p = item.world * Vector4::new(v.position[0], v.position[1], v.position[2], 1.0);
+            let n = (item.normal * Vector4::new(v.normal[0], v.normal[1], v.normal[2], 0.0))
+                .truncate()
+                .normalize();
+            MeshVertex {
+                position: [p.x, p.y, p.z],
+                normal: n.into(),
+                ..*v
             }
-        }
-        Event::WindowEvent {
-            event: WindowEvent::CloseRequested,
-            ..
-        } => elwt.exit(),
-        Event::AboutToWait => {
-            window.request_redraw();
-        }
-        _ => {}
-    });
+        }));
+        scene
+            .indices
+            .extend(mesh.indices.iter().map(|&i| offset + i));
+    }
+    if export::save_from_env(&scene).unwrap() {
+        std::process::exit(0);
+    }
+}
+
+#[allow(clippy::too_many_arguments, dead_code)]
+pub fn run(
+    mesh: &Mesh,
+    material: Material,
+    lights: &[Light],
+    colormap: &Colormap,
+    colormap_name: &str,
+    plot_box: Option<PlotBox>,
+    title: &str,
+) {
+    let mut graph = SceneGraph::new();
+    graph.add(None, SceneNode::new(title).with_mesh(0).with_material(0));
+    run_scene(
+        std::slice::from_ref(mesh),
+        graph,
+        &[material],
+        tumble_roots,
+        lights,
+        colormap,
+        colormap_name,
+        plot_box,
+        title,
+    );
+}
+
+/// Draws the nodes of `graph` that have a mesh, with `meshes[node.mesh]`
+/// and `materials[node.material]` (the first material by default), in one
+/// pass. `animate` moves the nodes before every frame, given the animation
+/// time; it must not add nodes. The axes of `plot_box` follow the node of the
+/// first mesh.
+#[allow(clippy::too_many_arguments)]
+pub fn run_scene(
+    meshes: &[Mesh],
+    mut graph: SceneGraph,
+    materials: &[Material],
+    animate: fn(&mut SceneGraph, f32),
+    lights: &[Light],
+    colormap: &Colormap,
+    colormap_name: &str,
+    plot_box: Option<PlotBox>,
+    title: &str,
+) {
+    export_from_env(meshes, &mut graph);
+    app::run::<State>(
+        &format!("ch09_{}", title),
+        Scene {
+            meshes,
+            graph,
+            materials,
+            animate,
+            lights,
+            colormap,
+            colormap_name,
+            plot_box,
+            title,
+        },
+    );
 }
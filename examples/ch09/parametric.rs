@@ -0,0 +1,106 @@
+use cgmath::Deg;
+use std::f32::consts::PI;
+mod common;
+use wgpu_book::colormap::Colormap;
+use wgpu_book::math_func;
+use wgpu_book::surface_data::{self, ColorBy, Seam, SurfaceOptions};
+
+fn sphere(u: f32, v: f32) -> [f32; 3] {
+    math_func::sphere_position(1.5, Deg(u), Deg(v))
+}
+
+fn sphere_normal(u: f32, v: f32) -> [f32; 3] {
+    math_func::sphere_position(1.0, Deg(u), Deg(v))
+}
+
+fn main() {
+    let mut surface_name = "klein";
+    let mut colormap_name = "jet";
+    let mut is_two_side: i32 = 1;
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 {
+        surface_name = &args[1];
+    }
+    if args.len() > 2 {
+        colormap_name = &args[2];
+    }
+    if args.len() > 3 {
+        is_two_side = args[3].parse().unwrap();
+    }
+
+    let colormap = Colormap::from_name_or_file(colormap_name).unwrap();
+    let options = SurfaceOptions {
+        colormap: colormap.clone(),
+        scale: Some(1.8),
+        ..Default::default()
+    };
+    let radius = |_: f32, _: f32, p: [f32; 3]| (p[0] * p[0] + p[2] * p[2]).sqrt();
+    let (f, u_range, v_range, nu, nv, options) = match surface_name {
+        "klein" => (
+            &math_func::klein_bottle as &dyn Fn(f32, f32) -> [f32; 3],
+            [0.0, PI],
+            [0.0, 2.0 * PI],
+            80,
+            40,
+            SurfaceOptions {
+                seam_v: Seam::Closed,
+                color_by: ColorBy::U,
+                ..options
+            },
+        ),
+        "mobius" => (
+            &math_func::mobius_strip as &dyn Fn(f32, f32) -> [f32; 3],
+            [0.0, 2.0 * PI],
+            [-1.0, 1.0],
+            80,
+            10,
+            SurfaceOptions {
+                seam_u: Seam::Twisted,
+                color_by: ColorBy::U,
+                ..options
+            },
+        ),
+        "seashell" => (
+            &math_func::seashell as &dyn Fn(f32, f32) -> [f32; 3],
+            [0.0, 6.0 * PI],
+            [0.0, 2.0 * PI],
+            120,
+            40,
+            SurfaceOptions {
+                seam_v: Seam::Closed,
+                color_by: ColorBy::Scalar(&radius),
+                ..options
+            },
+        ),
+        "sphere" => (
+            &sphere as &dyn Fn(f32, f32) -> [f32; 3],
+            [0.0, 180.0],
+            [0.0, 360.0],
+            30,
+            40,
+            SurfaceOptions {
+                seam_v: Seam::Closed,
+                normal: Some(&sphere_normal),
+                scale: None,
+                ..options
+            },
+        ),
+        _ => panic!(
+            "unknown surface '{}', expected klein, mobius, seashell or sphere",
+            surface_name
+        ),
+    };
+    let mesh = surface_data::parametric_surface(f, u_range, v_range, nu, nv, &options);
+    let plot_box = surface_data::parametric_plot_box(f, u_range, v_range, nu, nv, &options);
+
+    let material = common::material([1.0, 1.0, 1.0], 0.1, 0.8, 0.4, 30.0, is_two_side);
+    common::run(
+        &mesh,
+        material,
+        &common::default_lights(),
+        &colormap,
+        colormap_name,
+        Some(plot_box),
+        surface_name,
+    );
+}
@@ -0,0 +1,117 @@
+mod common;
+use wgpu_book::colormap::Colormap;
+use wgpu_book::math_func;
+use wgpu_book::mesh::{Mesh, Shading};
+use wgpu_book::scene::{SceneGraph, SceneNode, Transform};
+use wgpu_book::vertex_data;
+
+// distance of the shapes from the center of the surface
+const RING_RADIUS: f32 = 2.5;
+
+/// Mesh of a ch07 shape colored by height, from the bottom to the top of the
+/// colormap.
+fn shape_mesh(positions: &[[f32; 3]], normals: &[[f32; 3]], colormap: &Colormap) -> Mesh {
+    let ymin = positions.iter().map(|p| p[1]).fold(f32::INFINITY, f32::min);
+    let ymax = positions
+        .iter()
+        .map(|p| p[1])
+        .fold(f32::NEG_INFINITY, f32::max);
+    let mut mesh = Mesh::from_triangles(positions, normals, &[]);
+    for v in mesh.vertices.iter_mut() {
+        v.scalar = (v.position[1] - ymin) / (ymax - ymin).max(1e-6);
+        v.color = colormap.color(v.scalar);
+    }
+    mesh
+}
+
+// the table turns slowly, the ring of shapes the other way, and every shape
+// spins about its own axis
+fn animate(graph: &mut SceneGraph, t: f32) {
+    let table = graph.find("table").unwrap();
+    graph.set_transform(
+        table,
+        Transform::new([0.0; 3], [0.0, 0.2 * t, 0.0], [1.0; 3]),
+    );
+    let ring = graph.find("ring").unwrap();
+    graph.set_transform(
+        ring,
+        Transform::new([0.0; 3], [0.0, -0.5 * t, 0.0], [1.0; 3]),
+    );
+    for (i, &shape) in graph.node(ring).children().to_vec().iter().enumerate() {
+        let mut transform = *graph.node(shape).transform();
+        transform.rotation = [t + i as f32, 0.0, 0.5 * t];
+        graph.set_transform(shape, transform);
+    }
+}
+
+fn main() {
+    let mut colormap_name = "jet";
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 {
+        colormap_name = &args[1];
+    }
+
+    let colormap = Colormap::from_name_or_file(colormap_name).unwrap();
+    let (surface, plot_box) = common::create_mesh(
+        &math_func::sinc,
+        &colormap,
+        -8.0,
+        8.0,
+        -8.0,
+        8.0,
+        30,
+        30,
+        2.0,
+        0.3,
+        Shading::Smooth,
+    );
+    let shapes = [
+        vertex_data::sphere_data(1.0, 15, 20),
+        vertex_data::torus_data(1.0, 0.35, 40, 13),
+        vertex_data::cylinder_data(0.4, 0.8, 1.6, 30),
+        vertex_data::cone_data(0.3, 0.9, 1.6, 30),
+    ];
+    let mut meshes = vec![surface];
+    for (positions, normals, _) in &shapes {
+        meshes.push(shape_mesh(positions, normals, &colormap));
+    }
+    let materials = [
+        common::material([1.0, 1.0, 1.0], 0.1, 0.8, 0.4, 30.0, 1),
+        common::material([1.0, 1.0, 1.0], 0.15, 0.8, 0.6, 60.0, 0),
+    ];
+
+    let mut graph = SceneGraph::new();
+    let table = graph.add(None, SceneNode::new("table"));
+    graph.add(
+        Some(table),
+        SceneNode::new("surface").with_mesh(0).with_material(0),
+    );
+    let ring = graph.add(Some(table), SceneNode::new("ring"));
+    for (i, name) in ["sphere", "torus", "cylinder", "cone"].iter().enumerate() {
+        let angle = i as f32 * std::f32::consts::FRAC_PI_2;
+        let transform = Transform::new(
+            [RING_RADIUS * angle.cos(), 0.2, RING_RADIUS * angle.sin()],
+            [0.0; 3],
+            [0.35; 3],
+        );
+        graph.add(
+            Some(ring),
+            SceneNode::new(name)
+                .with_transform(transform)
+                .with_mesh(i + 1)
+                .with_material(1),
+        );
+    }
+
+    common::run_scene(
+        &meshes,
+        graph,
+        &materials,
+        animate,
+        &common::default_lights(),
+        &colormap,
+        colormap_name,
+        Some(plot_box),
+        "scene",
+    );
+}
@@ -9,14 +9,14 @@ struct Uniforms {
 struct Input {
     @location(0) pos: vec4<f32>,
     @location(1) normal: vec4<f32>,
-    @location(2) color: vec4<f32>,
+    @location(2) value: f32,
 }
 
 struct Output {
     @builtin(position) position: vec4<f32>,
     @location(0) v_position: vec4<f32>,
     @location(1) v_normal: vec4<f32>,
-    @location(2) v_color: vec4<f32>,
+    @location(2) v_value: f32,
 };
 
 @vertex
@@ -25,18 +25,17 @@ fn vs_main(in: Input) -> Output {
     let m_position: vec4<f32> = uniforms.model_mat * in.pos;
     output.v_position = m_position;
     output.v_normal = uniforms.normal_mat * in.normal;
-    output.v_color = in.color;
+    output.v_value = in.value;
     output.position = uniforms.view_project_mat * m_position;
     return output;
 }
 
 struct FragUniforms {
-    light_position: vec4<f32>,
     eye_position: vec4<f32>,
 };
 @group(0) @binding(1) var<uniform> frag_uniforms: FragUniforms;
 
-struct LightUniforms {
+struct MaterialUniforms {
     specular_color: vec4<f32>,
     ambient_intensity: f32,
     diffuse_intensity: f32,
@@ -44,26 +43,25 @@ struct LightUniforms {
     specular_shininess: f32,
     is_two_side: i32,
 };
-@group(0) @binding(2) var<uniform> light_uniforms: LightUniforms;
+@group(0) @binding(2) var<uniform> material: MaterialUniforms;
+@group(1) @binding(0) var colormap_texture: texture_2d<f32>;
+@group(1) @binding(1) var colormap_sampler: sampler;
+
+// the colormap texture is 256 texels wide; map [0, 1] onto the first and last texel centers
+fn colormap(value: f32) -> vec3<f32> {
+    let u = (clamp(value, 0.0, 1.0) * 255.0 + 0.5) / 256.0;
+    return textureSample(colormap_texture, colormap_sampler, vec2(u, 0.5)).rgb;
+}
 
 @fragment
 fn fs_main(in: Output) -> @location(0) vec4<f32> {
+    let color: vec3<f32> = colormap(in.v_value);
+
     let N: vec3<f32> = normalize(in.v_normal.xyz);
-    let L: vec3<f32> = normalize(frag_uniforms.light_position.xyz - in.v_position.xyz);
     let V: vec3<f32> = normalize(frag_uniforms.eye_position.xyz - in.v_position.xyz);
-    let H: vec3<f32> = normalize(L + V);
+    let terms = blinn_phong_lights(in.v_position.xyz, N, V, material.diffuse_intensity, material.specular_intensity, material.specular_shininess, material.is_two_side == 1);
 
-    // front side
-    var diffuse: f32 = light_uniforms.diffuse_intensity * max(dot(N, L), 0.0);
-    var specular: f32 = light_uniforms.specular_intensity * pow(max(dot(N, H), 0.0), light_uniforms.specular_shininess);
-
-    // back side
-    if (light_uniforms.is_two_side == 1) {
-        diffuse = diffuse + light_uniforms.diffuse_intensity * max(dot(-N, L), 0.0);
-        specular = specular + light_uniforms.specular_intensity * pow(max(dot(-N, H), 0.0), light_uniforms.specular_shininess);
-    }
-
-    let ambient: f32 = light_uniforms.ambient_intensity;
-    let final_color: vec3<f32> = in.v_color.xyz * (ambient + diffuse) + light_uniforms.specular_color.xyz * specular;
+    let ambient: f32 = material.ambient_intensity;
+    let final_color: vec3<f32> = color * (ambient + terms.diffuse) + material.specular_color.xyz * terms.specular;
     return vec4(final_color, 1.0);
-}
\ No newline at end of file
+}
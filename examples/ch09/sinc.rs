@@ -1,10 +1,12 @@
 mod common;
-#[path = "../common/math_func.rs"]
-mod math;
+use wgpu_book::colormap::Colormap;
+use wgpu_book::math_func;
+use wgpu_book::mesh::Shading;
 
 fn main() {
     let mut colormap_name = "jet";
     let mut is_two_side: i32 = 1;
+    let mut shading = Shading::Smooth;
     let args: Vec<String> = std::env::args().collect();
     if args.len() > 1 {
         colormap_name = &args[1];
@@ -12,10 +14,14 @@ fn main() {
     if args.len() > 2 {
         is_two_side = args[2].parse().unwrap();
     }
+    if args.len() > 3 {
+        shading = args[3].parse().unwrap();
+    }
 
-    let vertex_data = common::create_vertices(
-        &math::sinc,
-        colormap_name,
+    let colormap = Colormap::from_name_or_file(colormap_name).unwrap();
+    let (mesh, plot_box) = common::create_mesh(
+        &math_func::sinc,
+        &colormap,
         -8.0,
         8.0,
         -8.0,
@@ -24,8 +30,17 @@ fn main() {
         30,
         2.0,
         0.3,
+        shading,
     );
 
-    let light_data = common::light([1.0, 1.0, 1.0], 0.1, 0.8, 0.4, 30.0, is_two_side);
-    common::run(&vertex_data, light_data, colormap_name, "sinc");
+    let material = common::material([1.0, 1.0, 1.0], 0.1, 0.8, 0.4, 30.0, is_two_side);
+    common::run(
+        &mesh,
+        material,
+        &common::default_lights(),
+        &colormap,
+        colormap_name,
+        Some(plot_box),
+        "sinc",
+    );
 }
@@ -1,26 +1,33 @@
 use bytemuck::{cast_slice, Pod, Zeroable};
 use cgmath::{Matrix, Matrix4, SquareMatrix};
-use std::{iter, mem};
+use std::path::{Path, PathBuf};
+use std::{iter, mem, time::Duration};
 use wgpu::util::DeviceExt;
-use winit::{
-    event::{Event, WindowEvent},
-    event_loop::{ControlFlow, EventLoop},
-    window::{Window, WindowBuilder},
-};
-
-#[path = "../common/surface_data.rs"]
-mod surface;
-#[path = "../common/texture_data.rs"]
-mod texture;
-#[path = "../common/transforms.rs"]
-mod transforms;
+use winit::{dpi::PhysicalSize, event::WindowEvent};
+
+use wgpu_book::app::{self, App};
+use wgpu_book::depth::{DepthConfig, DepthTarget};
+use wgpu_book::export;
+use wgpu_book::ground::GroundPlane;
+use wgpu_book::light::{self, Light, LightsUniform};
+use wgpu_book::mesh::Mesh;
+use wgpu_book::orbit::{OrbitCamera, OrbitController};
+use wgpu_book::pbr::{PbrMaterial, PbrTextures, PBR_WGSL};
+use wgpu_book::shadow::ShadowMap;
+use wgpu_book::transforms::{self, Frame, InitWgpu};
 
 const ANIMATION_SPEED: f32 = 1.0;
 const IS_PERSPECTIVE: bool = true;
+const CAMERA_POSITION: [f32; 3] = [2.5, 1.25, 2.5];
+// the ground plane receiving the shadows and the extent of the shadow casters
+const GROUND_Y: f32 = -2.5;
+const GROUND_HALF_SIZE: f32 = 8.0;
+const SCENE_RADIUS: f32 = 2.5;
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
-pub struct Light {
+/// Blinn-Phong material parameters.
+pub struct Material {
     specular_color: [f32; 4],
     ambient_intensity: f32,
     diffuse_intensity: f32,
@@ -29,8 +36,9 @@ pub struct Light {
     is_two_side: i32,
 }
 
-pub fn light(sc: [f32; 3], ai: f32, di: f32, si: f32, ss: f32, two_side: i32) -> Light {
-    Light {
+#[allow(dead_code)]
+pub fn material(sc: [f32; 3], ai: f32, di: f32, si: f32, ss: f32, two_side: i32) -> Material {
+    Material {
         specular_color: [sc[0], sc[1], sc[2], 1.0],
         ambient_intensity: ai,
         diffuse_intensity: di,
@@ -40,6 +48,26 @@ pub fn light(sc: [f32; 3], ai: f32, di: f32, si: f32, ss: f32, two_side: i32) ->
     }
 }
 
+/// How the textured surface is shaded.
+#[allow(dead_code)]
+pub enum Shading {
+    /// Blinn-Phong with the image as the diffuse color.
+    BlinnPhong(Material),
+    /// Metallic-roughness PBR; without a base color texture of its own the
+    /// material uses the image.
+    Pbr(PbrMaterial),
+}
+
+/// A white point light at the camera and a directional light from above
+/// that casts the shadows on the ground.
+#[allow(dead_code)]
+pub fn default_lights() -> Vec<Light> {
+    vec![
+        Light::directional([0.5, -1.0, -0.3], [1.0, 1.0, 1.0]).with_intensity(0.5),
+        Light::point(CAMERA_POSITION, [1.0, 1.0, 1.0]).with_intensity(0.7),
+    ]
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
 pub struct Vertex {
@@ -70,100 +98,132 @@ impl Vertex {
     }
 }
 
+/// A piece of the scene with its own shading and textures.
+pub struct Part<'a> {
+    pub vertex_data: &'a [Vertex],
+    pub shading: Shading,
+}
+
+/// The GPU side of a [`Part`].
 #[allow(dead_code)]
-struct State {
-    pub init: transforms::InitWgpu,
-    pipeline: wgpu::RenderPipeline,
+struct PartBuffers {
+    pipeline: usize,
     vertex_buffer: wgpu::Buffer,
+    num_vertices: u32,
     uniform_bind_group: wgpu::BindGroup,
+    textures: PbrTextures,
+    texture_bind_group: wgpu::BindGroup,
+}
+
+struct State {
+    // one per shading model in use
+    pipelines: Vec<wgpu::RenderPipeline>,
+    parts: Vec<PartBuffers>,
     vertex_uniform_buffer: wgpu::Buffer,
-    view_mat: Matrix4<f32>,
+    shadow_map: ShadowMap,
+    ground: GroundPlane,
+    camera: OrbitCamera,
+    camera_controller: OrbitController,
+    fragment_uniform_buffer: wgpu::Buffer,
     project_mat: Matrix4<f32>,
-    num_vertices: u32,
+    depth: DepthTarget,
+}
 
-    image_texture: texture::Texture,
-    texture_bind_group: wgpu::BindGroup,
+struct Scene<'a> {
+    parts: Vec<Part<'a>>,
+    lights: &'a [Light],
+    /// Base color texture of the parts without one of their own; they are
+    /// white without it.
+    img_file: Option<&'a Path>,
+    u_mode: wgpu::AddressMode,
+    v_mode: wgpu::AddressMode,
 }
 
-impl State {
-    async fn new(
-        window: &Window,
-        vertex_data: &Vec<Vertex>,
-        light_data: Light,
-        img_file: &str,
-        u_mode: wgpu::AddressMode,
-        v_mode: wgpu::AddressMode,
-    ) -> Self {
-        let init = transforms::InitWgpu::init_wgpu(window).await;
-
-        // create image texture
-        let image_texture = texture::Texture::create_texture_data(
-            &init.device,
-            &init.queue,
+fn create_pipeline(
+    init: &InitWgpu,
+    layout: &wgpu::PipelineLayout,
+    shader_source: String,
+    depth: &DepthTarget,
+) -> wgpu::RenderPipeline {
+    let shader = init
+        .device
+        .create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shader"),
+            // the light array and its evaluation are shared with the other lit chapters
+            source: wgpu::ShaderSource::Wgsl(shader_source.into()),
+        });
+
+    init.device
+        .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[Vertex::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: init.config.format,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent::REPLACE,
+                        alpha: wgpu::BlendComponent::REPLACE,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: Some(depth.config.depth_stencil_state()),
+            multisample: init.multisample_state(),
+            multiview: None,
+        })
+}
+
+impl App for State {
+    type Scene<'a> = Scene<'a>;
+
+    fn init(init: &InitWgpu, scene: Scene<'_>) -> Self {
+        let Scene {
+            parts,
+            lights,
             img_file,
             u_mode,
             v_mode,
-        )
-        .unwrap();
+        } = scene;
+        let fallback_image = img_file.map(|file| image::open(file).unwrap().to_rgba8());
 
         let texture_bind_group_layout =
             init.device
                 .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                    entries: &[
-                        wgpu::BindGroupLayoutEntry {
-                            binding: 0,
-                            visibility: wgpu::ShaderStages::FRAGMENT,
-                            ty: wgpu::BindingType::Texture {
-                                multisampled: false,
-                                view_dimension: wgpu::TextureViewDimension::D2,
-                                sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                            },
-                            count: None,
-                        },
-                        wgpu::BindGroupLayoutEntry {
-                            binding: 1,
-                            visibility: wgpu::ShaderStages::FRAGMENT,
-                            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                            count: None,
-                        },
-                    ],
+                    entries: &PbrTextures::layout_entries(),
                     label: Some("Texture Bind Group Layout"),
                 });
 
-        let texture_bind_group = init.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &texture_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&image_texture.view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&image_texture.sampler),
-                },
-            ],
-            label: Some("Texture Bind Group"),
-        });
-
-        let shader = init
-            .device
-            .create_shader_module(wgpu::ShaderModuleDescriptor {
-                label: Some("Shader"),
-                source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
-            });
-
         // uniform data
-        let camera_position = (2.5, 1.25, 2.5).into();
+        let camera_position = CAMERA_POSITION.into();
         let look_direction = (0.0, 0.0, 0.0).into();
         let up_direction = cgmath::Vector3::unit_y();
 
-        let (view_mat, project_mat, _view_project_mat) = transforms::create_view_projection(
+        let (_, project_mat, _) = transforms::create_view_projection(
             camera_position,
             look_direction,
             up_direction,
             init.config.width as f32 / init.config.height as f32,
             IS_PERSPECTIVE,
         );
+        let depth = DepthTarget::new(
+            &init.device,
+            init.config.width,
+            init.config.height,
+            init.sample_count(),
+            DepthConfig::from_env(),
+        );
+        let project_mat = depth.config.projection(project_mat);
 
         // create vertex uniform buffer
         // model_mat and view_projection_mat will be stored in
@@ -175,44 +235,53 @@ impl State {
             mapped_at_creation: false,
         });
 
-        // create fragment uniform buffer. here we set eye_position = camera_position and
-        // light_position = eye_position
+        // create fragment uniform buffer. here we set eye_position = camera_position
         let fragment_uniform_buffer = init.device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Fragment Uniform Buffer"),
-            size: 32,
+            size: 16,
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
 
-        // store light and eye positions
-        let light_position: &[f32; 3] = camera_position.as_ref();
+        // store eye position
         let eye_position: &[f32; 3] = camera_position.as_ref();
         init.queue.write_buffer(
             &fragment_uniform_buffer,
             0,
-            bytemuck::cast_slice(light_position),
-        );
-        init.queue.write_buffer(
-            &fragment_uniform_buffer,
-            16,
             bytemuck::cast_slice(eye_position),
         );
 
-        // create light uniform buffer
-        let light_uniform_buffer = init.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Light Uniform Buffer"),
-            size: 48,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+        // create light uniform buffer holding the light array
+        let light_uniform_buffer =
+            init.device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Light Uniform Buffer"),
+                    contents: bytemuck::cast_slice(&[LightsUniform::new(lights)]),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
 
-        // store light parameters
-        init.queue.write_buffer(
+        // depth of the scene seen from the shadow-casting light, sampled at bindings 4 to 6
+        let shadow_map = ShadowMap::new(
+            &init.device,
+            Vertex::desc(),
+            None,
+            lights,
+            (0.0, 0.0, 0.0).into(),
+            SCENE_RADIUS,
+        );
+        let ground = GroundPlane::new(
+            &init.device,
+            init.config.format,
+            depth.config,
+            init.sample_count(),
+            GROUND_Y,
+            GROUND_HALF_SIZE,
+            CAMERA_POSITION,
             &light_uniform_buffer,
-            0,
-            bytemuck::cast_slice(&[light_data]),
+            &shadow_map,
         );
 
+        let [shadow_uniform, shadow_texture, shadow_sampler] = ShadowMap::layout_entries();
         let uniform_bind_group_layout =
             init.device
                 .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -247,29 +316,23 @@ impl State {
                             },
                             count: None,
                         },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 3,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Buffer {
+                                ty: wgpu::BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size: None,
+                            },
+                            count: None,
+                        },
+                        shadow_uniform,
+                        shadow_texture,
+                        shadow_sampler,
                     ],
                     label: Some("Uniform Bind Group Layout"),
                 });
 
-        let uniform_bind_group = init.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &uniform_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: vertex_uniform_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: fragment_uniform_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: light_uniform_buffer.as_entire_binding(),
-                },
-            ],
-            label: Some("Uniform Bind Group"),
-        });
-
         let pipeline_layout = init
             .device
             .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -278,98 +341,160 @@ impl State {
                 push_constant_ranges: &[],
             });
 
-        let pipeline = init
-            .device
-            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("Render Pipeline"),
-                layout: Some(&pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &shader,
-                    entry_point: "vs_main",
-                    buffers: &[Vertex::desc()],
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &shader,
-                    entry_point: "fs_main",
-                    targets: &[Some(wgpu::ColorTargetState {
-                        format: init.config.format,
-                        blend: Some(wgpu::BlendState {
-                            color: wgpu::BlendComponent::REPLACE,
-                            alpha: wgpu::BlendComponent::REPLACE,
-                        }),
-                        write_mask: wgpu::ColorWrites::ALL,
-                    })],
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    ..Default::default()
-                },
-                //depth_stencil: None,
-                depth_stencil: Some(wgpu::DepthStencilState {
-                    format: wgpu::TextureFormat::Depth24Plus,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::LessEqual,
-                    stencil: wgpu::StencilState::default(),
-                    bias: wgpu::DepthBiasState::default(),
-                }),
-                multisample: wgpu::MultisampleState::default(),
-                multiview: None,
+        // shading models in the order of `pipelines`
+        let mut pipeline_kinds = Vec::new();
+        let mut pipelines = Vec::new();
+        let mut part_buffers = Vec::new();
+        for Part {
+            vertex_data,
+            shading,
+        } in parts
+        {
+            let is_pbr = matches!(shading, Shading::Pbr(_));
+            let pipeline = match pipeline_kinds.iter().position(|&kind| kind == is_pbr) {
+                Some(pipeline) => pipeline,
+                None => {
+                    let shader_source = if is_pbr {
+                        [light::LIGHT_WGSL, PBR_WGSL, include_str!("pbr.wgsl")].join("\n")
+                    } else {
+                        [light::LIGHT_WGSL, include_str!("shader.wgsl")].join("\n")
+                    };
+                    pipelines.push(create_pipeline(
+                        init,
+                        &pipeline_layout,
+                        shader_source,
+                        &depth,
+                    ));
+                    pipeline_kinds.push(is_pbr);
+                    pipelines.len() - 1
+                }
+            };
+
+            // create the textures; Blinn-Phong only reads the base color at binding 0
+            // and the sampler at binding 1, PBR the whole set
+            let (mut pbr_material, mut material_data) = match &shading {
+                Shading::BlinnPhong(material) => (
+                    PbrMaterial::new([1.0, 1.0, 1.0], 0.0, 1.0),
+                    bytemuck::bytes_of(material).to_vec(),
+                ),
+                Shading::Pbr(material) => (
+                    material.clone(),
+                    bytemuck::bytes_of(&material.uniform()).to_vec(),
+                ),
+            };
+            if pbr_material.base_color_texture.is_none() {
+                pbr_material.base_color_texture = fallback_image.clone();
+            }
+            let textures =
+                PbrTextures::new(&init.device, &init.queue, &pbr_material, u_mode, v_mode);
+            let texture_bind_group = init.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &texture_bind_group_layout,
+                entries: &textures.bind_group_entries(),
+                label: Some("Texture Bind Group"),
             });
 
-        let vertex_buffer = init
-            .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Vertex Buffer"),
-                contents: cast_slice(vertex_data),
-                usage: wgpu::BufferUsages::VERTEX,
+            // create material uniform buffer holding the material parameters,
+            // padded to the size of the larger WGSL struct
+            material_data.resize(48, 0);
+            let material_uniform_buffer =
+                init.device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Material Uniform Buffer"),
+                        contents: &material_data,
+                        usage: wgpu::BufferUsages::UNIFORM,
+                    });
+
+            let [shadow_uniform, shadow_texture, shadow_sampler] = shadow_map.bind_group_entries();
+            let uniform_bind_group = init.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &uniform_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: vertex_uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: fragment_uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: material_uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: light_uniform_buffer.as_entire_binding(),
+                    },
+                    shadow_uniform,
+                    shadow_texture,
+                    shadow_sampler,
+                ],
+                label: Some("Uniform Bind Group"),
             });
 
-        let num_vertices = vertex_data.len() as u32;
+            let vertex_buffer = init
+                .device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Vertex Buffer"),
+                    contents: cast_slice(vertex_data),
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
+
+            part_buffers.push(PartBuffers {
+                pipeline,
+                vertex_buffer,
+                num_vertices: vertex_data.len() as u32,
+                uniform_bind_group,
+                textures,
+                texture_bind_group,
+            });
+        }
 
         Self {
-            init,
-            pipeline,
-            vertex_buffer,
-            uniform_bind_group,
+            pipelines,
+            parts: part_buffers,
             vertex_uniform_buffer,
-            view_mat,
+            shadow_map,
+            ground,
+            camera: OrbitCamera::new(camera_position, look_direction, up_direction),
+            camera_controller: OrbitController::default(),
+            fragment_uniform_buffer,
             project_mat,
-            num_vertices,
-
-            image_texture,
-            texture_bind_group,
+            depth,
         }
     }
 
-    fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
-        if new_size.width > 0 && new_size.height > 0 {
-            self.init.size = new_size;
-            self.init.config.width = new_size.width;
-            self.init.config.height = new_size.height;
-            self.init
-                .surface
-                .configure(&self.init.device, &self.init.config);
-            self.project_mat = transforms::create_projection(
-                new_size.width as f32 / new_size.height as f32,
-                IS_PERSPECTIVE,
-            );
-        }
+    fn resize(&mut self, init: &InitWgpu, new_size: PhysicalSize<u32>) {
+        self.depth
+            .resize(&init.device, new_size.width, new_size.height);
+        self.project_mat = self.depth.config.projection(transforms::create_projection(
+            new_size.width as f32 / new_size.height as f32,
+            IS_PERSPECTIVE,
+        ));
     }
 
-    #[allow(dead_code)]
-    fn input(&mut self, _event: &WindowEvent) -> bool {
-        false
+    fn input(&mut self, init: &InitWgpu, event: &WindowEvent) -> bool {
+        self.camera_controller.process_events(event, init.size)
     }
 
-    fn update(&mut self, dt: std::time::Duration) {
+    fn update(&mut self, init: &InitWgpu, elapsed: Duration, dt: Duration) {
+        self.camera_controller.update_camera(&mut self.camera, dt);
+        let view_mat = self.camera.view_mat();
+        let eye_position: [f32; 3] = self.camera.eye_position().into();
+        init.queue.write_buffer(
+            &self.fragment_uniform_buffer,
+            0,
+            bytemuck::cast_slice(&eye_position),
+        );
+        self.ground.set_eye_position(&init.queue, eye_position);
+
         // update uniform buffer
-        let dt = ANIMATION_SPEED * dt.as_secs_f32();
+        let t = ANIMATION_SPEED * elapsed.as_secs_f32();
         let model_mat = transforms::create_transforms(
             [0.0, 0.0, 0.0],
-            [dt.sin(), dt.cos(), 0.0],
+            [t.sin(), t.cos(), 0.0],
             [1.0, 1.0, 1.0],
         );
-        let view_project_mat = self.project_mat * self.view_mat;
+        let view_project_mat = self.project_mat * view_mat;
 
         let normal_mat = (model_mat.invert().unwrap()).transpose();
 
@@ -377,154 +502,131 @@ impl State {
         let view_projection_ref: &[f32; 16] = view_project_mat.as_ref();
         let normal_ref: &[f32; 16] = normal_mat.as_ref();
 
-        self.init.queue.write_buffer(
+        init.queue.write_buffer(
             &self.vertex_uniform_buffer,
             0,
             bytemuck::cast_slice(model_ref),
         );
-        self.init.queue.write_buffer(
+        init.queue.write_buffer(
             &self.vertex_uniform_buffer,
             64,
             bytemuck::cast_slice(view_projection_ref),
         );
-        self.init.queue.write_buffer(
+        init.queue.write_buffer(
             &self.vertex_uniform_buffer,
             128,
             bytemuck::cast_slice(normal_ref),
         );
+        self.shadow_map.update(&init.queue, model_mat);
+        self.ground.update(&init.queue, view_project_mat);
     }
 
-    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.init.surface.get_current_texture()?;
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-
-        let depth_texture = self.init.device.create_texture(&wgpu::TextureDescriptor {
-            size: wgpu::Extent3d {
-                width: self.init.config.width,
-                height: self.init.config.height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Depth24Plus,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            label: None,
-            view_formats: &[],
-        });
-
-        let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-        let mut encoder =
-            self.init
-                .device
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("Render Encoder"),
-                });
+    fn render(&mut self, init: &InitWgpu, frame: &mut Frame) {
+        let mut encoder = init
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
+        {
+            let mut shadow_pass = self.shadow_map.begin_pass(&mut encoder);
+            for part in &self.parts {
+                shadow_pass.set_vertex_buffer(0, part.vertex_buffer.slice(..));
+                shadow_pass.draw(0..part.num_vertices, 0..1);
+            }
+        }
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.2,
-                            g: 0.247,
-                            b: 0.314,
-                            a: 1.0,
-                        }),
-                        store: true,
-                    },
-                })],
-                //depth_stencil_attachment: None,
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: &depth_view,
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
-                        store: false,
+                color_attachments: &[Some(init.color_attachment(
+                    frame,
+                    wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.2,
+                        g: 0.247,
+                        b: 0.314,
+                        a: 1.0,
                     }),
-                    stencil_ops: None,
-                }),
+                ))],
+                //depth_stencil_attachment: None,
+                depth_stencil_attachment: Some(self.depth.attachment()),
             });
-            render_pass.set_pipeline(&self.pipeline);
-            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-            render_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
-            render_pass.set_bind_group(1, &self.texture_bind_group, &[]);
-            render_pass.draw(0..self.num_vertices, 0..1);
+            for part in &self.parts {
+                render_pass.set_pipeline(&self.pipelines[part.pipeline]);
+                render_pass.set_vertex_buffer(0, part.vertex_buffer.slice(..));
+                render_pass.set_bind_group(0, &part.uniform_bind_group, &[]);
+                render_pass.set_bind_group(1, &part.texture_bind_group, &[]);
+                render_pass.draw(0..part.num_vertices, 0..1);
+            }
+            self.ground.draw(&mut render_pass);
         }
 
-        self.init.queue.submit(iter::once(encoder.finish()));
+        init.queue.submit(iter::once(encoder.finish()));
+    }
+}
 
-        output.present();
-        Ok(())
+/// With `WGPU_BOOK_EXPORT` set, saves the triangles to that file and ends the
+/// program instead of showing them.
+fn export_from_env(vertex_data: &[Vertex]) {
+    let positions: Vec<[f32; 3]> = vertex_data
+        .iter()
+        .map(|v| [v.position[0], v.position[1], v.position[2]])
+        .collect();
+    let normals: Vec<[f32; 3]> = vertex_data
+        .iter()
+        .map(|v| [v.normal[0], v.normal[1], v.normal[2]])
+        .collect();
+    let uvs: Vec<[f32; 2]> = vertex_data.iter().map(|v| v.uv).collect();
+    let mesh = Mesh::from_triangles(&positions, &normals, &uvs);
+    if export::save_from_env(&mesh).unwrap() {
+        std::process::exit(0);
     }
 }
 
+/// Path of one of the bundled images in `examples/ch10/assets`.
+#[allow(dead_code)]
+pub fn asset(file_name: &str) -> PathBuf {
+    Path::new("examples/ch10/assets").join(file_name)
+}
+
+#[allow(dead_code)]
 pub fn run(
-    vertex_data: &Vec<Vertex>,
-    light_data: Light,
-    file_name: &str,
+    vertex_data: &[Vertex],
+    shading: Shading,
+    lights: &[Light],
+    img_file: &Path,
     u_mode: wgpu::AddressMode,
     v_mode: wgpu::AddressMode,
     title: &str,
 ) {
-    let path = "examples/ch10/assets/";
-    let img_file = [path, file_name].join("");
-
-    env_logger::init();
-    let event_loop = EventLoop::new().unwrap();
-    let window = WindowBuilder::new().build(&event_loop).unwrap();
-    window.set_title(&*format!("ch10_{}: {}", title, file_name));
-
-    let mut state = pollster::block_on(State::new(
-        &window,
-        &vertex_data,
-        light_data,
-        &img_file,
-        u_mode,
-        v_mode,
-    ));
-    let render_start_time = std::time::Instant::now();
-
-    event_loop.set_control_flow(ControlFlow::Wait);
-    let _ = event_loop.run(move |event, elwt| match event {
-        Event::WindowEvent {
-            event: WindowEvent::Resized(size),
-            ..
-        } => {
-            state.init.config.width = size.width;
-            state.init.config.height = size.height;
-            state
-                .init
-                .surface
-                .configure(&state.init.device, &state.init.config);
-        }
-        Event::WindowEvent {
-            event: WindowEvent::RedrawRequested,
-            ..
-        } => {
-            let now = std::time::Instant::now();
-            let dt = now - render_start_time;
-
-            state.update(dt);
-
-            match state.render() {
-                Ok(_) => {}
-                Err(wgpu::SurfaceError::Lost) => state.resize(state.init.size),
-                Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
-                Err(e) => eprintln!("{:?}", e),
-            }
-        }
-        Event::WindowEvent {
-            event: WindowEvent::CloseRequested,
-            ..
-        } => elwt.exit(),
-        Event::AboutToWait => {
-            window.request_redraw();
-        }
-        _ => {}
-    });
+    export_from_env(vertex_data);
+
+    let file_name = img_file.file_name().unwrap_or_default().to_string_lossy();
+    app::run::<State>(
+        &format!("ch10_{}: {}", title, file_name),
+        Scene {
+            parts: vec![Part {
+                vertex_data,
+                shading,
+            }],
+            lights,
+            img_file: Some(img_file),
+            u_mode,
+            v_mode,
+        },
+    );
+}
+
+/// Draws several parts, each with its own shading and textures; parts
+/// without a base color texture are white. Textures repeat.
+#[allow(dead_code)]
+pub fn run_parts(parts: Vec<Part>, lights: &[Light], title: &str) {
+    app::run::<State>(
+        &format!("ch10_{}", title),
+        Scene {
+            parts,
+            lights,
+            img_file: None,
+            u_mode: wgpu::AddressMode::Repeat,
+            v_mode: wgpu::AddressMode::Repeat,
+        },
+    );
 }
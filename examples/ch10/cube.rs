@@ -1,6 +1,5 @@
 mod common;
-#[path = "../common/vertex_data.rs"]
-mod vertex_data;
+use wgpu_book::vertex_data;
 
 fn vertex(p: [i8; 3], n: [i8; 3], t: [i8; 2]) -> common::Vertex {
     common::Vertex {
@@ -27,9 +26,17 @@ fn main() {
     }
 
     let vertex_data = create_vertices();
-    let light_data = common::light([1.0, 1.0, 0.0], 0.1, 0.8, 0.4, 30.0, 1);
+    let material = common::material([1.0, 1.0, 0.0], 0.1, 0.8, 0.4, 30.0, 1);
 
     let u_mode = wgpu::AddressMode::ClampToEdge;
     let v_mode = wgpu::AddressMode::ClampToEdge;
-    common::run(&vertex_data, light_data, file_name, u_mode, v_mode, "cube");
+    common::run(
+        &vertex_data,
+        common::Shading::BlinnPhong(material),
+        &common::default_lights(),
+        &common::asset(file_name),
+        u_mode,
+        v_mode,
+        "cube",
+    );
 }
@@ -0,0 +1,46 @@
+mod common;
+use wgpu_book::gltf::GltfScene;
+use wgpu_book::pbr::PbrMaterial;
+
+fn main() {
+    let mut file_name = "examples/ch10/assets/scene.gltf";
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 {
+        file_name = &args[1];
+    }
+
+    let scene = GltfScene::load(file_name).unwrap_or_else(|e| panic!("{:#}", e));
+    let (mut mesh, triangle_materials) = scene.flatten();
+    mesh.fit(2.0);
+
+    // one part per material, the glTF default material for primitives
+    // without one
+    let mut groups: Vec<(Option<usize>, Vec<common::Vertex>)> = Vec::new();
+    for (tri, &material) in mesh.indices.chunks_exact(3).zip(&triangle_materials) {
+        let group = match groups.iter().position(|(m, _)| *m == material) {
+            Some(group) => group,
+            None => {
+                groups.push((material, Vec::new()));
+                groups.len() - 1
+            }
+        };
+        groups[group].1.extend(tri.iter().map(|&i| {
+            let v = &mesh.vertices[i as usize];
+            common::vertex(v.position, v.normal, v.uv)
+        }));
+    }
+    let parts = groups
+        .iter()
+        .map(|(material, vertex_data)| common::Part {
+            vertex_data,
+            shading: common::Shading::Pbr(
+                material.map_or(PbrMaterial::new([1.0, 1.0, 1.0], 1.0, 1.0), |m| {
+                    scene.materials[m].clone()
+                }),
+            ),
+        })
+        .collect();
+
+    let name = file_name.rsplit(['/', '\\']).next().unwrap_or(file_name);
+    common::run_parts(parts, &common::default_lights(), &format!("gltf: {}", name));
+}
@@ -0,0 +1,48 @@
+mod common;
+use wgpu_book::obj::ObjModel;
+
+fn main() {
+    let mut file_name = "examples/ch10/assets/house.obj";
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 {
+        file_name = &args[1];
+    }
+
+    let mut model = ObjModel::load(file_name).unwrap();
+    model.mesh.fit(2.0);
+    let vertex_data: Vec<common::Vertex> = model
+        .mesh
+        .indices
+        .iter()
+        .map(|&i| {
+            let v = &model.mesh.vertices[i as usize];
+            common::vertex(v.position, v.normal, v.uv)
+        })
+        .collect();
+
+    // the pipeline has a single material and texture, so those of the
+    // material on most faces are used
+    let main_material = model.main_material();
+    let texture = main_material
+        .and_then(|m| m.diffuse_texture.clone())
+        .unwrap_or_else(|| common::asset("red-brick-wall.png"));
+    let (specular, shininess) = main_material.map_or((0.4, 30.0), |m| {
+        (
+            m.specular.iter().copied().fold(0.0, f32::max).min(1.0),
+            m.shininess.max(1.0),
+        )
+    });
+    let material = common::material([1.0, 1.0, 1.0], 0.1, 0.8, specular, shininess, 1);
+
+    let u_mode = wgpu::AddressMode::Repeat;
+    let v_mode = wgpu::AddressMode::Repeat;
+    common::run(
+        &vertex_data,
+        common::Shading::BlinnPhong(material),
+        &common::default_lights(),
+        &texture,
+        u_mode,
+        v_mode,
+        "model",
+    );
+}
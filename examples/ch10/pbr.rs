@@ -0,0 +1,67 @@
+mod common;
+use wgpu_book::pbr::{self, PbrMaterial};
+use wgpu_book::vertex_data;
+
+fn create_vertices() -> Vec<common::Vertex> {
+    let (pos, normal, uv) = vertex_data::sphere_data(1.5, 30, 40);
+    let mut data: Vec<common::Vertex> = Vec::with_capacity(pos.len());
+    for i in 0..pos.len() {
+        data.push(common::vertex(pos[i], normal[i], uv[i]));
+    }
+    data.to_vec()
+}
+
+fn load(path: &str) -> image::RgbaImage {
+    image::open(path)
+        .unwrap_or_else(|e| panic!("cannot load {}: {}", path, e))
+        .to_rgba8()
+}
+
+// usage: ch10_pbr [metallic] [roughness] [base_color normal metallic_roughness occlusion]
+// where any texture path may be `-` to leave it out. Without textures the
+// brick image is the base color and its luminance doubles as the height of
+// a normal map and as ambient occlusion.
+fn main() {
+    let file_name = "red-brick-wall.png";
+    let args: Vec<String> = std::env::args().collect();
+    let metallic = args.get(1).and_then(|s| s.parse().ok()).unwrap_or(0.0);
+    let roughness = args.get(2).and_then(|s| s.parse().ok()).unwrap_or(0.6);
+    let textures: Vec<Option<&str>> = (3..7)
+        .map(|i| args.get(i).map(String::as_str).filter(|&s| s != "-"))
+        .collect();
+
+    let mut material = PbrMaterial::new([1.0, 1.0, 1.0], metallic, roughness).with_two_side(true);
+    if args.len() > 3 {
+        if let Some(path) = textures[0] {
+            material = material.with_base_color_texture(load(path));
+        }
+        if let Some(path) = textures[1] {
+            material = material.with_normal_texture(load(path), 1.0);
+        }
+        if let Some(path) = textures[2] {
+            material = material.with_metallic_roughness_texture(load(path));
+        }
+        if let Some(path) = textures[3] {
+            material = material.with_occlusion_texture(load(path), 1.0);
+        }
+    } else {
+        let height = image::open(common::asset(file_name)).unwrap().to_luma8();
+        let occlusion = image::DynamicImage::ImageLuma8(height.clone()).to_rgba8();
+        material = material
+            .with_normal_texture(pbr::normal_map_from_height(&height, 4.0), 1.0)
+            .with_occlusion_texture(occlusion, 0.5);
+    }
+
+    let vertex_data = create_vertices();
+    let u_mode = wgpu::AddressMode::Repeat;
+    let v_mode = wgpu::AddressMode::Repeat;
+    common::run(
+        &vertex_data,
+        common::Shading::Pbr(material),
+        &common::default_lights(),
+        &common::asset(file_name),
+        u_mode,
+        v_mode,
+        "pbr",
+    );
+}
@@ -0,0 +1,79 @@
+struct Uniforms {
+    model_mat : mat4x4<f32>,
+    view_project_mat : mat4x4<f32>,
+    normal_mat : mat4x4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+
+struct Input {
+    @location(0) pos: vec4<f32>,
+    @location(1) normal: vec4<f32>,
+    @location(2) uv: vec2<f32>,
+}
+
+struct Output {
+    @builtin(position) position: vec4<f32>,
+    @location(0) v_position: vec4<f32>,
+    @location(1) v_normal: vec4<f32>,
+    @location(2) v_uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(in: Input) -> Output {
+    var output: Output;
+    let m_position: vec4<f32> = uniforms.model_mat * in.pos;
+    output.v_position = m_position;
+    output.v_normal = uniforms.normal_mat * in.normal;
+    output.v_uv = in.uv;
+    output.position = uniforms.view_project_mat * m_position;
+    return output;
+}
+
+struct FragUniforms {
+    eye_position: vec4<f32>,
+};
+@group(0) @binding(1) var<uniform> frag_uniforms: FragUniforms;
+
+struct MaterialUniforms {
+    base_color: vec4<f32>,
+    metallic: f32,
+    roughness: f32,
+    normal_scale: f32,
+    occlusion_strength: f32,
+    ambient: f32,
+    is_two_side: i32,
+};
+@group(0) @binding(2) var<uniform> material: MaterialUniforms;
+@group(1) @binding(0) var base_color_texture: texture_2d<f32>;
+@group(1) @binding(1) var texture_sampler: sampler;
+@group(1) @binding(2) var metallic_roughness_texture: texture_2d<f32>;
+@group(1) @binding(3) var normal_texture: texture_2d<f32>;
+@group(1) @binding(4) var occlusion_texture: texture_2d<f32>;
+
+@fragment
+fn fs_main(in: Output, @builtin(front_facing) front_facing: bool) -> @location(0) vec4<f32> {
+    let base_color = textureSample(base_color_texture, texture_sampler, in.v_uv) * material.base_color;
+    let metallic_roughness = textureSample(metallic_roughness_texture, texture_sampler, in.v_uv);
+    let occlusion = textureSample(occlusion_texture, texture_sampler, in.v_uv).r;
+    var tangent_normal = textureSample(normal_texture, texture_sampler, in.v_uv).xyz * 2.0 - 1.0;
+    tangent_normal = vec3(tangent_normal.xy * material.normal_scale, tangent_normal.z);
+
+    var N: vec3<f32> = normalize(in.v_normal.xyz);
+    if (material.is_two_side == 1 && !front_facing) {
+        N = -N;
+    }
+    let dp1 = dpdx(in.v_position.xyz);
+    let dp2 = dpdy(in.v_position.xyz);
+    let duv1 = dpdx(in.v_uv);
+    let duv2 = dpdy(in.v_uv);
+    N = perturb_normal(N, dp1, dp2, duv1, duv2, tangent_normal);
+    let V: vec3<f32> = normalize(frag_uniforms.eye_position.xyz - in.v_position.xyz);
+
+    let metallic = material.metallic * metallic_roughness.b;
+    let roughness = material.roughness * metallic_roughness.g;
+    let ao = mix(1.0, occlusion, material.occlusion_strength);
+    let direct = pbr_lights(in.v_position.xyz, N, V, base_color.rgb, metallic, roughness);
+    let final_color: vec3<f32> = direct + material.ambient * base_color.rgb * ao;
+    return vec4(final_color, 1.0);
+}
@@ -31,12 +31,11 @@ fn vs_main(in: Input) -> Output {
 }
 
 struct FragUniforms {
-    light_position: vec4<f32>,
     eye_position: vec4<f32>,
 };
 @group(0) @binding(1) var<uniform> frag_uniforms: FragUniforms;
 
-struct LightUniforms {
+struct MaterialUniforms {
     specular_color: vec4<f32>,
     ambient_intensity: f32,
     diffuse_intensity: f32,
@@ -44,7 +43,7 @@ struct LightUniforms {
     specular_shininess: f32,
     is_two_side: i32,
 };
-@group(0) @binding(2) var<uniform> light_uniforms: LightUniforms;
+@group(0) @binding(2) var<uniform> material: MaterialUniforms;
 @group(1) @binding(0) var texture_data: texture_2d<f32>;
 @group(1) @binding(1) var texture_sampler: sampler;
 
@@ -53,21 +52,10 @@ fn fs_main(in: Output) -> @location(0) vec4<f32> {
     let texture_color:vec4<f32> = textureSample(texture_data, texture_sampler, in.v_uv);
 
     let N: vec3<f32> = normalize(in.v_normal.xyz);
-    let L: vec3<f32> = normalize(frag_uniforms.light_position.xyz - in.v_position.xyz);
     let V: vec3<f32> = normalize(frag_uniforms.eye_position.xyz - in.v_position.xyz);
-    let H: vec3<f32> = normalize(L + V);
+    let terms = blinn_phong_lights(in.v_position.xyz, N, V, material.diffuse_intensity, material.specular_intensity, material.specular_shininess, material.is_two_side == 1);
 
-    // front side
-    var diffuse: f32 = light_uniforms.diffuse_intensity * max(dot(N, L), 0.0);
-    var specular: f32 = light_uniforms.specular_intensity * pow(max(dot(N, H), 0.0), light_uniforms.specular_shininess);
-
-    // back side
-    if (light_uniforms.is_two_side == 1) {
-        diffuse = diffuse + light_uniforms.diffuse_intensity * max(dot(-N, L), 0.0);
-        specular = specular + light_uniforms.specular_intensity * pow(max(dot(-N, H), 0.0), light_uniforms.specular_shininess);
-    }
-
-    let ambient: f32 = light_uniforms.ambient_intensity;
-    let final_color: vec3<f32> = texture_color.rgb * (ambient + diffuse) + light_uniforms.specular_color.xyz * specular;
+    let ambient: f32 = material.ambient_intensity;
+    let final_color: vec3<f32> = texture_color.rgb * (ambient + terms.diffuse) + material.specular_color.xyz * terms.specular;
     return vec4(final_color, 1.0);
-}
\ No newline at end of file
+}
@@ -0,0 +1,34 @@
+mod common;
+use wgpu_book::vertex_data;
+
+fn create_vertices() -> Vec<common::Vertex> {
+    let (pos, normal, uv) = vertex_data::torus_data(1.5, 0.4, 40, 15);
+    let mut data: Vec<common::Vertex> = Vec::with_capacity(pos.len());
+    for i in 0..pos.len() {
+        data.push(common::vertex(pos[i], normal[i], uv[i]));
+    }
+    data.to_vec()
+}
+
+fn main() {
+    let mut file_name = "red-brick-wall.png";
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 {
+        file_name = &args[1];
+    }
+
+    let vertex_data = create_vertices();
+    let material = common::material([1.0, 1.0, 0.0], 0.1, 0.8, 0.4, 30.0, 1);
+
+    let u_mode = wgpu::AddressMode::ClampToEdge;
+    let v_mode = wgpu::AddressMode::ClampToEdge;
+    common::run(
+        &vertex_data,
+        common::Shading::BlinnPhong(material),
+        &common::default_lights(),
+        &common::asset(file_name),
+        u_mode,
+        v_mode,
+        "torus",
+    );
+}
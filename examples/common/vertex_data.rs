@@ -1,100 +0,0 @@
-#[rustfmt::skip]
-#[allow(dead_code)]
-pub fn cube_data() -> (Vec<[i8; 3]>, Vec<[i8; 3]>, Vec<[i8; 2]>, Vec<[i8; 3]>) {
-    let positions = [
-        // front (0, 0, 1)
-        [-1, -1, 1], [1, -1, 1], [-1, 1, 1], [-1, 1, 1], [ 1, -1, 1], [ 1, 1, 1],
-        // right (1, 0, 0)
-        [ 1, -1, 1], [1, -1, -1], [ 1, 1, 1], [ 1, 1, 1], [ 1, -1, -1], [ 1, 1, -1],
-        // back (0, 0, -1)
-        [ 1, -1, -1], [-1, -1, -1], [1, 1, -1], [ 1, 1, -1], [-1, -1, -1], [-1, 1, -1],
-        // left (-1, 0, 0)
-        [-1, -1, -1], [-1, -1, 1], [-1, 1, -1], [-1, 1, -1], [-1, -1, 1], [-1, 1, 1],
-        // top (0, 1, 0)
-        [-1, 1, 1], [ 1, 1, 1], [-1, 1, -1], [-1, 1, -1], [ 1, 1, 1], [ 1, 1, -1],
-        // bottom (0, -1, 0)
-        [-1, -1, -1], [ 1, -1, -1], [-1, -1, 1], [-1, -1, 1], [ 1, -1, -1], [ 1, -1, 1],
-    ];
-
-    let colors = [
-        // front - blue
-        [0, 0, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1],
-        // right - red
-        [1, 0, 0], [1, 0, 0], [1, 0, 0], [1, 0, 0], [1, 0, 0], [1, 0, 0],
-        // back - yellow
-        [1, 1, 0], [1, 1, 0], [1, 1, 0], [1, 1, 0], [1, 1, 0], [1, 1, 0],
-        // left - aqua
-        [0, 1, 1], [0, 1, 1], [0, 1, 1], [0, 1, 1], [0, 1, 1], [0, 1, 1],
-        // top - green
-        [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0],
-        // bottom - fuchsia
-        [1, 0, 1], [1, 0, 1], [1, 0, 1], [1, 0, 1], [1, 0, 1], [1, 0, 1],
-    ];
-
-    let uvs= [
-        // front
-        [0, 0], [1, 0], [0, 1], [0, 1], [1, 0], [1, 1],
-        // right
-        [0, 0], [1, 0], [0, 1], [0, 1], [1, 0], [1, 1],
-        // back
-        [0, 0], [1, 0], [0, 1], [0, 1], [1, 0], [1, 1],
-        // left
-        [0, 0], [1, 0], [0, 1], [0, 1], [1, 0], [1, 1],
-        // top
-        [0, 0], [1, 0], [0, 1], [0, 1], [1, 0], [1, 1],
-        // bottom
-        [0, 0], [1, 0], [0, 1], [0, 1], [1, 0], [1, 1],
-    ];
-
-    let normals = [
-        // front
-        [0, 0, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1],
-        // right
-        [1, 0, 0], [1, 0, 0], [1, 0, 0], [1, 0, 0], [1, 0, 0], [1, 0, 0],
-        // back
-        [0, 0, -1], [0, 0, -1], [0, 0, -1], [0, 0, -1], [0, 0, -1], [0, 0, -1],
-        // left
-        [-1, 0, 0], [-1, 0, 0], [-1, 0, 0], [-1, 0, 0], [-1, 0, 0], [-1, 0, 0],
-        // top
-        [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0],
-        // bottom
-        [0, -1, 0], [0, -1, 0], [0, -1, 0], [0, -1, 0], [0, -1, 0], [0, -1, 0],
-    ];
-    
-    (positions.to_vec(), colors.to_vec(), uvs.to_vec(), normals.to_vec())
-}
-
-#[allow(dead_code)]
-pub fn cube_data_index() -> (Vec<[i8; 3]>, Vec<[i8; 3]>, Vec<u16>) {
-    let positions = [
-        [-1, -1, 1],  // vertex a
-        [1, -1, 1],   // vertex b
-        [1, 1, 1],    // vertex c
-        [-1, 1, 1],   // vertex d
-        [-1, -1, -1], // vertex e
-        [1, -1, -1],  // vertex f
-        [1, 1, -1],   // vertex g
-        [-1, 1, -1],  // vertex h
-    ];
-
-    let colors = [
-        [0, 0, 1], // vertex a
-        [1, 0, 1], // vertex b
-        [1, 1, 1], // vertex c
-        [0, 1, 1], // vertex d
-        [0, 0, 0], // vertex e
-        [1, 0, 0], // vertex f
-        [1, 1, 0], // vertex g
-        [0, 1, 0], // vertex h
-    ];
-
-    let indices = [
-        0, 1, 2, 2, 3, 0, // front
-        1, 5, 6, 6, 2, 1, // right
-        4, 7, 6, 6, 5, 4, // back
-        0, 3, 7, 7, 4, 0, // left
-        3, 2, 6, 6, 7, 3, // top
-        0, 4, 5, 5, 1, 0, // bottom
-    ];
-    (positions.to_vec(), colors.to_vec(), indices.to_vec())
-}
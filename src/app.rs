@@ -0,0 +1,139 @@
+//! The application loop shared by the chapter examples.
+//!
+//! An example implements [`App`] with its scene-specific code and hands it to
+//! [`run`], which owns the [`InitWgpu`], the winit event loop, the animation
+//! clock and the surface error handling. With `WGPU_BOOK_PNG` set, [`run`]
+//! renders a single frame to PNG instead (see [`Headless`]).
+
+use crate::headless::Headless;
+use crate::transforms::{Frame, InitWgpu};
+use anyhow::Result;
+use std::time::{Duration, Instant};
+use winit::{
+    dpi::PhysicalSize,
+    event::{DeviceEvent, Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    window::WindowBuilder,
+};
+
+pub trait App: Sized {
+    /// What the scene is built from, e.g. vertex data and a material.
+    type Scene<'a>;
+
+    /// Creates the pipelines and buffers.
+    fn init(init: &InitWgpu, scene: Self::Scene<'_>) -> Self;
+
+    /// Called after the surface, the MSAA target and `init.size` were updated
+    /// to the new, non-zero `size`; update the projection and depth buffer here.
+    fn resize(&mut self, _init: &InitWgpu, _size: PhysicalSize<u32>) {}
+
+    /// Handles a window event; returns `true` if it was consumed.
+    fn input(&mut self, _init: &InitWgpu, _event: &WindowEvent) -> bool {
+        false
+    }
+
+    /// Handles raw device input such as mouse motion.
+    fn device_input(&mut self, _init: &InitWgpu, _event: &DeviceEvent) {}
+
+    /// Advances the animation; `elapsed` is the time since the start of the
+    /// animation and `dt` the time since the previous frame (zero for the
+    /// first one).
+    fn update(&mut self, init: &InitWgpu, elapsed: Duration, dt: Duration);
+
+    /// Records and submits the commands drawing into `frame`.
+    fn render(&mut self, init: &InitWgpu, frame: &mut Frame);
+
+    /// Window title replacing the one given to [`run`]; it is read again
+    /// after every consumed input.
+    fn title(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Opens a window titled `title` and runs `A` until the window is closed, or
+/// renders one frame to PNG when headless rendering is requested.
+pub fn run<A: App>(title: &str, scene: A::Scene<'_>) {
+    env_logger::init();
+    if let Some(headless) = Headless::from_env() {
+        render_png::<A>(&headless, scene).unwrap();
+        return;
+    }
+
+    let event_loop = EventLoop::new().unwrap();
+    let window = WindowBuilder::new()
+        .with_title(title)
+        .build(&event_loop)
+        .unwrap();
+    let mut init = pollster::block_on(InitWgpu::init_wgpu(&window));
+    let mut app = A::init(&init, scene);
+    if let Some(title) = app.title() {
+        window.set_title(&title);
+    }
+    let render_start_time = Instant::now();
+    let mut last_frame_time = render_start_time;
+
+    event_loop.set_control_flow(ControlFlow::Wait);
+    let _ = event_loop.run(move |event, elwt| match event {
+        Event::DeviceEvent { ref event, .. } => app.device_input(&init, event),
+        Event::WindowEvent { ref event, .. } if app.input(&init, event) => {
+            if let Some(title) = app.title() {
+                window.set_title(&title);
+            }
+        }
+        Event::WindowEvent {
+            event: WindowEvent::Resized(size),
+            ..
+        } => resize(&mut init, &mut app, size),
+        Event::WindowEvent {
+            event: WindowEvent::ScaleFactorChanged { .. },
+            ..
+        } => resize(&mut init, &mut app, window.inner_size()),
+        Event::WindowEvent {
+            event: WindowEvent::RedrawRequested,
+            ..
+        } if !init.is_minimized() => {
+            let now = Instant::now();
+            app.update(&init, now - render_start_time, now - last_frame_time);
+            last_frame_time = now;
+            match init.get_current_frame() {
+                Ok(mut frame) => {
+                    app.render(&init, &mut frame);
+                    frame.present();
+                }
+                // the surface has to be configured again, e.g. after a mode switch
+                Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                    let size = init.size;
+                    resize(&mut init, &mut app, size);
+                }
+                Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
+                Err(e) => eprintln!("{:?}", e),
+            }
+        }
+        Event::WindowEvent {
+            event: WindowEvent::CloseRequested,
+            ..
+        } => elwt.exit(),
+        // rendering pauses while the window is minimized
+        Event::AboutToWait if !init.is_minimized() => {
+            window.request_redraw();
+        }
+        _ => {}
+    });
+}
+
+fn resize<A: App>(init: &mut InitWgpu, app: &mut A, size: PhysicalSize<u32>) {
+    init.resize(size);
+    if !init.is_minimized() {
+        app.resize(init, size);
+    }
+}
+
+fn render_png<A: App>(headless: &Headless, scene: A::Scene<'_>) -> Result<()> {
+    let init = pollster::block_on(headless.init_wgpu())?;
+    let mut app = A::init(&init, scene);
+    app.update(&init, headless.time, Duration::ZERO);
+    let mut frame = init.get_current_frame()?;
+    app.render(&init, &mut frame);
+    frame.present();
+    init.save_png(&headless.path)
+}
@@ -0,0 +1,730 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::Value;
+use std::path::Path;
+
+// Evenly spaced stops of the built-in colormaps. Some sampled values happen to
+// be close to 1/pi, which clippy would flag.
+#[allow(clippy::approx_constant)]
+fn builtin_colors(name: &str) -> Option<&'static [[f32; 3]]> {
+    let colors: &'static [[f32; 3]] = match name {
+        "hsv" => &[
+            [1.0, 0.0, 0.0],
+            [1.0, 0.5, 0.0],
+            [0.97, 1.0, 0.01],
+            [0.0, 0.99, 0.04],
+            [0.0, 0.98, 0.52],
+            [0.0, 0.98, 1.0],
+            [0.01, 0.49, 1.0],
+            [0.03, 0.0, 0.99],
+            [1.0, 0.0, 0.96],
+            [1.0, 0.0, 0.49],
+            [1.0, 0.0, 0.02],
+        ],
+        "hot" => &[
+            [0.0, 0.0, 0.0],
+            [0.3, 0.0, 0.0],
+            [0.6, 0.0, 0.0],
+            [0.9, 0.0, 0.0],
+            [0.93, 0.27, 0.0],
+            [0.97, 0.55, 0.0],
+            [1.0, 0.82, 0.0],
+            [1.0, 0.87, 0.25],
+            [1.0, 0.91, 0.5],
+            [1.0, 0.96, 0.75],
+            [1.0, 1.0, 1.0],
+        ],
+        "cool" => &[
+            [0.49, 0.0, 0.7],
+            [0.45, 0.0, 0.85],
+            [0.42, 0.15, 0.89],
+            [0.38, 0.29, 0.93],
+            [0.27, 0.57, 0.91],
+            [0.0, 0.8, 0.77],
+            [0.0, 0.97, 0.57],
+            [0.0, 0.98, 0.46],
+            [0.0, 1.0, 0.35],
+            [0.16, 1.0, 0.03],
+            [0.58, 1.0, 0.0],
+        ],
+        "spring" => &[
+            [1.0, 0.0, 1.0],
+            [1.0, 0.1, 0.9],
+            [1.0, 0.2, 0.8],
+            [1.0, 0.3, 0.7],
+            [1.0, 0.4, 0.6],
+            [1.0, 0.5, 0.5],
+            [1.0, 0.6, 0.4],
+            [1.0, 0.7, 0.3],
+            [1.0, 0.8, 0.2],
+            [1.0, 0.9, 0.1],
+            [1.0, 1.0, 0.0],
+        ],
+        "summer" => &[
+            [0.0, 0.5, 0.4],
+            [0.1, 0.55, 0.4],
+            [0.2, 0.6, 0.4],
+            [0.3, 0.65, 0.4],
+            [0.4, 0.7, 0.4],
+            [0.5, 0.75, 0.4],
+            [0.6, 0.8, 0.4],
+            [0.7, 0.85, 0.4],
+            [0.8, 0.9, 0.4],
+            [0.9, 0.95, 0.4],
+            [1.0, 1.0, 0.4],
+        ],
+        "autumn" => &[
+            [1.0, 0.0, 0.0],
+            [1.0, 0.1, 0.0],
+            [1.0, 0.2, 0.0],
+            [1.0, 0.3, 0.0],
+            [1.0, 0.4, 0.0],
+            [1.0, 0.5, 0.0],
+            [1.0, 0.6, 0.0],
+            [1.0, 0.7, 0.0],
+            [1.0, 0.8, 0.0],
+            [1.0, 0.9, 0.0],
+            [1.0, 1.0, 0.0],
+        ],
+        "winter" => &[
+            [0.0, 0.0, 1.0],
+            [0.0, 0.1, 0.95],
+            [0.0, 0.2, 0.9],
+            [0.0, 0.3, 0.85],
+            [0.0, 0.4, 0.8],
+            [0.0, 0.5, 0.75],
+            [0.0, 0.6, 0.7],
+            [0.0, 0.7, 0.65],
+            [0.0, 0.8, 0.6],
+            [0.0, 0.9, 0.55],
+            [0.0, 1.0, 0.5],
+        ],
+        "bone" => &[
+            [0.0, 0.0, 0.0],
+            [0.08, 0.08, 0.11],
+            [0.16, 0.16, 0.23],
+            [0.25, 0.25, 0.34],
+            [0.33, 0.33, 0.45],
+            [0.41, 0.44, 0.54],
+            [0.5, 0.56, 0.62],
+            [0.58, 0.67, 0.7],
+            [0.66, 0.78, 0.78],
+            [0.83, 0.89, 0.89],
+            [1.0, 1.0, 1.0],
+        ],
+        "cooper" => &[
+            [0.0, 0.0, 0.0],
+            [0.13, 0.08, 0.05],
+            [0.25, 0.16, 0.1],
+            [0.38, 0.24, 0.15],
+            [0.5, 0.31, 0.2],
+            [0.62, 0.39, 0.25],
+            [0.75, 0.47, 0.3],
+            [0.87, 0.55, 0.35],
+            [1.0, 0.63, 0.4],
+            [1.0, 0.71, 0.45],
+            [1.0, 0.78, 0.5],
+        ],
+        "greys" => &[
+            [0.0, 0.0, 0.0],
+            [0.1, 0.1, 0.1],
+            [0.2, 0.2, 0.2],
+            [0.3, 0.3, 0.3],
+            [0.4, 0.4, 0.4],
+            [0.5, 0.5, 0.5],
+            [0.6, 0.6, 0.6],
+            [0.7, 0.7, 0.7],
+            [0.8, 0.8, 0.8],
+            [0.9, 0.9, 0.9],
+            [1.0, 1.0, 1.0],
+        ],
+        "jet" => &[
+            [0.0, 0.0, 0.51],
+            [0.0, 0.24, 0.67],
+            [0.01, 0.49, 0.78],
+            [0.01, 0.75, 0.89],
+            [0.02, 1.0, 1.0],
+            [0.51, 1.0, 0.5],
+            [1.0, 1.0, 0.0],
+            [0.99, 0.67, 0.0],
+            [0.99, 0.33, 0.0],
+            [0.98, 0.0, 0.0],
+            [0.5, 0.0, 0.0],
+        ],
+        "viridis" => &[
+            [0.267, 0.004, 0.329],
+            [0.282, 0.094, 0.416],
+            [0.278, 0.173, 0.478],
+            [0.259, 0.247, 0.522],
+            [0.227, 0.318, 0.545],
+            [0.196, 0.384, 0.553],
+            [0.173, 0.443, 0.557],
+            [0.149, 0.506, 0.557],
+            [0.125, 0.565, 0.549],
+            [0.122, 0.624, 0.533],
+            [0.153, 0.678, 0.502],
+            [0.239, 0.733, 0.451],
+            [0.361, 0.784, 0.384],
+            [0.506, 0.827, 0.298],
+            [0.667, 0.859, 0.192],
+            [0.835, 0.886, 0.098],
+            [0.992, 0.906, 0.145],
+        ],
+        "plasma" => &[
+            [0.051, 0.031, 0.529],
+            [0.188, 0.020, 0.588],
+            [0.294, 0.008, 0.631],
+            [0.396, 0.000, 0.655],
+            [0.490, 0.012, 0.659],
+            [0.580, 0.063, 0.631],
+            [0.663, 0.133, 0.584],
+            [0.733, 0.204, 0.529],
+            [0.796, 0.275, 0.471],
+            [0.851, 0.345, 0.416],
+            [0.898, 0.420, 0.361],
+            [0.937, 0.498, 0.306],
+            [0.969, 0.580, 0.251],
+            [0.988, 0.671, 0.200],
+            [0.992, 0.765, 0.153],
+            [0.973, 0.867, 0.141],
+            [0.941, 0.976, 0.129],
+        ],
+        "inferno" => &[
+            [0.000, 0.000, 0.016],
+            [0.039, 0.027, 0.137],
+            [0.125, 0.047, 0.286],
+            [0.235, 0.035, 0.392],
+            [0.337, 0.059, 0.427],
+            [0.439, 0.098, 0.431],
+            [0.537, 0.133, 0.412],
+            [0.635, 0.169, 0.380],
+            [0.733, 0.212, 0.329],
+            [0.816, 0.271, 0.267],
+            [0.890, 0.349, 0.196],
+            [0.941, 0.443, 0.118],
+            [0.976, 0.549, 0.035],
+            [0.984, 0.667, 0.059],
+            [0.973, 0.788, 0.196],
+            [0.945, 0.910, 0.396],
+            [0.988, 1.000, 0.643],
+        ],
+        "magma" => &[
+            [0.000, 0.000, 0.016],
+            [0.035, 0.027, 0.129],
+            [0.110, 0.063, 0.275],
+            [0.208, 0.063, 0.416],
+            [0.314, 0.071, 0.482],
+            [0.412, 0.110, 0.502],
+            [0.510, 0.145, 0.506],
+            [0.608, 0.180, 0.498],
+            [0.710, 0.212, 0.475],
+            [0.812, 0.251, 0.435],
+            [0.898, 0.314, 0.388],
+            [0.957, 0.412, 0.361],
+            [0.984, 0.529, 0.376],
+            [0.992, 0.647, 0.443],
+            [0.996, 0.761, 0.529],
+            [0.992, 0.878, 0.631],
+            [0.988, 0.992, 0.749],
+        ],
+        "cividis" => &[
+            [0.000, 0.125, 0.318],
+            [0.004, 0.169, 0.392],
+            [0.067, 0.212, 0.424],
+            [0.149, 0.255, 0.427],
+            [0.235, 0.302, 0.427],
+            [0.314, 0.345, 0.427],
+            [0.384, 0.392, 0.431],
+            [0.443, 0.435, 0.443],
+            [0.498, 0.482, 0.455],
+            [0.549, 0.529, 0.467],
+            [0.600, 0.580, 0.471],
+            [0.663, 0.631, 0.463],
+            [0.729, 0.682, 0.439],
+            [0.808, 0.737, 0.404],
+            [0.886, 0.796, 0.357],
+            [0.953, 0.855, 0.306],
+            [0.992, 0.914, 0.271],
+        ],
+        "turbo" => &[
+            [0.133, 0.090, 0.106],
+            [0.286, 0.243, 0.682],
+            [0.267, 0.412, 0.933],
+            [0.196, 0.580, 0.969],
+            [0.145, 0.737, 0.882],
+            [0.161, 0.863, 0.733],
+            [0.247, 0.949, 0.576],
+            [0.396, 0.988, 0.431],
+            [0.580, 0.980, 0.314],
+            [0.769, 0.922, 0.231],
+            [0.925, 0.816, 0.176],
+            [1.000, 0.675, 0.141],
+            [1.000, 0.510, 0.114],
+            [0.937, 0.337, 0.082],
+            [0.792, 0.180, 0.047],
+            [0.639, 0.071, 0.008],
+            [0.565, 0.047, 0.000],
+        ],
+        "rdbu" => &[
+            [0.404, 0.000, 0.122],
+            [0.580, 0.063, 0.153],
+            [0.722, 0.176, 0.208],
+            [0.816, 0.337, 0.290],
+            [0.894, 0.510, 0.408],
+            [0.949, 0.667, 0.549],
+            [0.976, 0.796, 0.706],
+            [0.976, 0.894, 0.843],
+            [0.945, 0.937, 0.929],
+            [0.867, 0.918, 0.945],
+            [0.749, 0.859, 0.918],
+            [0.596, 0.776, 0.875],
+            [0.420, 0.671, 0.816],
+            [0.263, 0.553, 0.753],
+            [0.165, 0.439, 0.682],
+            [0.086, 0.318, 0.553],
+            [0.016, 0.184, 0.376],
+        ],
+        "brbg" => &[
+            [0.329, 0.188, 0.020],
+            [0.463, 0.271, 0.035],
+            [0.596, 0.369, 0.078],
+            [0.714, 0.486, 0.176],
+            [0.808, 0.631, 0.333],
+            [0.878, 0.765, 0.518],
+            [0.933, 0.863, 0.690],
+            [0.957, 0.922, 0.831],
+            [0.929, 0.945, 0.918],
+            [0.839, 0.929, 0.914],
+            [0.702, 0.882, 0.855],
+            [0.533, 0.804, 0.765],
+            [0.353, 0.694, 0.655],
+            [0.192, 0.569, 0.533],
+            [0.067, 0.447, 0.416],
+            [0.008, 0.337, 0.298],
+            [0.000, 0.231, 0.184],
+        ],
+        "piyg" => &[
+            [0.557, 0.004, 0.322],
+            [0.686, 0.075, 0.427],
+            [0.788, 0.212, 0.537],
+            [0.855, 0.408, 0.655],
+            [0.906, 0.584, 0.765],
+            [0.945, 0.722, 0.855],
+            [0.973, 0.831, 0.910],
+            [0.976, 0.906, 0.945],
+            [0.961, 0.949, 0.937],
+            [0.922, 0.957, 0.863],
+            [0.847, 0.933, 0.733],
+            [0.737, 0.882, 0.561],
+            [0.608, 0.808, 0.392],
+            [0.475, 0.714, 0.255],
+            [0.353, 0.612, 0.165],
+            [0.247, 0.502, 0.118],
+            [0.149, 0.388, 0.094],
+        ],
+        "puor" => &[
+            [0.494, 0.227, 0.031],
+            [0.624, 0.302, 0.027],
+            [0.741, 0.388, 0.039],
+            [0.847, 0.494, 0.098],
+            [0.929, 0.612, 0.235],
+            [0.976, 0.733, 0.427],
+            [0.992, 0.831, 0.624],
+            [0.980, 0.906, 0.800],
+            [0.953, 0.933, 0.918],
+            [0.890, 0.890, 0.933],
+            [0.804, 0.800, 0.890],
+            [0.710, 0.686, 0.827],
+            [0.596, 0.557, 0.745],
+            [0.482, 0.404, 0.655],
+            [0.373, 0.235, 0.561],
+            [0.271, 0.098, 0.439],
+            [0.176, 0.000, 0.294],
+        ],
+        "seismic" => &[
+            [0.0, 0.0, 0.3],
+            [0.0, 0.0, 1.0],
+            [1.0, 1.0, 1.0],
+            [1.0, 0.0, 0.0],
+            [0.5, 0.0, 0.0],
+        ],
+        "bwr" => &[[0.0, 0.0, 1.0], [1.0, 1.0, 1.0], [1.0, 0.0, 0.0]],
+        _ => return None,
+    };
+    Some(colors)
+}
+
+/// Names of the built-in colormaps. Any of them can be reversed by adding
+/// `_r`, e.g. `jet_r`.
+pub const COLORMAP_NAMES: [&str; 23] = [
+    "jet", "hsv", "hot", "cool", "spring", "summer", "autumn", "winter", "bone", "cooper", "greys",
+    "viridis", "plasma", "inferno", "magma", "cividis", "turbo", "rdbu", "brbg", "piyg", "puor",
+    "seismic", "bwr",
+];
+
+/// A colormap given by color stops at increasing positions in `[0, 1]`;
+/// colors between stops are interpolated linearly.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Colormap {
+    stops: Vec<(f32, [f32; 3])>,
+}
+
+impl Colormap {
+    /// Creates a colormap from `(position, color)` stops. The positions must
+    /// be finite and non-decreasing; they are rescaled so the first stop sits
+    /// at 0 and the last at 1. The color components must lie in `[0, 1]`.
+    pub fn new(mut stops: Vec<(f32, [f32; 3])>) -> Result<Self> {
+        if stops.is_empty() {
+            bail!("a colormap needs at least one color stop");
+        }
+        if stops.iter().any(|(p, _)| !p.is_finite()) {
+            bail!("colormap stop positions must be finite");
+        }
+        if stops.windows(2).any(|w| w[1].0 < w[0].0) {
+            bail!("colormap stop positions must not decrease");
+        }
+        if let Some((_, c)) = stops
+            .iter()
+            .find(|(_, c)| c.iter().any(|x| !(0.0..=1.0).contains(x)))
+        {
+            bail!("colormap colors must lie in [0, 1], found {:?}", c);
+        }
+
+        let (first, last) = (stops[0].0, stops[stops.len() - 1].0);
+        if last > first {
+            for (p, _) in stops.iter_mut() {
+                *p = (*p - first) / (last - first);
+            }
+        } else {
+            stops[0].0 = 0.0;
+        }
+        Ok(Self { stops })
+    }
+
+    /// Creates a colormap from evenly spaced colors.
+    pub fn uniform(colors: &[[f32; 3]]) -> Result<Self> {
+        let n = colors.len().max(2) - 1;
+        Self::new(
+            colors
+                .iter()
+                .enumerate()
+                .map(|(i, &c)| (i as f32 / n as f32, c))
+                .collect(),
+        )
+    }
+
+    /// Looks up a built-in colormap by name (case-insensitive); a trailing
+    /// `_r` reverses it.
+    pub fn from_name(name: &str) -> Result<Self> {
+        let lower = name.to_ascii_lowercase();
+        let (base, reversed) = match lower.strip_suffix("_r") {
+            Some(base) => (base, true),
+            None => (lower.as_str(), false),
+        };
+        let colors = builtin_colors(base).ok_or_else(|| {
+            anyhow!(
+                "unknown colormap '{}', expected one of: {}",
+                name,
+                COLORMAP_NAMES.join(", ")
+            )
+        })?;
+        let colormap = Self::uniform(colors)?;
+        Ok(if reversed {
+            colormap.reversed()
+        } else {
+            colormap
+        })
+    }
+
+    /// Treats `arg` as a colormap file if such a file exists and as a
+    /// built-in name otherwise.
+    pub fn from_name_or_file(arg: &str) -> Result<Self> {
+        if Path::new(arg).is_file() {
+            Self::from_file(arg)
+        } else {
+            Self::from_name(arg)
+        }
+    }
+
+    /// Loads a colormap file. The format follows the extension: `.json` for
+    /// ParaView/matplotlib exports, `.png` (or any other image format) for a
+    /// gradient image, and text with one stop per line for everything else.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        let load = || match extension.as_deref() {
+            Some("json") => Self::from_json(&std::fs::read_to_string(path)?),
+            Some("txt" | "csv" | "tsv" | "dat") | None => {
+                Self::from_text(&std::fs::read_to_string(path)?)
+            }
+            Some(_) => Self::from_image(&image::open(path)?.to_rgb8()),
+        };
+        load().with_context(|| format!("cannot load colormap from {}", path.display()))
+    }
+
+    /// Parses stops from text, one per line: either `r g b` for evenly spaced
+    /// colors or `position r g b`. Values may be separated by commas,
+    /// semicolons or whitespace; colors are in `[0, 1]`, or in `[0, 255]` if
+    /// any component is above 1. Empty lines, `#` comments and header lines
+    /// before the first stop are skipped.
+    pub fn from_text(text: &str) -> Result<Self> {
+        let mut rows = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line
+                .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+                .filter(|f| !f.is_empty())
+                .collect();
+            let values: Result<Vec<f32>, _> = fields.iter().map(|f| f.parse::<f32>()).collect();
+            match values {
+                Ok(values) => rows.push(values),
+                Err(_) if rows.is_empty() => continue,
+                Err(e) => bail!("line {}: {}", i + 1, e),
+            }
+        }
+
+        let columns = rows.first().map_or(0, Vec::len);
+        if columns != 3 && columns != 4 {
+            bail!("expected 3 (r g b) or 4 (position r g b) values per line");
+        }
+        if let Some(row) = rows.iter().find(|r| r.len() != columns) {
+            bail!("expected {} values per line, found {:?}", columns, row);
+        }
+        let scale = if rows
+            .iter()
+            .any(|r| r[columns - 3..].iter().any(|&c| c > 1.0))
+        {
+            1.0 / 255.0
+        } else {
+            1.0
+        };
+        let scaled = |row: &[f32]| [row[0], row[1], row[2]].map(|c| c * scale);
+
+        if columns == 3 {
+            let colors: Vec<[f32; 3]> = rows.iter().map(|r| scaled(r)).collect();
+            Self::uniform(&colors)
+        } else {
+            Self::new(rows.iter().map(|r| (r[0], scaled(&r[1..]))).collect())
+        }
+    }
+
+    /// Parses a JSON colormap export. Supported layouts:
+    ///
+    /// - ParaView presets: an object (or an array whose first entry is an
+    ///   object) with `RGBPoints` as a flat `[x, r, g, b, ...]` list
+    /// - matplotlib segment data: an object with `red`, `green` and `blue`
+    ///   lists of `[x, y0, y1]` anchors
+    /// - matplotlib listed colors: an object with `colors`, or a bare array,
+    ///   of `[r, g, b]` (or `[r, g, b, a]`) entries
+    pub fn from_json(text: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(text)?;
+        let value = match &value {
+            Value::Array(items) if items.first().is_some_and(Value::is_object) => &items[0],
+            _ => &value,
+        };
+
+        if let Some(points) = value.get("RGBPoints") {
+            let points = numbers(points)?;
+            if points.len() % 4 != 0 {
+                bail!("RGBPoints must hold x, r, g, b quadruples");
+            }
+            return Self::new(
+                points
+                    .chunks_exact(4)
+                    .map(|p| (p[0], [p[1], p[2], p[3]]))
+                    .collect(),
+            );
+        }
+
+        if let (Some(red), Some(green), Some(blue)) =
+            (value.get("red"), value.get("green"), value.get("blue"))
+        {
+            let [red, green, blue] = [red, green, blue].map(anchors);
+            return Self::from_segment_data([red?, green?, blue?]);
+        }
+
+        let colors = value.get("colors").unwrap_or(value);
+        let colors = colors
+            .as_array()
+            .ok_or_else(|| anyhow!("no RGBPoints, segment data or color list found"))?
+            .iter()
+            .map(|c| match numbers(c)?.as_slice() {
+                [r, g, b] | [r, g, b, _] => Ok([*r, *g, *b]),
+                _ => bail!("expected [r, g, b] colors, found {}", c),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::uniform(&colors)
+    }
+
+    /// Reads the colors of the first row of a gradient image, such as a
+    /// 1-pixel-high PNG, from left to right.
+    pub fn from_image(image: &image::RgbImage) -> Result<Self> {
+        if image.width() == 0 || image.height() == 0 {
+            bail!("the colormap image is empty");
+        }
+        let colors: Vec<[f32; 3]> = (0..image.width())
+            .map(|x| image.get_pixel(x, 0).0.map(|c| c as f32 / 255.0))
+            .collect();
+        Self::uniform(&colors)
+    }
+
+    // Merges per-channel anchors into stops; a channel that jumps at x
+    // (y0 != y1) gives two stops at the same position.
+    fn from_segment_data(channels: [Vec<[f32; 3]>; 3]) -> Result<Self> {
+        let mut xs: Vec<f32> = channels.iter().flatten().map(|a| a[0]).collect();
+        xs.sort_by(f32::total_cmp);
+        xs.dedup();
+
+        let mut stops = Vec::with_capacity(2 * xs.len());
+        for &x in &xs {
+            let left = channels.each_ref().map(|c| segment_value(c, x, true));
+            let right = channels.each_ref().map(|c| segment_value(c, x, false));
+            stops.push((x, left));
+            if right != left {
+                stops.push((x, right));
+            }
+        }
+        Self::new(stops)
+    }
+
+    pub fn stops(&self) -> &[(f32, [f32; 3])] {
+        &self.stops
+    }
+
+    pub fn reversed(&self) -> Self {
+        let stops = self
+            .stops
+            .iter()
+            .rev()
+            .map(|&(p, c)| (1.0 - p, c))
+            .collect();
+        Self { stops }
+    }
+
+    /// Color at `t` in `[0, 1]`; values outside are clamped.
+    pub fn color(&self, t: f32) -> [f32; 3] {
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        let i = self.stops.partition_point(|&(p, _)| p <= t);
+        if i == 0 {
+            return self.stops[0].1;
+        }
+        if i == self.stops.len() {
+            return self.stops[i - 1].1;
+        }
+        let (p0, a) = self.stops[i - 1];
+        let (p1, b) = self.stops[i];
+        let s = (t - p0) / (p1 - p0);
+        [
+            a[0] + (b[0] - a[0]) * s,
+            a[1] + (b[1] - a[1]) * s,
+            a[2] + (b[2] - a[2]) * s,
+        ]
+    }
+
+    /// Color of `t` within `[min, max]`.
+    pub fn color_lerp(&self, min: f32, max: f32, t: f32) -> [f32; 3] {
+        let tn = if max > min {
+            (t - min) / (max - min)
+        } else {
+            0.0
+        };
+        self.color(tn)
+    }
+
+    /// Samples the colormap at `width` evenly spaced points as RGBA8 texels,
+    /// for uploading it as a lookup texture.
+    pub fn texels(&self, width: u32) -> Vec<[u8; 4]> {
+        (0..width)
+            .map(|i| {
+                let t = i as f32 / (width.max(2) as f32 - 1.0);
+                let [r, g, b] = self
+                    .color(t)
+                    .map(|x| (x.clamp(0.0, 1.0) * 255.0).round() as u8);
+                [r, g, b, 255]
+            })
+            .collect()
+    }
+}
+
+// The name-based functions below predate `Colormap`. Like before, unknown
+// names fall back to `jet`.
+fn named_or_jet(colormap_name: &str) -> Colormap {
+    Colormap::from_name(colormap_name).unwrap_or_else(|_| Colormap::from_name("jet").unwrap())
+}
+
+/// Eleven evenly spaced colors of the named colormap.
+#[deprecated(note = "use `Colormap::from_name(name)?.stops()` instead")]
+pub fn colormap_data(colormap_name: &str) -> [[f32; 3]; 11] {
+    let colormap = named_or_jet(colormap_name);
+    std::array::from_fn(|i| colormap.color(i as f32 / 10.0))
+}
+
+/// Color of `t` within `[min, max]` in the named colormap.
+#[deprecated(note = "use `Colormap::from_name(name)?.color_lerp(min, max, t)` instead")]
+pub fn color_lerp(colormap_name: &str, min: f32, max: f32, t: f32) -> [f32; 3] {
+    named_or_jet(colormap_name).color_lerp(min, max, t)
+}
+
+/// Samples the named colormap as RGBA8 texels.
+#[deprecated(note = "use `Colormap::from_name(name)?.texels(width)` instead")]
+pub fn colormap_texels(colormap_name: &str, width: u32) -> Vec<[u8; 4]> {
+    named_or_jet(colormap_name).texels(width)
+}
+
+fn numbers(value: &Value) -> Result<Vec<f32>> {
+    value
+        .as_array()
+        .ok_or_else(|| anyhow!("expected an array of numbers, found {}", value))?
+        .iter()
+        .map(|v| {
+            v.as_f64()
+                .map(|x| x as f32)
+                .ok_or_else(|| anyhow!("expected a number, found {}", v))
+        })
+        .collect()
+}
+
+// `[x, y0, y1]` anchors of one matplotlib segment data channel, sorted by x.
+fn anchors(value: &Value) -> Result<Vec<[f32; 3]>> {
+    let mut anchors = value
+        .as_array()
+        .ok_or_else(|| anyhow!("expected a list of [x, y0, y1] anchors"))?
+        .iter()
+        .map(|a| match numbers(a)?.as_slice() {
+            [x, y0, y1] => Ok([*x, *y0, *y1]),
+            _ => bail!("expected an [x, y0, y1] anchor, found {}", a),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if anchors.is_empty() {
+        bail!("a segment data channel needs at least one anchor");
+    }
+    anchors.sort_by(|a, b| a[0].total_cmp(&b[0]));
+    Ok(anchors)
+}
+
+// Value of a channel at x, approaching from the left (`y0` of an anchor) or
+// from the right (`y1`).
+fn segment_value(anchors: &[[f32; 3]], x: f32, from_left: bool) -> f32 {
+    let i = anchors.partition_point(|a| a[0] < x);
+    if i < anchors.len() && anchors[i][0] == x {
+        return if from_left {
+            anchors[i][1]
+        } else {
+            anchors[i][2]
+        };
+    }
+    if i == 0 {
+        return anchors[0][1];
+    }
+    if i == anchors.len() {
+        return anchors[i - 1][2];
+    }
+    let (a, b) = (anchors[i - 1], anchors[i]);
+    let s = (x - a[0]) / (b[0] - a[0]);
+    a[2] + (b[1] - a[2]) * s
+}
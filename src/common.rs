@@ -0,0 +1,227 @@
+use crate::headless::Headless;
+use crate::msaa::{self, MsaaTarget};
+use anyhow::Result;
+use wgpu::{IndexFormat, InstanceDescriptor, PrimitiveTopology, ShaderSource};
+use winit::{
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    window::Window,
+};
+
+pub struct Inputs<'a> {
+    pub source: ShaderSource<'a>,
+    pub topology: PrimitiveTopology,
+    pub strip_index_format: Option<IndexFormat>,
+}
+
+pub async fn run(event_loop: EventLoop<()>, window: Window, inputs: Inputs<'_>, num_vertices: u32) {
+    let size = window.inner_size();
+    let instance = wgpu::Instance::new(InstanceDescriptor {
+        backends: wgpu::Backends::VULKAN,
+        dx12_shader_compiler: wgpu::Dx12Compiler::default(),
+    });
+
+    let surface = unsafe { instance.create_surface(&window) }.unwrap();
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::default(),
+            force_fallback_adapter: false,
+            compatible_surface: Some(&surface),
+        })
+        .await
+        .expect("Failed to find an appropriate adapter");
+
+    let (device, queue) = adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                features: msaa::device_features(&adapter),
+                limits: wgpu::Limits::default(),
+            },
+            None,
+        )
+        .await
+        .expect("Failed to create device");
+
+    let swapchain_capabilities = surface.get_capabilities(&adapter);
+    let swapchain_format = swapchain_capabilities.formats[0];
+
+    let mut config = wgpu::SurfaceConfiguration {
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        format: swapchain_format,
+        width: size.width,
+        height: size.height,
+        present_mode: wgpu::PresentMode::Fifo,
+        alpha_mode: swapchain_capabilities.alpha_modes[0],
+        view_formats: vec![],
+    };
+
+    surface.configure(&device, &config);
+
+    let sample_count = msaa::supported_sample_count(
+        &adapter,
+        &device,
+        swapchain_format,
+        &[],
+        msaa::sample_count_from_env(),
+    );
+    let mut msaa = MsaaTarget::new(
+        &device,
+        swapchain_format,
+        size.width,
+        size.height,
+        sample_count,
+    );
+    let render_pipeline = create_pipeline(&device, swapchain_format, sample_count, inputs);
+
+    event_loop.set_control_flow(ControlFlow::Wait);
+    let _ = event_loop.run(move |event, elwt| {
+        let _ = (&instance, &adapter);
+        match event {
+            Event::WindowEvent {
+                event: WindowEvent::Resized(_) | WindowEvent::ScaleFactorChanged { .. },
+                ..
+            } => {
+                // a minimized window has no size to configure; drawing pauses
+                // until it gets one again
+                let size = window.inner_size();
+                if size.width > 0 && size.height > 0 {
+                    config.width = size.width;
+                    config.height = size.height;
+                    surface.configure(&device, &config);
+                    msaa.resize(&device, size.width, size.height);
+                }
+            }
+            Event::WindowEvent {
+                event: WindowEvent::RedrawRequested,
+                ..
+            } if window.inner_size().width > 0 && window.inner_size().height > 0 => {
+                match surface.get_current_texture() {
+                    Ok(frame) => {
+                        let view = frame
+                            .texture
+                            .create_view(&wgpu::TextureViewDescriptor::default());
+                        draw(
+                            &device,
+                            &queue,
+                            &msaa,
+                            &view,
+                            &render_pipeline,
+                            num_vertices,
+                        );
+                        frame.present();
+                    }
+                    // the surface has to be configured again, e.g. after a mode switch
+                    Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                        surface.configure(&device, &config);
+                        window.request_redraw();
+                    }
+                    Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
+                    Err(e) => eprintln!("{:?}", e),
+                }
+            }
+            Event::WindowEvent {
+                event: WindowEvent::CloseRequested,
+                ..
+            } => elwt.exit(),
+            _ => {}
+        }
+    });
+}
+
+/// Renders a single frame offscreen and saves it as PNG.
+pub async fn render_png(inputs: Inputs<'_>, num_vertices: u32, headless: &Headless) -> Result<()> {
+    let init = headless.init_wgpu().await?;
+    let render_pipeline = create_pipeline(
+        &init.device,
+        init.config.format,
+        init.sample_count(),
+        inputs,
+    );
+    let frame = init.get_current_frame()?;
+    draw(
+        &init.device,
+        &init.queue,
+        &init.msaa,
+        &frame.view,
+        &render_pipeline,
+        num_vertices,
+    );
+    init.save_png(&headless.path)
+}
+
+fn create_pipeline(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    inputs: Inputs<'_>,
+) -> wgpu::RenderPipeline {
+    // load the shaders
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: inputs.source,
+    });
+
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: None,
+        bind_group_layouts: &[],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: None,
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            targets: &[Some(format.into())],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: inputs.topology,
+            strip_index_format: inputs.strip_index_format,
+            ..Default::default()
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            ..Default::default()
+        },
+        multiview: None,
+    })
+}
+
+fn draw(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    msaa: &MsaaTarget,
+    view: &wgpu::TextureView,
+    render_pipeline: &wgpu::RenderPipeline,
+    num_vertices: u32,
+) {
+    let mut encoder =
+        device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    {
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(msaa.color_attachment(
+                view,
+                wgpu::LoadOp::Clear(wgpu::Color {
+                    r: 0.05,
+                    g: 0.062,
+                    b: 0.08,
+                    a: 1.0,
+                }),
+            ))],
+            depth_stencil_attachment: None,
+        });
+        rpass.set_pipeline(render_pipeline);
+        rpass.draw(0..num_vertices, 0..1);
+    }
+
+    queue.submit(Some(encoder.finish()));
+}
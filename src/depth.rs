@@ -0,0 +1,184 @@
+use crate::headless;
+use cgmath::Matrix4;
+
+/// Depth buffer format and depth convention of the 3D chapters.
+///
+/// Read from the environment like [`crate::headless::Headless`], so it works
+/// for windowed and headless runs alike:
+///
+/// - `WGPU_BOOK_DEPTH`: `depth24plus` (default), `depth32float` or
+///   `depth24plusstencil8`
+/// - `WGPU_BOOK_REVERSE_Z`: `1` to map the near plane to depth 1 and the far
+///   plane to 0, which together with `depth32float` spreads the precision
+///   evenly over the distance
+///
+/// ```shell
+/// WGPU_BOOK_DEPTH=depth32float WGPU_BOOK_REVERSE_Z=1 cargo run --example ch08_cube
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepthConfig {
+    pub format: wgpu::TextureFormat,
+    pub reverse_z: bool,
+}
+
+impl Default for DepthConfig {
+    fn default() -> Self {
+        Self {
+            format: wgpu::TextureFormat::Depth24Plus,
+            reverse_z: false,
+        }
+    }
+}
+
+impl DepthConfig {
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Ok(name) = std::env::var("WGPU_BOOK_DEPTH") {
+            match Self::format_from_name(&name) {
+                Some(format) => config.format = format,
+                None => log::warn!("unknown depth format {:?}, using {:?}", name, config.format),
+            }
+        }
+        config.reverse_z = headless::env_flag("WGPU_BOOK_REVERSE_Z");
+        config
+    }
+
+    pub fn format_from_name(name: &str) -> Option<wgpu::TextureFormat> {
+        match name.to_ascii_lowercase().as_str() {
+            "depth24plus" => Some(wgpu::TextureFormat::Depth24Plus),
+            "depth32float" => Some(wgpu::TextureFormat::Depth32Float),
+            "depth24plusstencil8" => Some(wgpu::TextureFormat::Depth24PlusStencil8),
+            _ => None,
+        }
+    }
+
+    pub fn has_stencil(&self) -> bool {
+        self.format.has_stencil_aspect()
+    }
+
+    /// `LessEqual`, or `GreaterEqual` with reverse-Z.
+    pub fn compare(&self) -> wgpu::CompareFunction {
+        if self.reverse_z {
+            wgpu::CompareFunction::GreaterEqual
+        } else {
+            wgpu::CompareFunction::LessEqual
+        }
+    }
+
+    /// Depth of the far plane, which the buffer is cleared to.
+    pub fn clear_depth(&self) -> f32 {
+        if self.reverse_z {
+            0.0
+        } else {
+            1.0
+        }
+    }
+
+    /// The `depth_stencil` state of a pipeline drawing into the depth target.
+    pub fn depth_stencil_state(&self) -> wgpu::DepthStencilState {
+        wgpu::DepthStencilState {
+            format: self.format,
+            depth_write_enabled: true,
+            depth_compare: self.compare(),
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }
+    }
+
+    /// Adapts a projection matrix made for the `[0, 1]` depth range (e.g. by
+    /// [`crate::transforms::create_projection`]) to this convention; with
+    /// reverse-Z the depth becomes `1 - z`.
+    pub fn projection(&self, project_mat: Matrix4<f32>) -> Matrix4<f32> {
+        if self.reverse_z {
+            #[rustfmt::skip]
+            let reverse = Matrix4::new(
+                1.0, 0.0, 0.0, 0.0,
+                0.0, 1.0, 0.0, 0.0,
+                0.0, 0.0, -1.0, 0.0,
+                0.0, 0.0, 1.0, 1.0,
+            );
+            reverse * project_mat
+        } else {
+            project_mat
+        }
+    }
+}
+
+/// A depth texture the size of the surface. Create it once and call
+/// [`DepthTarget::resize`] when the surface changes size. `sample_count` must
+/// match the color target, see [`crate::msaa`].
+pub struct DepthTarget {
+    pub config: DepthConfig,
+    pub sample_count: u32,
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+}
+
+impl DepthTarget {
+    pub fn new(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        sample_count: u32,
+        config: DepthConfig,
+    ) -> Self {
+        let (texture, view) = Self::create(device, width, height, sample_count, config.format);
+        Self {
+            config,
+            sample_count,
+            texture,
+            view,
+        }
+    }
+
+    fn create(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        sample_count: u32,
+        format: wgpu::TextureFormat,
+    ) -> (wgpu::Texture, wgpu::TextureView) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            label: Some("Depth Texture"),
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (texture, view)
+    }
+
+    /// Recreates the texture if the size changed.
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        let size = self.texture.size();
+        if size.width != width.max(1) || size.height != height.max(1) {
+            (self.texture, self.view) =
+                Self::create(device, width, height, self.sample_count, self.config.format);
+        }
+    }
+
+    /// Attachment for a render pass that clears depth (and stencil) and
+    /// discards them at the end.
+    pub fn attachment(&self) -> wgpu::RenderPassDepthStencilAttachment<'_> {
+        let stencil_ops = self.config.has_stencil().then_some(wgpu::Operations {
+            load: wgpu::LoadOp::Clear(0),
+            store: false,
+        });
+        wgpu::RenderPassDepthStencilAttachment {
+            view: &self.view,
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Clear(self.config.clear_depth()),
+                store: false,
+            }),
+            stencil_ops,
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Writing a [`Mesh`] to a Wavefront OBJ ([`crate::obj::write`]), STL
+//! ([`crate::stl::write`]) or PLY ([`crate::ply::write`]) file, chosen by
+//! the file extension.
+//!
+//! The examples drawing generated meshes save theirs instead of opening a
+//! window when the environment asks for it:
+//!
+//! - `WGPU_BOOK_EXPORT`: output file ending in `.obj`, `.stl` or `.ply`
+//! - `WGPU_BOOK_EXPORT_ASCII`: `1` to write ASCII instead of binary STL and
+//!   PLY
+//!
+//! ```shell
+//! WGPU_BOOK_EXPORT=sinc.stl cargo run --example ch09_sinc
+//! ```
+
+use crate::mesh::Mesh;
+use crate::{headless, obj, ply, stl};
+use anyhow::{bail, Context, Result};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// How STL and PLY files are stored; OBJ files are always text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Ascii,
+    Binary,
+}
+
+/// Writes `mesh` to `path` in the format of its extension.
+pub fn save<P: AsRef<Path>>(mesh: &Mesh, path: P, encoding: Encoding) -> Result<()> {
+    let path = path.as_ref();
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if !["obj", "stl", "ply"].contains(&extension.as_str()) {
+        bail!(
+            "cannot export {}: expected an .obj, .stl or .ply file",
+            path.display()
+        );
+    }
+    let file =
+        std::fs::File::create(path).with_context(|| format!("cannot create {}", path.display()))?;
+    let mut w = BufWriter::new(file);
+    match extension.as_str() {
+        "obj" => obj::write(mesh, &mut w),
+        "stl" => stl::write(mesh, &mut w, encoding),
+        _ => ply::write(mesh, &mut w, encoding),
+    }
+    .and_then(|()| Ok(w.flush()?))
+    .with_context(|| format!("cannot write {}", path.display()))
+}
+
+/// Saves `mesh` to the file named by `WGPU_BOOK_EXPORT`, if it is set;
+/// returns whether it was.
+pub fn save_from_env(mesh: &Mesh) -> Result<bool> {
+    let Ok(path) = std::env::var("WGPU_BOOK_EXPORT") else {
+        return Ok(false);
+    };
+    let encoding = if headless::env_flag("WGPU_BOOK_EXPORT_ASCII") {
+        Encoding::Ascii
+    } else {
+        Encoding::Binary
+    };
+    save(mesh, &path, encoding)?;
+    log::info!("exported {} triangles to {}", mesh.indices.len() / 3, path);
+    Ok(true)
+}
@@ -0,0 +1,719 @@
+//! glTF 2.0 import: `.gltf` files with external or base64-embedded buffers
+//! and images, and binary `.glb` files.
+//!
+//! A [`GltfScene`] keeps the meshes, the node hierarchy and the materials of
+//! a file. Node transforms are composed like
+//! [`crate::transforms::create_transforms`], translation * rotation * scale,
+//! and [`GltfScene::flatten`] applies them to give one triangle mesh in world
+//! space, like [`crate::obj::ObjModel`]. Materials become [`PbrMaterial`]s
+//! with their textures decoded. Emission, texture transforms, cameras, skins,
+//! morph targets and animations are ignored.
+//!
+//! ```no_run
+//! use wgpu_book::gltf::GltfScene;
+//! let scene = GltfScene::load("examples/ch10/assets/scene.gltf").unwrap();
+//! let (mesh, materials) = scene.flatten();
+//! println!("{} triangles", materials.len());
+//! ```
+
+use crate::mesh::{Mesh, MeshVertex};
+use crate::pbr::PbrMaterial;
+use anyhow::{anyhow, bail, Context, Result};
+use cgmath::*;
+use image::RgbaImage;
+use serde_json::Value;
+use std::path::Path;
+
+const GLB_MAGIC: &[u8] = b"glTF";
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+const FLOAT: u64 = 5126;
+const BYTE: u64 = 5120;
+const UNSIGNED_BYTE: u64 = 5121;
+const SHORT: u64 = 5122;
+const UNSIGNED_SHORT: u64 = 5123;
+const UNSIGNED_INT: u64 = 5125;
+
+pub struct GltfPrimitive {
+    /// Triangle list in the space of the mesh. Without `NORMAL` the
+    /// triangles are flat, without `TEXCOORD_0` the texture coordinates are 0
+    /// and without `COLOR_0` the color is white.
+    pub mesh: Mesh,
+    pub material: Option<usize>,
+}
+
+pub struct GltfMesh {
+    pub name: Option<String>,
+    pub primitives: Vec<GltfPrimitive>,
+}
+
+pub struct GltfNode {
+    pub name: Option<String>,
+    /// Relative to the parent node: `matrix`, or translation * rotation *
+    /// scale.
+    pub transform: Matrix4<f32>,
+    pub mesh: Option<usize>,
+    pub children: Vec<usize>,
+}
+
+pub struct GltfScene {
+    pub meshes: Vec<GltfMesh>,
+    pub nodes: Vec<GltfNode>,
+    pub materials: Vec<PbrMaterial>,
+    /// Root nodes of the displayed scene: that of `scene`, else of the first
+    /// of `scenes`, else every node without a parent.
+    pub roots: Vec<usize>,
+}
+
+impl GltfScene {
+    /// Loads a `.gltf` or `.glb` file (told apart by content); external
+    /// buffers and images are looked up next to it.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let data =
+            std::fs::read(path).with_context(|| format!("cannot read {}", path.display()))?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        Self::from_slice(&data, dir).with_context(|| format!("cannot load {}", path.display()))
+    }
+
+    /// Parses the contents of a `.gltf` or `.glb` file; `dir` is where
+    /// external buffers and images are looked up.
+    pub fn from_slice(data: &[u8], dir: &Path) -> Result<Self> {
+        let (json, bin) = if data.starts_with(GLB_MAGIC) {
+            parse_glb(data)?
+        } else {
+            (data, None)
+        };
+        let json: Value = serde_json::from_slice(json)?;
+        let version = json["asset"]["version"].as_str().unwrap_or_default();
+        if !version.starts_with('2') {
+            bail!("unsupported glTF version {:?}", version);
+        }
+        let document = Document::new(json, bin, dir)?;
+
+        let meshes = array(&document.json, "meshes")
+            .iter()
+            .enumerate()
+            .map(|(i, mesh)| document.mesh(mesh).with_context(|| format!("mesh {}", i)))
+            .collect::<Result<_>>()?;
+        let materials = array(&document.json, "materials")
+            .iter()
+            .enumerate()
+            .map(|(i, material)| {
+                document
+                    .material(material)
+                    .with_context(|| format!("material {}", i))
+            })
+            .collect::<Result<_>>()?;
+        let nodes = array(&document.json, "nodes")
+            .iter()
+            .enumerate()
+            .map(|(i, node)| node_from_json(node).with_context(|| format!("node {}", i)))
+            .collect::<Result<Vec<_>>>()?;
+        let scene = Self {
+            roots: document.roots(&nodes)?,
+            meshes,
+            nodes,
+            materials,
+        };
+        scene.validate()?;
+        Ok(scene)
+    }
+
+    /// Every mesh of the scene with the world transform of its node.
+    pub fn instances(&self) -> Vec<(usize, Matrix4<f32>)> {
+        let mut instances = Vec::new();
+        let mut stack: Vec<(usize, Matrix4<f32>)> = self
+            .roots
+            .iter()
+            .rev()
+            .map(|&n| (n, Matrix4::identity()))
+            .collect();
+        while let Some((index, parent)) = stack.pop() {
+            let node = &self.nodes[index];
+            let world = parent * node.transform;
+            if let Some(mesh) = node.mesh {
+                instances.push((mesh, world));
+            }
+            stack.extend(node.children.iter().rev().map(|&c| (c, world)));
+        }
+        instances
+    }
+
+    /// All primitives of the scene transformed into world space as one
+    /// triangle list, with the material of every triangle.
+    pub fn flatten(&self) -> (Mesh, Vec<Option<usize>>) {
+        let mut mesh = Mesh::default();
+        let mut triangle_materials = Vec::new();
+        for (index, world) in self.instances() {
+            let normal_mat =
+                Matrix3::from_cols(world.x.truncate(), world.y.truncate(), world.z.truncate());
+            // a mirroring transform turns the triangles inside out
+            let mirrored = normal_mat.determinant() < 0.0;
+            let normal_mat = normal_mat
+                .invert()
+                .map_or(Matrix3::identity(), |m| m.transpose());
+            for primitive in &self.meshes[index].primitives {
+                let base = mesh.vertices.len() as u32;
+                mesh.vertices
+                    .extend(primitive.mesh.vertices.iter().map(|v| {
+                        let p = world * Vector3::from(v.position).extend(1.0);
+                        let n = normal_mat * Vector3::from(v.normal);
+                        MeshVertex {
+                            position: p.truncate().into(),
+                            normal: if n.magnitude2() > 0.0 {
+                                n.normalize().into()
+                            } else {
+                                v.normal
+                            },
+                            ..*v
+                        }
+                    }));
+                for tri in primitive.mesh.indices.chunks_exact(3) {
+                    let tri = if mirrored {
+                        [tri[0], tri[2], tri[1]]
+                    } else {
+                        [tri[0], tri[1], tri[2]]
+                    };
+                    mesh.indices.extend(tri.iter().map(|&i| base + i));
+                    triangle_materials.push(primitive.material);
+                }
+            }
+        }
+        (mesh, triangle_materials)
+    }
+
+    // indices must be in range and the nodes must form trees
+    fn validate(&self) -> Result<()> {
+        for (i, mesh) in self.meshes.iter().enumerate() {
+            for primitive in &mesh.primitives {
+                if primitive
+                    .material
+                    .is_some_and(|m| m >= self.materials.len())
+                {
+                    bail!("mesh {} uses a material that does not exist", i);
+                }
+            }
+        }
+        let mut parent = vec![None; self.nodes.len()];
+        for (i, node) in self.nodes.iter().enumerate() {
+            if node.mesh.is_some_and(|m| m >= self.meshes.len()) {
+                bail!("node {} uses a mesh that does not exist", i);
+            }
+            for &child in &node.children {
+                match parent.get(child) {
+                    None => bail!("node {} has a child that does not exist", i),
+                    Some(Some(_)) => bail!("node {} has more than one parent", child),
+                    Some(None) => parent[child] = Some(i),
+                }
+            }
+        }
+        for &root in &self.roots {
+            if root >= self.nodes.len() {
+                bail!("the scene has a node that does not exist");
+            }
+        }
+        for start in 0..self.nodes.len() {
+            let mut node = start;
+            for _ in 0..self.nodes.len() {
+                match parent[node] {
+                    Some(p) if p == start => bail!("node {} is its own ancestor", start),
+                    Some(p) => node = p,
+                    None => break,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The JSON and the binary chunk of a `.glb` file.
+fn parse_glb(data: &[u8]) -> Result<(&[u8], Option<&[u8]>)> {
+    let u32_at = |offset: usize| -> Result<u32> {
+        let bytes = data
+            .get(offset..offset + 4)
+            .ok_or_else(|| anyhow!("the GLB file is truncated"))?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    };
+    let version = u32_at(4)?;
+    if version != 2 {
+        bail!("unsupported GLB version {}", version);
+    }
+    let length = u32_at(8)? as usize;
+    if length > data.len() {
+        bail!("the GLB file is truncated");
+    }
+    let mut chunks = Vec::new();
+    let mut offset = 12;
+    while offset < length {
+        let chunk_length = u32_at(offset)? as usize;
+        let chunk_type = u32_at(offset + 4)?;
+        let chunk = data
+            .get(offset + 8..offset + 8 + chunk_length)
+            .ok_or_else(|| anyhow!("a GLB chunk is truncated"))?;
+        chunks.push((chunk_type, chunk));
+        // chunks are padded to 4 bytes
+        offset += 8 + chunk_length.div_ceil(4) * 4;
+    }
+    match chunks.as_slice() {
+        [(CHUNK_JSON, json), rest @ ..] => {
+            let bin = rest
+                .iter()
+                .find(|(t, _)| *t == CHUNK_BIN)
+                .map(|&(_, bin)| bin);
+            Ok((json, bin))
+        }
+        _ => bail!("the GLB file does not start with a JSON chunk"),
+    }
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value[key].as_array().map_or(&[], Vec::as_slice)
+}
+
+fn index(value: &Value, key: &str) -> Result<Option<usize>> {
+    match &value[key] {
+        Value::Null => Ok(None),
+        v => v
+            .as_u64()
+            .map(|i| Some(i as usize))
+            .ok_or_else(|| anyhow!("{} must be an index, found {}", key, v)),
+    }
+}
+
+fn floats<const N: usize>(value: &Value, key: &str, default: [f32; N]) -> Result<[f32; N]> {
+    match &value[key] {
+        Value::Null => Ok(default),
+        Value::Array(items) if items.len() == N => {
+            let mut result = default;
+            for (r, item) in result.iter_mut().zip(items) {
+                *r = item
+                    .as_f64()
+                    .ok_or_else(|| anyhow!("{} must hold numbers", key))?
+                    as f32;
+            }
+            Ok(result)
+        }
+        v => bail!("{} must be {} numbers, found {}", key, N, v),
+    }
+}
+
+fn float(value: &Value, key: &str, default: f32) -> Result<f32> {
+    match &value[key] {
+        Value::Null => Ok(default),
+        v => Ok(v
+            .as_f64()
+            .ok_or_else(|| anyhow!("{} must be a number, found {}", key, v))?
+            as f32),
+    }
+}
+
+fn node_from_json(node: &Value) -> Result<GltfNode> {
+    let transform = if node["matrix"].is_null() {
+        let [tx, ty, tz] = floats(node, "translation", [0.0; 3])?;
+        let [x, y, z, w] = floats(node, "rotation", [0.0, 0.0, 0.0, 1.0])?;
+        let [sx, sy, sz] = floats(node, "scale", [1.0; 3])?;
+        Matrix4::from_translation(Vector3::new(tx, ty, tz))
+            * Matrix4::from(Quaternion::new(w, x, y, z).normalize())
+            * Matrix4::from_nonuniform_scale(sx, sy, sz)
+    } else {
+        // column-major, like cgmath
+        let m = floats(node, "matrix", [0.0; 16])?;
+        Matrix4::new(
+            m[0], m[1], m[2], m[3], m[4], m[5], m[6], m[7], m[8], m[9], m[10], m[11], m[12], m[13],
+            m[14], m[15],
+        )
+    };
+    Ok(GltfNode {
+        name: node["name"].as_str().map(str::to_string),
+        transform,
+        mesh: index(node, "mesh")?,
+        children: array(node, "children")
+            .iter()
+            .map(|c| {
+                c.as_u64()
+                    .map(|c| c as usize)
+                    .ok_or_else(|| anyhow!("children must be indices"))
+            })
+            .collect::<Result<_>>()?,
+    })
+}
+
+/// Where an accessor's elements are: `count` elements of `components`
+/// values each, `stride` bytes apart from `offset` on.
+struct AccessorData<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    stride: usize,
+    count: usize,
+    components: usize,
+    component_type: u64,
+    normalized: bool,
+}
+
+impl AccessorData<'_> {
+    fn component(&self, element: usize, component: usize) -> f32 {
+        let size = component_size(self.component_type);
+        let at = self.offset + element * self.stride + component * size;
+        let b = &self.bytes[at..at + size];
+        let n = self.normalized;
+        match self.component_type {
+            FLOAT => f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+            UNSIGNED_BYTE if n => b[0] as f32 / 255.0,
+            UNSIGNED_BYTE => b[0] as f32,
+            BYTE if n => (b[0] as i8 as f32 / 127.0).max(-1.0),
+            BYTE => b[0] as i8 as f32,
+            UNSIGNED_SHORT if n => u16::from_le_bytes([b[0], b[1]]) as f32 / 65535.0,
+            UNSIGNED_SHORT => u16::from_le_bytes([b[0], b[1]]) as f32,
+            SHORT if n => (i16::from_le_bytes([b[0], b[1]]) as f32 / 32767.0).max(-1.0),
+            SHORT => i16::from_le_bytes([b[0], b[1]]) as f32,
+            _ => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32,
+        }
+    }
+
+    fn index(&self, element: usize) -> u32 {
+        let at = self.offset + element * self.stride;
+        let b = &self.bytes[at..];
+        match self.component_type {
+            UNSIGNED_BYTE => b[0] as u32,
+            UNSIGNED_SHORT => u16::from_le_bytes([b[0], b[1]]) as u32,
+            _ => u32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+        }
+    }
+}
+
+fn component_size(component_type: u64) -> usize {
+    match component_type {
+        BYTE | UNSIGNED_BYTE => 1,
+        SHORT | UNSIGNED_SHORT => 2,
+        _ => 4,
+    }
+}
+
+struct Document<'a> {
+    json: Value,
+    buffers: Vec<Vec<u8>>,
+    dir: &'a Path,
+}
+
+impl<'a> Document<'a> {
+    fn new(json: Value, bin: Option<&[u8]>, dir: &'a Path) -> Result<Self> {
+        let mut buffers = Vec::new();
+        for (i, buffer) in array(&json, "buffers").iter().enumerate() {
+            let data = match buffer["uri"].as_str() {
+                Some(uri) => read_uri(uri, dir).with_context(|| format!("buffer {}", i))?,
+                None if i == 0 => bin
+                    .ok_or_else(|| anyhow!("buffer 0 has no uri and there is no GLB chunk"))?
+                    .to_vec(),
+                None => bail!("buffer {} has no uri", i),
+            };
+            let length = index(buffer, "byteLength")?.unwrap_or(0);
+            if data.len() < length {
+                bail!("buffer {} holds {} of {} bytes", i, data.len(), length);
+            }
+            buffers.push(data);
+        }
+        Ok(Self { json, buffers, dir })
+    }
+
+    fn item(&self, key: &str, i: usize) -> Result<&Value> {
+        array(&self.json, key)
+            .get(i)
+            .ok_or_else(|| anyhow!("{} {} does not exist", key, i))
+    }
+
+    fn buffer_view(&self, i: usize) -> Result<(&[u8], Option<usize>)> {
+        let view = self.item("bufferViews", i)?;
+        let buffer =
+            index(view, "buffer")?.ok_or_else(|| anyhow!("buffer view {} has no buffer", i))?;
+        let buffer = self
+            .buffers
+            .get(buffer)
+            .ok_or_else(|| anyhow!("buffer {} does not exist", buffer))?;
+        let offset = index(view, "byteOffset")?.unwrap_or(0);
+        let length = index(view, "byteLength")?.unwrap_or(0);
+        let bytes = buffer
+            .get(offset..offset + length)
+            .ok_or_else(|| anyhow!("buffer view {} is out of range", i))?;
+        Ok((bytes, index(view, "byteStride")?))
+    }
+
+    fn accessor(&self, i: usize) -> Result<AccessorData<'_>> {
+        let accessor = self.item("accessors", i)?;
+        if !accessor["sparse"].is_null() {
+            bail!("accessor {} is sparse, which is not supported", i);
+        }
+        let count = index(accessor, "count")?.unwrap_or(0);
+        let components = match accessor["type"].as_str() {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") | Some("MAT2") => 4,
+            Some("MAT3") => 9,
+            Some("MAT4") => 16,
+            t => bail!("accessor {} has an unknown type {:?}", i, t),
+        };
+        let component_type = accessor["componentType"].as_u64().unwrap_or(0);
+        if ![
+            FLOAT,
+            BYTE,
+            UNSIGNED_BYTE,
+            SHORT,
+            UNSIGNED_SHORT,
+            UNSIGNED_INT,
+        ]
+        .contains(&component_type)
+        {
+            bail!(
+                "accessor {} has an unknown component type {}",
+                i,
+                component_type
+            );
+        }
+        let element_size = components * component_size(component_type);
+        let Some(view) = index(accessor, "bufferView")? else {
+            // without a buffer view all elements are zero
+            return Ok(AccessorData {
+                bytes: &[0; 64],
+                offset: 0,
+                stride: 0,
+                count,
+                components,
+                component_type,
+                normalized: false,
+            });
+        };
+        let (bytes, stride) = self.buffer_view(view)?;
+        let offset = index(accessor, "byteOffset")?.unwrap_or(0);
+        let stride = stride.unwrap_or(element_size);
+        if count > 0 && offset + stride * (count - 1) + element_size > bytes.len() {
+            bail!("accessor {} reads past the end of its buffer view", i);
+        }
+        Ok(AccessorData {
+            bytes,
+            offset,
+            stride,
+            count,
+            components,
+            component_type,
+            normalized: accessor["normalized"].as_bool().unwrap_or(false),
+        })
+    }
+
+    /// The first `N` components of every element, the rest filled from `fill`.
+    fn read<const N: usize>(&self, i: usize, fill: [f32; N]) -> Result<Vec<[f32; N]>> {
+        let data = self.accessor(i)?;
+        Ok((0..data.count)
+            .map(|e| {
+                let mut v = fill;
+                for (c, x) in v.iter_mut().enumerate().take(data.components) {
+                    *x = data.component(e, c);
+                }
+                v
+            })
+            .collect())
+    }
+
+    fn mesh(&self, mesh: &Value) -> Result<GltfMesh> {
+        let mut primitives = Vec::new();
+        for primitive in array(mesh, "primitives") {
+            if let Some(primitive) = self.primitive(primitive)? {
+                primitives.push(primitive);
+            }
+        }
+        Ok(GltfMesh {
+            name: mesh["name"].as_str().map(str::to_string),
+            primitives,
+        })
+    }
+
+    fn primitive(&self, primitive: &Value) -> Result<Option<GltfPrimitive>> {
+        let attributes = &primitive["attributes"];
+        let position =
+            index(attributes, "POSITION")?.ok_or_else(|| anyhow!("a primitive has no POSITION"))?;
+        let positions = self.read(position, [0.0; 3])?;
+        let n = positions.len();
+        let attribute = |name, fill| -> Result<Option<Vec<[f32; 4]>>> {
+            let Some(i) = index(attributes, name)? else {
+                return Ok(None);
+            };
+            let values = self.read(i, fill)?;
+            if values.len() != n {
+                bail!("{} has {} elements for {} positions", name, values.len(), n);
+            }
+            Ok(Some(values))
+        };
+        let normals = attribute("NORMAL", [0.0; 4])?;
+        let uvs = attribute("TEXCOORD_0", [0.0; 4])?;
+        let colors = attribute("COLOR_0", [1.0; 4])?;
+
+        let indices: Vec<u32> = match index(primitive, "indices")? {
+            Some(i) => {
+                let data = self.accessor(i)?;
+                if data.components != 1 || data.component_type == FLOAT {
+                    bail!("accessor {} cannot hold indices", i);
+                }
+                (0..data.count).map(|e| data.index(e)).collect()
+            }
+            None => (0..n as u32).collect(),
+        };
+        if indices.iter().any(|&i| i as usize >= n) {
+            bail!("a primitive has an index past its {} vertices", n);
+        }
+        let indices = match primitive["mode"].as_u64().unwrap_or(4) {
+            4 => indices[..indices.len() / 3 * 3].to_vec(),
+            // strips alternate the winding, fans share the first vertex
+            5 => (0..indices.len().saturating_sub(2))
+                .flat_map(|i| {
+                    if i % 2 == 0 {
+                        [indices[i], indices[i + 1], indices[i + 2]]
+                    } else {
+                        [indices[i], indices[i + 2], indices[i + 1]]
+                    }
+                })
+                .collect(),
+            6 => (1..indices.len().saturating_sub(1))
+                .flat_map(|i| [indices[i], indices[i + 1], indices[0]])
+                .collect(),
+            mode => {
+                log::warn!("skipping a primitive of points or lines (mode {})", mode);
+                return Ok(None);
+            }
+        };
+
+        let vertices = (0..n)
+            .map(|i| MeshVertex {
+                position: positions[i],
+                normal: normals
+                    .as_ref()
+                    .map_or([0.0; 3], |v| [v[i][0], v[i][1], v[i][2]]),
+                color: colors
+                    .as_ref()
+                    .map_or([1.0; 3], |v| [v[i][0], v[i][1], v[i][2]]),
+                uv: uvs.as_ref().map_or([0.0; 2], |v| [v[i][0], v[i][1]]),
+                scalar: 0.0,
+            })
+            .collect();
+        let mut mesh = Mesh { vertices, indices };
+        if normals.is_none() {
+            mesh = mesh.to_flat();
+        }
+        Ok(Some(GltfPrimitive {
+            mesh,
+            material: index(primitive, "material")?,
+        }))
+    }
+
+    fn material(&self, material: &Value) -> Result<PbrMaterial> {
+        let pbr = &material["pbrMetallicRoughness"];
+        let [r, g, b, a] = floats(pbr, "baseColorFactor", [1.0; 4])?;
+        let mut result = PbrMaterial::new(
+            [r, g, b],
+            float(pbr, "metallicFactor", 1.0)?,
+            float(pbr, "roughnessFactor", 1.0)?,
+        )
+        .with_two_side(material["doubleSided"].as_bool().unwrap_or(false));
+        result.base_color[3] = a;
+        if let Some(image) = self.texture(&pbr["baseColorTexture"])? {
+            result = result.with_base_color_texture(image);
+        }
+        if let Some(image) = self.texture(&pbr["metallicRoughnessTexture"])? {
+            result = result.with_metallic_roughness_texture(image);
+        }
+        let normal = &material["normalTexture"];
+        if let Some(image) = self.texture(normal)? {
+            result = result.with_normal_texture(image, float(normal, "scale", 1.0)?);
+        }
+        let occlusion = &material["occlusionTexture"];
+        if let Some(image) = self.texture(occlusion)? {
+            result = result.with_occlusion_texture(image, float(occlusion, "strength", 1.0)?);
+        }
+        Ok(result)
+    }
+
+    /// The image of a texture info object such as `baseColorTexture`.
+    fn texture(&self, info: &Value) -> Result<Option<RgbaImage>> {
+        let Some(texture) = index(info, "index")? else {
+            return Ok(None);
+        };
+        if index(info, "texCoord")?.unwrap_or(0) != 0 {
+            log::warn!(
+                "texture {} uses a second set of texture coordinates",
+                texture
+            );
+        }
+        let source = index(self.item("textures", texture)?, "source")?
+            .ok_or_else(|| anyhow!("texture {} has no image", texture))?;
+        let image = self.item("images", source)?;
+        let bytes = match (image["uri"].as_str(), index(image, "bufferView")?) {
+            (Some(uri), _) => read_uri(uri, self.dir),
+            (None, Some(view)) => Ok(self.buffer_view(view)?.0.to_vec()),
+            (None, None) => Err(anyhow!("the image has neither a uri nor a buffer view")),
+        };
+        let image = bytes
+            .and_then(|bytes| Ok(image::load_from_memory(&bytes)?.to_rgba8()))
+            .with_context(|| format!("image {}", source))?;
+        Ok(Some(image))
+    }
+
+    fn roots(&self, nodes: &[GltfNode]) -> Result<Vec<usize>> {
+        let scenes = array(&self.json, "scenes");
+        let scene = index(&self.json, "scene")?.unwrap_or(0);
+        if let Some(scene) = scenes.get(scene) {
+            return array(scene, "nodes")
+                .iter()
+                .map(|n| {
+                    n.as_u64()
+                        .map(|n| n as usize)
+                        .ok_or_else(|| anyhow!("scene nodes must be indices"))
+                })
+                .collect();
+        }
+        let mut is_child = vec![false; nodes.len()];
+        for &child in nodes.iter().flat_map(|n| &n.children) {
+            if let Some(c) = is_child.get_mut(child) {
+                *c = true;
+            }
+        }
+        Ok((0..nodes.len()).filter(|&i| !is_child[i]).collect())
+    }
+}
+
+/// The contents of a base64 `data:` URI or of a file relative to `dir`.
+fn read_uri(uri: &str, dir: &Path) -> Result<Vec<u8>> {
+    if let Some(data) = uri.strip_prefix("data:") {
+        let (_, payload) = data
+            .split_once(";base64,")
+            .ok_or_else(|| anyhow!("only base64 data URIs are supported"))?;
+        return decode_base64(payload);
+    }
+    let path = dir.join(uri.replace("%20", " "));
+    std::fs::read(&path).with_context(|| format!("cannot read {}", path.display()))
+}
+
+fn decode_base64(text: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3);
+    let mut bits = 0u32;
+    let mut count = 0;
+    for c in text
+        .bytes()
+        .filter(|&c| c != b'=' && !c.is_ascii_whitespace())
+    {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => bail!("invalid base64 character {:?}", c as char),
+        };
+        bits = bits << 6 | value as u32;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            bytes.push((bits >> count) as u8);
+        }
+    }
+    Ok(bytes)
+}
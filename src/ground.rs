@@ -0,0 +1,184 @@
+//! A gray ground plane that receives the shadows of the lit chapters.
+
+use crate::depth::DepthConfig;
+use crate::light::LIGHT_WGSL;
+use crate::shadow::ShadowMap;
+use cgmath::Matrix4;
+use wgpu::util::DeviceExt;
+
+/// A square in the plane `y = height`, lit by the same light array and shadow
+/// map as the rest of the scene.
+pub struct GroundPlane {
+    pipeline: wgpu::RenderPipeline,
+    vertex_buffer: wgpu::Buffer,
+    uniform_buffer: wgpu::Buffer,
+    eye_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl GroundPlane {
+    /// `lights_buffer` holds the chapter's [`crate::light::LightsUniform`];
+    /// `format`, `depth` and `sample_count` are those of the main render pass.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        depth: DepthConfig,
+        sample_count: u32,
+        height: f32,
+        half_size: f32,
+        eye_position: [f32; 3],
+        lights_buffer: &wgpu::Buffer,
+        shadow_map: &ShadowMap,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Ground Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                [LIGHT_WGSL, include_str!("ground.wgsl")].join("\n").into(),
+            ),
+        });
+
+        let (h, s) = (height, half_size);
+        let vertices: [[f32; 3]; 6] = [
+            [-s, h, -s],
+            [-s, h, s],
+            [s, h, s],
+            [s, h, s],
+            [s, h, -s],
+            [-s, h, -s],
+        ];
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Ground Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Ground Uniform Buffer"),
+            size: 64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let eye = [eye_position[0], eye_position[1], eye_position[2], 1.0];
+        let eye_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Ground Eye Buffer"),
+            contents: bytemuck::cast_slice(&eye),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let uniform_entry = |binding, visibility| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let [shadow_uniform, shadow_texture, shadow_sampler] = ShadowMap::layout_entries();
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                uniform_entry(0, wgpu::ShaderStages::VERTEX),
+                uniform_entry(1, wgpu::ShaderStages::FRAGMENT),
+                uniform_entry(3, wgpu::ShaderStages::FRAGMENT),
+                shadow_uniform,
+                shadow_texture,
+                shadow_sampler,
+            ],
+            label: Some("Ground Bind Group Layout"),
+        });
+        let [shadow_uniform, shadow_texture, shadow_sampler] = shadow_map.bind_group_entries();
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: eye_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: lights_buffer.as_entire_binding(),
+                },
+                shadow_uniform,
+                shadow_texture,
+                shadow_sampler,
+            ],
+            label: Some("Ground Bind Group"),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Ground Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Ground Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: 12,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x3],
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: Some(depth.depth_stencil_state()),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            vertex_buffer,
+            uniform_buffer,
+            eye_buffer,
+            bind_group,
+        }
+    }
+
+    pub fn update(&self, queue: &wgpu::Queue, view_project_mat: Matrix4<f32>) {
+        let view_project_ref: &[f32; 16] = view_project_mat.as_ref();
+        queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(view_project_ref),
+        );
+    }
+
+    /// Moves the eye the specular highlights are computed for.
+    pub fn set_eye_position(&self, queue: &wgpu::Queue, eye_position: [f32; 3]) {
+        let eye = [eye_position[0], eye_position[1], eye_position[2], 1.0];
+        queue.write_buffer(&self.eye_buffer, 0, bytemuck::cast_slice(&eye));
+    }
+
+    /// Draws the plane into a pass whose targets match the formats given to
+    /// [`GroundPlane::new`]. Changes the bound pipeline and bind group 0.
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.draw(0..6, 0..1);
+    }
+}
@@ -0,0 +1,32 @@
+struct Uniforms {
+    view_project_mat: mat4x4<f32>,
+};
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+
+struct Output {
+    @builtin(position) position: vec4<f32>,
+    @location(0) v_position: vec3<f32>,
+};
+
+@vertex
+fn vs_main(@location(0) pos: vec3<f32>) -> Output {
+    var output: Output;
+    output.v_position = pos;
+    output.position = uniforms.view_project_mat * vec4(pos, 1.0);
+    return output;
+}
+
+struct FragUniforms {
+    eye_position: vec4<f32>,
+};
+@group(0) @binding(1) var<uniform> frag_uniforms: FragUniforms;
+
+const GROUND_COLOR: vec3<f32> = vec3(0.5, 0.5, 0.5);
+
+@fragment
+fn fs_main(in: Output) -> @location(0) vec4<f32> {
+    let N = vec3(0.0, 1.0, 0.0);
+    let V = normalize(frag_uniforms.eye_position.xyz - in.v_position);
+    let terms = blinn_phong_lights(in.v_position, N, V, 0.8, 0.1, 10.0, false);
+    return vec4(GROUND_COLOR * (0.1 + terms.diffuse) + terms.specular, 1.0);
+}
@@ -0,0 +1,76 @@
+use crate::transforms::InitWgpu;
+use anyhow::Result;
+use std::time::Duration;
+
+/// Settings for rendering a single frame to PNG instead of opening a window.
+///
+/// Read from the environment so the chapter examples keep their own
+/// command-line arguments:
+///
+/// - `WGPU_BOOK_PNG`: output file; headless mode is enabled when it is set
+/// - `WGPU_BOOK_SIZE`: image size as `WIDTHxHEIGHT` (default `800x600`), with
+///   a lower- or uppercase `x`; a malformed or zero size is ignored with a
+///   warning
+/// - `WGPU_BOOK_TIME`: animation time in seconds (default `0`)
+/// - `WGPU_BOOK_FALLBACK`: `1` to force the fallback (software) adapter
+///
+/// ```shell
+/// WGPU_BOOK_PNG=cube.png WGPU_BOOK_FALLBACK=1 cargo run --example ch08_cube
+/// ```
+pub struct Headless {
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+    pub time: Duration,
+    pub force_fallback_adapter: bool,
+}
+
+impl Headless {
+    pub fn from_env() -> Option<Self> {
+        let path = std::env::var("WGPU_BOOK_PNG").ok()?;
+
+        let (mut width, mut height) = (800, 600);
+        if let Ok(size) = std::env::var("WGPU_BOOK_SIZE") {
+            match parse_size(&size) {
+                Some((w, h)) => (width, height) = (w, h),
+                None => log::warn!(
+                    "ignoring WGPU_BOOK_SIZE={}: expected WIDTHxHEIGHT with non-zero sizes",
+                    size
+                ),
+            }
+        }
+
+        let time = std::env::var("WGPU_BOOK_TIME")
+            .ok()
+            .and_then(|t| t.parse::<f32>().ok())
+            .unwrap_or(0.0);
+
+        let force_fallback_adapter = env_flag("WGPU_BOOK_FALLBACK");
+
+        Some(Self {
+            path,
+            width,
+            height,
+            time: Duration::from_secs_f32(time.max(0.0)),
+            force_fallback_adapter,
+        })
+    }
+
+    pub async fn init_wgpu(&self) -> Result<InitWgpu> {
+        InitWgpu::init_headless(self.width, self.height, self.force_fallback_adapter).await
+    }
+}
+
+/// Whether the environment variable `name` is set to `1` or `true`.
+pub fn env_flag(name: &str) -> bool {
+    std::env::var(name)
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+fn parse_size(size: &str) -> Option<(u32, u32)> {
+    let (w, h) = size.split_once(['x', 'X'])?;
+    let width: u32 = w.trim().parse().ok()?;
+    let height: u32 = h.trim().parse().ok()?;
+    (width > 0 && height > 0).then_some((width, height))
+}
@@ -0,0 +1,416 @@
+//! Drawing many copies of a mesh, either with one instanced draw call or with
+//! one draw call per object.
+//!
+//! Both paths use the same shader and per-object data ([`InstanceRaw`]): the
+//! instanced path reads it from an instance vertex buffer
+//! (`VertexStepMode::Instance`), the per-object path from a uniform buffer
+//! with one slot per object, selected with a dynamic offset before each draw
+//! like the nodes of a [`crate::scene::SceneGraph`].
+
+use crate::colormap::Colormap;
+use crate::depth::DepthConfig;
+use crate::scene::Transform;
+use anyhow::{anyhow, Result};
+use bytemuck::{Pod, Zeroable};
+use cgmath::Matrix4;
+use std::f32::consts::PI;
+use std::mem;
+use wgpu::util::DeviceExt;
+
+/// Model matrix and color of one object, as read by the shader.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
+pub struct InstanceRaw {
+    pub model: [[f32; 4]; 4],
+    pub color: [f32; 4],
+}
+
+impl InstanceRaw {
+    /// The matrix columns go to locations 2 to 5 and the color to location 6,
+    /// after the position and normal of the mesh.
+    const ATTRIBUTES: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+        2 => Float32x4, 3 => Float32x4, 4 => Float32x4, 5 => Float32x4, 6 => Float32x4
+    ];
+
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<InstanceRaw>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// One copy of the mesh. Scales should be uniform, since normals are
+/// transformed with the model matrix.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Instance {
+    pub transform: Transform,
+    pub color: [f32; 3],
+}
+
+impl Instance {
+    pub fn to_raw(&self) -> InstanceRaw {
+        let [r, g, b] = self.color;
+        InstanceRaw {
+            model: self.transform.matrix().into(),
+            color: [r, g, b, 1.0],
+        }
+    }
+}
+
+/// How the objects are laid out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+    Grid,
+    Cloud,
+}
+
+impl std::str::FromStr for Layout {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "grid" => Ok(Layout::Grid),
+            "cloud" => Ok(Layout::Cloud),
+            _ => Err(anyhow!(
+                "unknown layout '{}', expected 'grid' or 'cloud'",
+                s
+            )),
+        }
+    }
+}
+
+impl Layout {
+    /// `count` objects of half-size 1 meshes (such as
+    /// [`crate::vertex_data::cube_data`]) that fit in a ball of radius `size`.
+    pub fn instances(self, count: usize, size: f32, colormap: &Colormap) -> Vec<Instance> {
+        match self {
+            Layout::Grid => grid(count, size / 3f32.sqrt(), colormap),
+            Layout::Cloud => particle_cloud(count, size, 1, colormap),
+        }
+    }
+}
+
+/// `count` objects in a cube of half-size `size`, filled layer by layer from
+/// the bottom and colored by height.
+pub fn grid(count: usize, size: f32, colormap: &Colormap) -> Vec<Instance> {
+    let mut side = 1;
+    while side * side * side < count {
+        side += 1;
+    }
+    let spacing = 2.0 * size / side as f32;
+    let coordinate = |i: usize| -size + (i as f32 + 0.5) * spacing;
+    (0..count)
+        .map(|k| {
+            let (x, z, y) = (k % side, k / side % side, k / (side * side));
+            Instance {
+                transform: Transform::new(
+                    [coordinate(x), coordinate(y), coordinate(z)],
+                    [0.0; 3],
+                    [0.3 * spacing; 3],
+                ),
+                color: colormap.color_lerp(0.0, (side - 1).max(1) as f32, y as f32),
+            }
+        })
+        .collect()
+}
+
+/// `count` randomly rotated objects spread evenly over a ball of radius
+/// `radius`, colored by the distance from its center. The same `seed` gives
+/// the same cloud.
+pub fn particle_cloud(count: usize, radius: f32, seed: u64, colormap: &Colormap) -> Vec<Instance> {
+    let mut rng = Rng::new(seed);
+    // the ball divided among the objects
+    let scale = 0.3 * radius / (count.max(1) as f32).cbrt();
+    // a corner of a unit cube is sqrt(3) times its scale from the center
+    let reach = radius - 3f32.sqrt() * scale;
+    let mut instances = Vec::with_capacity(count);
+    while instances.len() < count {
+        let p = [0; 3].map(|_| rng.next_f32() * 2.0 - 1.0);
+        let r2 = p[0] * p[0] + p[1] * p[1] + p[2] * p[2];
+        if r2 > 1.0 {
+            continue;
+        }
+        let rotation = [0; 3].map(|_| rng.next_f32() * 2.0 * PI);
+        instances.push(Instance {
+            transform: Transform::new(p.map(|x| x * reach), rotation, [scale; 3]),
+            color: colormap.color(r2.sqrt()),
+        });
+    }
+    instances
+}
+
+/// xorshift64*, enough for scattering objects.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        let x = self.0.wrapping_mul(0x2545_F491_4F6C_DD1D);
+        (x >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// Whether [`InstanceRenderer::draw`] issues one instanced draw call or one
+/// draw call per object.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrawMode {
+    Instanced,
+    PerObject,
+}
+
+impl std::str::FromStr for DrawMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "instanced" => Ok(DrawMode::Instanced),
+            "per-object" => Ok(DrawMode::PerObject),
+            _ => Err(anyhow!(
+                "unknown draw mode '{}', expected 'instanced' or 'per-object'",
+                s
+            )),
+        }
+    }
+}
+
+// the view-projection and common model matrix
+const UNIFORM_SIZE: u64 = 128;
+
+/// A triangle list drawn once per [`Instance`], lit by a fixed directional
+/// light.
+pub struct InstanceRenderer {
+    instanced_pipeline: wgpu::RenderPipeline,
+    object_pipeline: wgpu::RenderPipeline,
+    vertex_buffer: wgpu::Buffer,
+    num_vertices: u32,
+    instance_buffer: wgpu::Buffer,
+    num_instances: u32,
+    uniform_buffer: wgpu::Buffer,
+    uniform_bind_group: wgpu::BindGroup,
+    object_bind_group: wgpu::BindGroup,
+    object_stride: u64,
+}
+
+impl InstanceRenderer {
+    /// `positions` and `normals` form the triangle list of the mesh;
+    /// `format`, `depth` and `sample_count` are those of the render pass.
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        depth: DepthConfig,
+        sample_count: u32,
+        positions: &[[f32; 3]],
+        normals: &[[f32; 3]],
+        instances: &[Instance],
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Instance Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("instance.wgsl").into()),
+        });
+
+        let vertices: Vec<[[f32; 3]; 2]> = positions
+            .iter()
+            .zip(normals)
+            .map(|(&p, &n)| [p, n])
+            .collect();
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Instance Mesh Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let raw: Vec<InstanceRaw> = instances.iter().map(Instance::to_raw).collect();
+        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Instance Buffer"),
+            contents: bytemuck::cast_slice(&raw),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        // the same data one uniform slot per object
+        let object_size = mem::size_of::<InstanceRaw>() as u64;
+        let object_stride = object_size
+            .next_multiple_of(device.limits().min_uniform_buffer_offset_alignment as u64);
+        let mut object_data = vec![0u8; object_stride as usize * raw.len().max(1)];
+        for (chunk, object) in object_data
+            .chunks_exact_mut(object_stride as usize)
+            .zip(&raw)
+        {
+            chunk[..object_size as usize].copy_from_slice(bytemuck::bytes_of(object));
+        }
+        let object_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Object Uniform Buffer"),
+            contents: &object_data,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Instance Uniform Buffer"),
+            size: UNIFORM_SIZE,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let uniform_entry = |dynamic, size| wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: dynamic,
+                min_binding_size: wgpu::BufferSize::new(size),
+            },
+            count: None,
+        };
+        let uniform_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[uniform_entry(false, UNIFORM_SIZE)],
+                label: Some("Instance Uniform Bind Group Layout"),
+            });
+        let object_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[uniform_entry(true, object_size)],
+                label: Some("Object Bind Group Layout"),
+            });
+        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &uniform_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+            label: Some("Instance Uniform Bind Group"),
+        });
+        let object_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &object_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &object_buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(object_size),
+                }),
+            }],
+            label: Some("Object Bind Group"),
+        });
+
+        let mesh_layout = wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<[[f32; 3]; 2]>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3],
+        };
+        let create_pipeline = |label,
+                               entry_point,
+                               bind_group_layouts: &[&wgpu::BindGroupLayout],
+                               buffers: &[wgpu::VertexBufferLayout]| {
+            let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(label),
+                bind_group_layouts,
+                push_constant_ranges: &[],
+            });
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point,
+                    buffers,
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    cull_mode: Some(wgpu::Face::Back),
+                    ..Default::default()
+                },
+                depth_stencil: Some(depth.depth_stencil_state()),
+                multisample: wgpu::MultisampleState {
+                    count: sample_count,
+                    ..Default::default()
+                },
+                multiview: None,
+            })
+        };
+        let instanced_pipeline = create_pipeline(
+            "Instanced Pipeline",
+            "vs_instanced",
+            &[&uniform_bind_group_layout],
+            &[mesh_layout.clone(), InstanceRaw::desc()],
+        );
+        let object_pipeline = create_pipeline(
+            "Per-Object Pipeline",
+            "vs_object",
+            &[&uniform_bind_group_layout, &object_bind_group_layout],
+            &[mesh_layout],
+        );
+
+        Self {
+            instanced_pipeline,
+            object_pipeline,
+            vertex_buffer,
+            num_vertices: vertices.len() as u32,
+            instance_buffer,
+            num_instances: raw.len() as u32,
+            uniform_buffer,
+            uniform_bind_group,
+            object_bind_group,
+            object_stride,
+        }
+    }
+
+    pub fn num_instances(&self) -> u32 {
+        self.num_instances
+    }
+
+    /// Sets the camera and a model matrix applied to all objects.
+    pub fn update(
+        &self,
+        queue: &wgpu::Queue,
+        view_project_mat: Matrix4<f32>,
+        model_mat: Matrix4<f32>,
+    ) {
+        let view_project_ref: &[f32; 16] = view_project_mat.as_ref();
+        let model_ref: &[f32; 16] = model_mat.as_ref();
+        queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(view_project_ref),
+        );
+        queue.write_buffer(&self.uniform_buffer, 64, bytemuck::cast_slice(model_ref));
+    }
+
+    /// Draws all objects into a pass whose targets match the formats given to
+    /// [`InstanceRenderer::new`]. Changes the bound pipeline, vertex buffers
+    /// and bind groups 0 and 1.
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, mode: DrawMode) {
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
+        match mode {
+            DrawMode::Instanced => {
+                render_pass.set_pipeline(&self.instanced_pipeline);
+                render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+                render_pass.draw(0..self.num_vertices, 0..self.num_instances);
+            }
+            DrawMode::PerObject => {
+                render_pass.set_pipeline(&self.object_pipeline);
+                for i in 0..self.num_instances as u64 {
+                    let offset = (i * self.object_stride) as u32;
+                    render_pass.set_bind_group(1, &self.object_bind_group, &[offset]);
+                    render_pass.draw(0..self.num_vertices, 0..1);
+                }
+            }
+        }
+    }
+}
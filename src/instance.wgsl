@@ -0,0 +1,55 @@
+struct Uniforms {
+    view_project_mat: mat4x4<f32>,
+    // applied to all objects on top of their own model matrices
+    model_mat: mat4x4<f32>,
+};
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+
+// the per-object path reads what the instanced path gets from the instance buffer
+struct Object {
+    model_mat: mat4x4<f32>,
+    color: vec4<f32>,
+};
+@group(1) @binding(0) var<uniform> object: Object;
+
+struct Output {
+    @builtin(position) position: vec4<f32>,
+    @location(0) v_normal: vec3<f32>,
+    @location(1) v_color: vec3<f32>,
+};
+
+fn transform(model_mat: mat4x4<f32>, color: vec4<f32>, pos: vec3<f32>, normal: vec3<f32>) -> Output {
+    var output: Output;
+    let world_mat = uniforms.model_mat * model_mat;
+    output.position = uniforms.view_project_mat * world_mat * vec4(pos, 1.0);
+    // the objects are scaled uniformly, so the model matrix keeps normals perpendicular
+    output.v_normal = (world_mat * vec4(normal, 0.0)).xyz;
+    output.v_color = color.rgb;
+    return output;
+}
+
+@vertex
+fn vs_instanced(
+    @location(0) pos: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) model_0: vec4<f32>,
+    @location(3) model_1: vec4<f32>,
+    @location(4) model_2: vec4<f32>,
+    @location(5) model_3: vec4<f32>,
+    @location(6) color: vec4<f32>,
+) -> Output {
+    return transform(mat4x4(model_0, model_1, model_2, model_3), color, pos, normal);
+}
+
+@vertex
+fn vs_object(@location(0) pos: vec3<f32>, @location(1) normal: vec3<f32>) -> Output {
+    return transform(object.model_mat, object.color, pos, normal);
+}
+
+@fragment
+fn fs_main(in: Output) -> @location(0) vec4<f32> {
+    let N = normalize(in.v_normal);
+    let L = normalize(vec3(0.4, 1.0, 0.6));
+    let diffuse = max(dot(N, L), 0.0);
+    return vec4(in.v_color * (0.25 + 0.75 * diffuse), 1.0);
+}
@@ -0,0 +1,121 @@
+//! Key bindings of the fly camera of ch06, loaded from JSON.
+//!
+//! ```json
+//! {"forward": ["w", "ArrowUp"], "boost": "Control"}
+//! ```
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::Value;
+use std::path::Path;
+use winit::keyboard::Key;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Forward,
+    Backward,
+    Left,
+    Right,
+    Up,
+    Down,
+    Boost,
+}
+
+impl Action {
+    const NAMES: [(&'static str, Action); 7] = [
+        ("forward", Action::Forward),
+        ("backward", Action::Backward),
+        ("left", Action::Left),
+        ("right", Action::Right),
+        ("up", Action::Up),
+        ("down", Action::Down),
+        ("boost", Action::Boost),
+    ];
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|&(_, action)| action)
+    }
+}
+
+/// Which keys trigger which [`Action`]. Keys are named by the character they
+/// type (`"w"`) or by their winit `NamedKey` (`"Shift"`, `"Space"`,
+/// `"ArrowUp"`), ignoring case.
+#[derive(Clone, Debug)]
+pub struct KeyBindings {
+    keys: Vec<(String, Action)>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let keys = [
+            ("w", Action::Forward),
+            ("s", Action::Backward),
+            ("a", Action::Left),
+            ("d", Action::Right),
+            ("e", Action::Up),
+            ("q", Action::Down),
+            ("shift", Action::Boost),
+        ];
+        Self {
+            keys: keys
+                .iter()
+                .map(|&(key, action)| (key.to_string(), action))
+                .collect(),
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let load = || Self::from_json(&std::fs::read_to_string(path)?);
+        load().with_context(|| format!("cannot load key bindings from {}", path.display()))
+    }
+
+    /// Parses an object mapping action names to a key or a list of keys, e.g.
+    /// `{"forward": ["w", "ArrowUp"], "boost": "Control"}`. Actions that are
+    /// left out keep their default keys.
+    pub fn from_json(text: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(text)?;
+        let Value::Object(entries) = value else {
+            bail!("expected an object mapping actions to keys");
+        };
+        let mut bindings = Self::default();
+        for (name, keys) in &entries {
+            let action = Action::from_name(name).ok_or_else(|| {
+                let names: Vec<_> = Action::NAMES.iter().map(|(n, _)| *n).collect();
+                anyhow!("unknown action {:?}, expected one of {:?}", name, names)
+            })?;
+            let keys = match keys {
+                Value::String(key) => vec![key.as_str()],
+                Value::Array(keys) => keys
+                    .iter()
+                    .map(|key| {
+                        key.as_str()
+                            .ok_or_else(|| anyhow!("expected a key name, found {}", key))
+                    })
+                    .collect::<Result<_>>()?,
+                _ => bail!("expected a key or a list of keys for {:?}", name),
+            };
+            bindings.keys.retain(|&(_, a)| a != action);
+            bindings
+                .keys
+                .extend(keys.iter().map(|key| (key.to_lowercase(), action)));
+        }
+        Ok(bindings)
+    }
+
+    pub fn action(&self, key: &Key) -> Option<Action> {
+        let name = match key {
+            Key::Character(c) => c.to_lowercase(),
+            Key::Named(named) => format!("{:?}", named).to_lowercase(),
+            _ => return None,
+        };
+        self.keys
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|&(_, action)| action)
+    }
+}
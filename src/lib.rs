@@ -0,0 +1,35 @@
+//! Shared code for the chapter examples of the wgpu learning project.
+//!
+//! Every chapter under `examples/` builds on these modules instead of
+//! pulling the files in with `#[path = ...]`.
+
+pub mod app;
+pub mod bitmap_font;
+pub mod colormap;
+pub mod common;
+pub mod depth;
+pub mod export;
+pub mod gltf;
+pub mod ground;
+pub mod headless;
+pub mod instance;
+pub mod keybindings;
+pub mod light;
+pub mod math_func;
+pub mod mesh;
+pub mod msaa;
+pub mod obj;
+pub mod orbit;
+pub mod overlay;
+pub mod pbr;
+pub mod ply;
+pub mod scene;
+pub mod shadow;
+pub mod stl;
+pub mod surface_data;
+pub mod texture_data;
+pub mod transforms;
+pub mod vertex_data;
+
+pub use texture_data::Texture;
+pub use transforms::{create_transforms, create_view_projection, Frame, InitWgpu};
@@ -0,0 +1,159 @@
+//! Scene lights shared by the lit chapter shaders (ch08 solid, ch09 colormap
+//! and ch10 texture).
+//!
+//! The lights are uploaded as a [`LightsUniform`] and evaluated in WGSL by the
+//! functions in [`LIGHT_WGSL`], which the shaders are prepended with.
+
+use bytemuck::{Pod, Zeroable};
+use cgmath::{Angle, Deg};
+
+/// Maximum number of lights in a [`LightsUniform`].
+pub const MAX_LIGHTS: usize = 8;
+
+/// WGSL declarations of the light array (bound at `@group(0) @binding(3)`),
+/// the shadow map of [`crate::shadow::ShadowMap`] (bindings 4 to 6) and
+/// `blinn_phong_lights`, which sums the contributions of all lights.
+pub const LIGHT_WGSL: &str = concat!(
+    include_str!("shadow.wgsl"),
+    "\n",
+    include_str!("light.wgsl")
+);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LightKind {
+    /// Parallel light shining along `direction`, e.g. the sun.
+    Directional { direction: [f32; 3] },
+    /// Light emitted from `position` in all directions.
+    Point { position: [f32; 3] },
+    /// Point light restricted to a cone around `direction`: full intensity
+    /// within `inner` of the axis, fading out towards `outer`.
+    Spot {
+        position: [f32; 3],
+        direction: [f32; 3],
+        inner: Deg<f32>,
+        outer: Deg<f32>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Light {
+    pub kind: LightKind,
+    pub color: [f32; 3],
+    pub intensity: f32,
+    /// Constant, linear and quadratic distance attenuation of point and spot
+    /// lights; the default `[1, 0, 0]` does not attenuate.
+    pub attenuation: [f32; 3],
+}
+
+impl Light {
+    fn new(kind: LightKind, color: [f32; 3]) -> Self {
+        Self {
+            kind,
+            color,
+            intensity: 1.0,
+            attenuation: [1.0, 0.0, 0.0],
+        }
+    }
+
+    pub fn directional(direction: [f32; 3], color: [f32; 3]) -> Self {
+        Self::new(LightKind::Directional { direction }, color)
+    }
+
+    pub fn point(position: [f32; 3], color: [f32; 3]) -> Self {
+        Self::new(LightKind::Point { position }, color)
+    }
+
+    pub fn spot(
+        position: [f32; 3],
+        direction: [f32; 3],
+        inner: Deg<f32>,
+        outer: Deg<f32>,
+        color: [f32; 3],
+    ) -> Self {
+        let kind = LightKind::Spot {
+            position,
+            direction,
+            inner,
+            outer,
+        };
+        Self::new(kind, color)
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn with_attenuation(mut self, constant: f32, linear: f32, quadratic: f32) -> Self {
+        self.attenuation = [constant, linear, quadratic];
+        self
+    }
+
+    fn raw(&self) -> LightRaw {
+        let [r, g, b] = self.color.map(|c| c * self.intensity);
+        let [c, l, q] = self.attenuation;
+        let (kind, position, direction, cone) = match self.kind {
+            LightKind::Directional { direction } => (0.0, [0.0; 3], direction, [1.0, 1.0]),
+            LightKind::Point { position } => (1.0, position, [0.0; 3], [1.0, 1.0]),
+            LightKind::Spot {
+                position,
+                direction,
+                inner,
+                outer,
+            } => {
+                // keep the outer cone strictly wider so the fade is well defined
+                let outer = Deg(outer.0.max(inner.0 + 0.1));
+                (2.0, position, direction, [inner.cos(), outer.cos()])
+            }
+        };
+        LightRaw {
+            position: [position[0], position[1], position[2], kind],
+            direction: [direction[0], direction[1], direction[2], 0.0],
+            color: [r, g, b, 1.0],
+            attenuation: [c, l, q, 0.0],
+            cone: [cone[0], cone[1], 0.0, 0.0],
+        }
+    }
+}
+
+// Matches `Light` in light.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct LightRaw {
+    /// xyz: position, w: kind (0 directional, 1 point, 2 spot).
+    position: [f32; 4],
+    direction: [f32; 4],
+    /// Color premultiplied by the intensity.
+    color: [f32; 4],
+    attenuation: [f32; 4],
+    /// Cosines of the inner and outer cone angles.
+    cone: [f32; 4],
+}
+
+/// Uniform buffer contents for up to [`MAX_LIGHTS`] lights.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct LightsUniform {
+    count: u32,
+    _padding: [u32; 3],
+    lights: [LightRaw; MAX_LIGHTS],
+}
+
+impl LightsUniform {
+    /// Lights beyond [`MAX_LIGHTS`] are ignored.
+    pub fn new(lights: &[Light]) -> Self {
+        if lights.len() > MAX_LIGHTS {
+            log::warn!(
+                "{} lights given, only the first {} are used",
+                lights.len(),
+                MAX_LIGHTS
+            );
+        }
+        let mut uniform = Self::zeroed();
+        for (raw, light) in uniform.lights.iter_mut().zip(lights) {
+            *raw = light.raw();
+        }
+        uniform.count = lights.len().min(MAX_LIGHTS) as u32;
+        uniform
+    }
+}
@@ -0,0 +1,97 @@
+// Scene lights, see `wgpu_book::light`.
+
+const MAX_LIGHTS: u32 = 8u;
+const LIGHT_DIRECTIONAL: f32 = 0.0;
+const LIGHT_SPOT: f32 = 2.0;
+
+struct Light {
+    // xyz: position, w: kind (0 directional, 1 point, 2 spot)
+    position: vec4<f32>,
+    direction: vec4<f32>,
+    // color premultiplied by the intensity
+    color: vec4<f32>,
+    // constant, linear and quadratic attenuation
+    attenuation: vec4<f32>,
+    // cosines of the inner and outer cone angles
+    cone: vec4<f32>,
+};
+
+struct Lights {
+    count: u32,
+    lights: array<Light, MAX_LIGHTS>,
+};
+@group(0) @binding(3) var<uniform> lights: Lights;
+
+struct LightSample {
+    // unit vector from the surface towards the light
+    L: vec3<f32>,
+    // light color arriving at the surface: attenuated, limited to the spot
+    // cone and shadowed
+    radiance: vec3<f32>,
+};
+
+fn light_count() -> u32 {
+    return min(lights.count, MAX_LIGHTS);
+}
+
+// Direction and radiance of light `i` at `position`.
+fn light_sample(i: u32, position: vec3<f32>) -> LightSample {
+    let light = lights.lights[i];
+    var incoming: LightSample;
+    incoming.radiance = light.color.rgb;
+    if (light.position.w == LIGHT_DIRECTIONAL) {
+        incoming.L = normalize(-light.direction.xyz);
+    } else {
+        let to_light = light.position.xyz - position;
+        let distance = length(to_light);
+        incoming.L = to_light / distance;
+        let k = light.attenuation.xyz;
+        incoming.radiance = incoming.radiance / max(k.x + k.y * distance + k.z * distance * distance, 0.0001);
+        if (light.position.w == LIGHT_SPOT) {
+            let cos_angle = dot(-incoming.L, normalize(light.direction.xyz));
+            incoming.radiance = incoming.radiance * smoothstep(light.cone.y, light.cone.x, cos_angle);
+        }
+    }
+    incoming.radiance = incoming.radiance * shadow_visibility(i, position);
+    return incoming;
+}
+
+struct LightTerms {
+    diffuse: vec3<f32>,
+    specular: vec3<f32>,
+};
+
+// Blinn-Phong diffuse and specular terms summed over all lights, already
+// multiplied by the light colors. With `two_side` the back face is lit too.
+fn blinn_phong_lights(
+    position: vec3<f32>,
+    N: vec3<f32>,
+    V: vec3<f32>,
+    diffuse_intensity: f32,
+    specular_intensity: f32,
+    shininess: f32,
+    two_side: bool,
+) -> LightTerms {
+    var terms: LightTerms;
+    terms.diffuse = vec3(0.0);
+    terms.specular = vec3(0.0);
+    for (var i: u32 = 0u; i < light_count(); i = i + 1u) {
+        let incoming = light_sample(i, position);
+        let L = incoming.L;
+        let H = normalize(L + V);
+
+        // front side
+        var diffuse: f32 = diffuse_intensity * max(dot(N, L), 0.0);
+        var specular: f32 = specular_intensity * pow(max(dot(N, H), 0.0), shininess);
+
+        // back side
+        if (two_side) {
+            diffuse = diffuse + diffuse_intensity * max(dot(-N, L), 0.0);
+            specular = specular + specular_intensity * pow(max(dot(-N, H), 0.0), shininess);
+        }
+
+        terms.diffuse = terms.diffuse + incoming.radiance * diffuse;
+        terms.specular = terms.specular + incoming.radiance * specular;
+    }
+    return terms;
+}
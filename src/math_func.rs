@@ -0,0 +1,66 @@
+use ::cgmath::*;
+use std::f32::consts::PI;
+
+pub fn sphere_position(r: f32, theta: Deg<f32>, phi: Deg<f32>) -> [f32; 3] {
+    let snt = theta.sin();
+    let cnt = theta.cos();
+    let snp = phi.sin();
+    let cnp = phi.cos();
+
+    [r * snt * cnp, r * cnt, -r * snt * snp]
+}
+
+pub fn cylinder_position(r: f32, y: f32, theta: Deg<f32>) -> [f32; 3] {
+    [r * theta.cos(), y, -r * theta.sin()]
+}
+
+pub fn torus_position(r_torus: f32, r_tube: f32, u: Deg<f32>, v: Deg<f32>) -> [f32; 3] {
+    let x = (r_torus + r_tube * v.cos()) * u.cos();
+    let y = r_tube * v.sin();
+    let z = -(r_torus + r_tube * v.cos()) * u.sin();
+
+    [x, y, z]
+}
+
+pub fn sinc(x: f32, z: f32) -> [f32; 3] {
+    let r = (x * x + z * z).sqrt();
+    let y = if r == 0.0 { 1.0 } else { r.sin() / r };
+    [x, y, z]
+}
+
+/// Klein bottle for `u` in `[0, π]` and `v` in `[0, 2π]` (periodic in `v`).
+pub fn klein_bottle(u: f32, v: f32) -> [f32; 3] {
+    let (su, cu) = u.sin_cos();
+    let (sv, cv) = v.sin_cos();
+    let (c2, c3, c4) = (cu.powi(2), cu.powi(3), cu.powi(4));
+    let (c5, c6, c7) = (cu.powi(5), cu.powi(6), cu.powi(7));
+
+    let x = -2.0 / 15.0
+        * cu
+        * (3.0 * cv - 30.0 * su + 90.0 * c4 * su - 60.0 * c6 * su + 5.0 * cu * cv * su);
+    let y = -1.0 / 15.0
+        * su
+        * (3.0 * cv - 3.0 * c2 * cv - 48.0 * c4 * cv + 48.0 * c6 * cv - 60.0 * su
+            + 5.0 * cu * cv * su
+            - 5.0 * c3 * cv * su
+            - 80.0 * c5 * cv * su
+            + 80.0 * c7 * cv * su);
+    let z = 2.0 / 15.0 * (3.0 + 5.0 * cu * su) * sv;
+    [x, y, z]
+}
+
+/// Möbius strip of radius 1 for `u` in `[0, 2π]` and `v` in `[-1, 1]`.
+pub fn mobius_strip(u: f32, v: f32) -> [f32; 3] {
+    let r = 1.0 + 0.5 * v * (0.5 * u).cos();
+    [r * u.cos(), 0.5 * v * (0.5 * u).sin(), r * u.sin()]
+}
+
+/// Seashell spiral for `u` in `[0, 6π]` and `v` in `[0, 2π]` (periodic in `v`).
+pub fn seashell(u: f32, v: f32) -> [f32; 3] {
+    let e = (u / (6.0 * PI)).exp();
+    let c = (0.5 * v).cos().powi(2);
+    let x = 2.0 * (1.0 - e) * u.cos() * c;
+    let y = 1.0 - (u / (3.0 * PI)).exp() - v.sin() + e * v.sin();
+    let z = 2.0 * (-1.0 + e) * u.sin() * c;
+    [x, y, z]
+}
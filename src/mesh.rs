@@ -0,0 +1,162 @@
+use cgmath::*;
+
+/// How the normals of a [`Mesh`] are computed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shading {
+    /// Every triangle gets its own vertices and face normal (faceted look).
+    Flat,
+    /// Vertices are shared and normals are averaged over the adjacent triangles.
+    Smooth,
+}
+
+impl std::str::FromStr for Shading {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "flat" => Ok(Shading::Flat),
+            "smooth" => Ok(Shading::Smooth),
+            _ => Err(anyhow::anyhow!(
+                "unknown shading '{}', expected 'flat' or 'smooth'",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MeshVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub color: [f32; 3],
+    pub uv: [f32; 2],
+    /// Colormap coordinate in `[0, 1]`, for looking `color` up on the GPU.
+    pub scalar: f32,
+}
+
+/// Indexed triangle list: every three entries of `indices` form a
+/// counter-clockwise triangle.
+#[derive(Clone, Debug, Default)]
+pub struct Mesh {
+    pub vertices: Vec<MeshVertex>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    /// Wraps a triangle list such as the one of
+    /// [`crate::vertex_data::sphere_data`] or
+    /// [`crate::surface_data::simple_surface_data`]; `normals` and `uvs` may
+    /// be empty, and the vertices are white.
+    pub fn from_triangles(positions: &[[f32; 3]], normals: &[[f32; 3]], uvs: &[[f32; 2]]) -> Mesh {
+        let vertices = positions
+            .iter()
+            .enumerate()
+            .map(|(i, &position)| MeshVertex {
+                position,
+                normal: normals.get(i).copied().unwrap_or_default(),
+                color: [1.0; 3],
+                uv: uvs.get(i).copied().unwrap_or_default(),
+                scalar: 0.0,
+            })
+            .collect();
+        let indices = (0..positions.len() as u32 / 3 * 3).collect();
+        Mesh { vertices, indices }
+    }
+
+    /// Sets the vertex colors, e.g. to the colormap colors of
+    /// [`crate::surface_data::simple_surface_data`].
+    pub fn with_colors(mut self, colors: &[[f32; 3]]) -> Mesh {
+        for (v, &color) in self.vertices.iter_mut().zip(colors) {
+            v.color = color;
+        }
+        self
+    }
+
+    /// Recomputes every vertex normal as the area-weighted average of the
+    /// normals of the triangles that share the vertex.
+    pub fn compute_smooth_normals(&mut self) {
+        let sums = self.normal_sums();
+        for (v, n) in self.vertices.iter_mut().zip(sums) {
+            if n.magnitude2() > 0.0 {
+                v.normal = n.normalize().into();
+            }
+        }
+    }
+
+    /// Returns a copy where no vertices are shared and each triangle carries
+    /// its own face normal.
+    pub fn to_flat(&self) -> Mesh {
+        let mut vertices = Vec::with_capacity(self.indices.len());
+        for tri in self.indices.chunks_exact(3) {
+            let n = self.face_normal(tri);
+            let normal = if n.magnitude2() > 0.0 {
+                n.normalize().into()
+            } else {
+                self.vertices[tri[0] as usize].normal
+            };
+            for &i in tri {
+                vertices.push(MeshVertex {
+                    normal,
+                    ..self.vertices[i as usize]
+                });
+            }
+        }
+        let indices = (0..vertices.len() as u32).collect();
+        Mesh { vertices, indices }
+    }
+
+    /// Applies `shading` to a mesh with shared vertices.
+    pub fn with_shading(mut self, shading: Shading) -> Mesh {
+        match shading {
+            Shading::Flat => self.to_flat(),
+            Shading::Smooth => {
+                self.compute_smooth_normals();
+                self
+            }
+        }
+    }
+
+    /// Centers the bounding box on the origin and scales the mesh uniformly
+    /// so that it fits into a sphere of `radius`, e.g. for a model loaded
+    /// from a file.
+    pub fn fit(&mut self, radius: f32) {
+        let Some(first) = self.vertices.first() else {
+            return;
+        };
+        let (mut min, mut max) = (first.position, first.position);
+        for v in &self.vertices {
+            for i in 0..3 {
+                min[i] = min[i].min(v.position[i]);
+                max[i] = max[i].max(v.position[i]);
+            }
+        }
+        let center = (Vector3::from(min) + Vector3::from(max)) * 0.5;
+        let extent = (Vector3::from(max) - center).magnitude();
+        let scale = if extent > 0.0 { radius / extent } else { 1.0 };
+        for v in &mut self.vertices {
+            v.position = ((Vector3::from(v.position) - center) * scale).into();
+        }
+    }
+
+    /// The sum of the normals of the triangles around each vertex, each as
+    /// long as twice the triangle area; [`Mesh::compute_smooth_normals`]
+    /// normalizes them.
+    pub fn normal_sums(&self) -> Vec<Vector3<f32>> {
+        let mut sums = vec![Vector3::zero(); self.vertices.len()];
+        for tri in self.indices.chunks_exact(3) {
+            // the length of the cross product is twice the triangle area
+            let n = self.face_normal(tri);
+            for &i in tri {
+                sums[i as usize] += n;
+            }
+        }
+        sums
+    }
+
+    fn face_normal(&self, tri: &[u32]) -> Vector3<f32> {
+        let p0 = Vector3::from(self.vertices[tri[0] as usize].position);
+        let p1 = Vector3::from(self.vertices[tri[1] as usize].position);
+        let p2 = Vector3::from(self.vertices[tri[2] as usize].position);
+        (p1 - p0).cross(p2 - p0)
+    }
+}
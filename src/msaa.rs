@@ -0,0 +1,174 @@
+//! Multisample anti-aliasing shared by all chapters.
+//!
+//! Every example reads the sample count from `WGPU_BOOK_MSAA` (`1`, `2`, `4`
+//! or `8`), like the depth settings of [`crate::depth`], so the examples keep
+//! their own command-line arguments. The scene is then drawn into a
+//! multisampled color texture, with a multisampled depth buffer, and resolved
+//! into the surface texture at the end of the pass. A count the adapter cannot
+//! render falls back to the next lower one.
+//!
+//! ```shell
+//! WGPU_BOOK_MSAA=4 cargo run --example ch07_torus
+//! ```
+
+/// Sample counts that can be selected with `WGPU_BOOK_MSAA`.
+pub const SAMPLE_COUNTS: [u32; 4] = [1, 2, 4, 8];
+
+/// The sample count requested with `WGPU_BOOK_MSAA`, 1 if it is not set.
+pub fn sample_count_from_env() -> u32 {
+    let Ok(value) = std::env::var("WGPU_BOOK_MSAA") else {
+        return 1;
+    };
+    match value.parse::<u32>() {
+        Ok(count) if SAMPLE_COUNTS.contains(&count) => count,
+        _ => {
+            log::warn!(
+                "WGPU_BOOK_MSAA takes one of {:?}, got {:?}; rendering without MSAA",
+                SAMPLE_COUNTS,
+                value
+            );
+            1
+        }
+    }
+}
+
+/// Format features the device validates against: the adapter's own when the
+/// device allows adapter-specific features (or is downlevel), otherwise those
+/// WebGPU guarantees.
+fn format_features(
+    adapter: &wgpu::Adapter,
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+) -> wgpu::TextureFormatFeatures {
+    let adapter_specific = device
+        .features()
+        .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+    if adapter_specific || !adapter.get_downlevel_capabilities().is_webgpu_compliant() {
+        adapter.get_texture_format_features(format)
+    } else {
+        format.guaranteed_format_features(device.features())
+    }
+}
+
+/// The highest sample count up to `requested` that `color_format` (including
+/// the resolve) and all `depth_formats` support.
+pub fn supported_sample_count(
+    adapter: &wgpu::Adapter,
+    device: &wgpu::Device,
+    color_format: wgpu::TextureFormat,
+    depth_formats: &[wgpu::TextureFormat],
+    requested: u32,
+) -> u32 {
+    let color = format_features(adapter, device, color_format).flags;
+    let supported = |count: u32| {
+        count == 1
+            || (color.contains(wgpu::TextureFormatFeatureFlags::MULTISAMPLE_RESOLVE)
+                && color.sample_count_supported(count)
+                && depth_formats.iter().all(|&format| {
+                    format_features(adapter, device, format)
+                        .flags
+                        .sample_count_supported(count)
+                }))
+    };
+    let count = SAMPLE_COUNTS
+        .iter()
+        .rev()
+        .copied()
+        .find(|&count| count <= requested && supported(count))
+        .unwrap_or(1);
+    if count != requested {
+        log::warn!(
+            "{}x MSAA is not supported for {:?}, using {}x",
+            requested,
+            color_format,
+            count
+        );
+    }
+    count
+}
+
+/// Device features to request so that sample counts beyond the ones WebGPU
+/// guarantees (1 and 4) can be used where the adapter supports them.
+pub fn device_features(adapter: &wgpu::Adapter) -> wgpu::Features {
+    adapter.features() & wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+}
+
+/// The multisampled color texture a frame is drawn into before it is resolved
+/// into the surface texture. Holds nothing when the sample count is 1.
+pub struct MsaaTarget {
+    pub sample_count: u32,
+    format: wgpu::TextureFormat,
+    target: Option<(wgpu::Texture, wgpu::TextureView)>,
+}
+
+impl MsaaTarget {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+        sample_count: u32,
+    ) -> Self {
+        let mut msaa = Self {
+            sample_count,
+            format,
+            target: None,
+        };
+        msaa.resize(device, width, height);
+        msaa
+    }
+
+    /// Recreates the texture for the new surface size.
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        if self.sample_count == 1 {
+            return;
+        }
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("MSAA Texture"),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: self.sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        self.target = Some((texture, view));
+    }
+
+    /// The `multisample` state of a pipeline drawing into this target.
+    pub fn multisample_state(&self) -> wgpu::MultisampleState {
+        wgpu::MultisampleState {
+            count: self.sample_count,
+            ..Default::default()
+        }
+    }
+
+    /// Color attachment that draws into `view`, through the multisampled
+    /// texture if there is one. The samples are stored so that a later pass
+    /// loading them, like an overlay, resolves the same image again.
+    pub fn color_attachment<'a>(
+        &'a self,
+        view: &'a wgpu::TextureView,
+        load: wgpu::LoadOp<wgpu::Color>,
+    ) -> wgpu::RenderPassColorAttachment<'a> {
+        let ops = wgpu::Operations { load, store: true };
+        match &self.target {
+            Some((_, msaa_view)) => wgpu::RenderPassColorAttachment {
+                view: msaa_view,
+                resolve_target: Some(view),
+                ops,
+            },
+            None => wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops,
+            },
+        }
+    }
+}
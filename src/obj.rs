@@ -0,0 +1,415 @@
+//! Wavefront OBJ import, with the materials of the referenced MTL files, and
+//! export of a [`Mesh`].
+//!
+//! Faces may have any number of corners and are triangulated by ear
+//! clipping, and indices may be negative (counting back from the last
+//! vertex). Vertices without a normal get the face normal, or within a
+//! smoothing group (`s 1`) the area-weighted average of the normals of the
+//! faces around their position. Texture coordinates are flipped to the
+//! top-left origin of wgpu textures.
+//!
+//! ```no_run
+//! use wgpu_book::obj::ObjModel;
+//! let model = ObjModel::load("examples/ch10/assets/house.obj").unwrap();
+//! println!("{} triangles", model.mesh.indices.len() / 3);
+//! ```
+
+use crate::mesh::{Mesh, MeshVertex};
+use anyhow::{anyhow, bail, Context, Result};
+use cgmath::*;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Position, texture coordinate and normal index of a vertex, where its
+/// normal is generated (0 if it has one, the smoothing group if positive, or
+/// minus the number of its face if the face is flat) and its material, whose
+/// diffuse color it gets.
+type Key = (usize, Option<usize>, Option<usize>, i64, Option<usize>);
+
+/// The subset of an MTL material the lit chapters can show.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjMaterial {
+    pub name: String,
+    /// `Ka`
+    pub ambient: [f32; 3],
+    /// `Kd`
+    pub diffuse: [f32; 3],
+    /// `Ks`
+    pub specular: [f32; 3],
+    /// `Ns`
+    pub shininess: f32,
+    /// `d`, or `1 - Tr`
+    pub opacity: f32,
+    /// `map_Kd`, joined to the directory of the OBJ file.
+    pub diffuse_texture: Option<PathBuf>,
+}
+
+impl ObjMaterial {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ambient: [0.1, 0.1, 0.1],
+            diffuse: [0.8, 0.8, 0.8],
+            specular: [0.0, 0.0, 0.0],
+            shininess: 1.0,
+            opacity: 1.0,
+            diffuse_texture: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ObjModel {
+    /// All faces as one triangle list. Every vertex has a normal and a
+    /// texture coordinate (0 if the file has none), and the diffuse color of
+    /// its material as `color`.
+    pub mesh: Mesh,
+    pub materials: Vec<ObjMaterial>,
+    /// Index into `materials` for every triangle of `mesh`, `None` for faces
+    /// before the first `usemtl`.
+    pub triangle_materials: Vec<Option<usize>>,
+}
+
+impl ObjModel {
+    /// Loads an OBJ file; `mtllib` files are looked up next to it.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read {}", path.display()))?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        Self::parse(&text, dir).with_context(|| format!("cannot load {}", path.display()))
+    }
+
+    /// Parses the text of an OBJ file; `dir` is where `mtllib` files and the
+    /// textures they name are looked up. A material library that cannot be
+    /// read is skipped with a warning.
+    pub fn parse(text: &str, dir: &Path) -> Result<Self> {
+        let mut positions: Vec<[f32; 3]> = Vec::new();
+        let mut colors: Vec<Option<[f32; 3]>> = Vec::new();
+        let mut uvs: Vec<[f32; 2]> = Vec::new();
+        let mut normals: Vec<[f32; 3]> = Vec::new();
+        let mut model = ObjModel::default();
+        let mut material = None;
+        let mut smoothing_group = 0;
+        let mut faces = 0;
+        let mut keys: Vec<Key> = Vec::new();
+        let mut vertex_of_key = HashMap::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut tokens = line.split_whitespace();
+            let Some(keyword) = tokens.next() else {
+                continue;
+            };
+            let args: Vec<&str> = tokens.collect();
+            let context = || format!("line {}: {}", i + 1, line.trim());
+            match keyword {
+                "v" => {
+                    let v = floats(&args, 3).with_context(context)?;
+                    positions.push([v[0], v[1], v[2]]);
+                    // `v x y z r g b` is a common extension for vertex colors
+                    colors.push((v.len() >= 6).then(|| [v[3], v[4], v[5]]));
+                }
+                "vt" => {
+                    let v = floats(&args, 1).with_context(context)?;
+                    uvs.push([v[0], 1.0 - v.get(1).copied().unwrap_or(0.0)]);
+                }
+                "vn" => {
+                    let v = floats(&args, 3).with_context(context)?;
+                    normals.push([v[0], v[1], v[2]]);
+                }
+                "f" => {
+                    if args.len() < 3 {
+                        return Err(anyhow!("a face needs at least 3 vertices"))
+                            .with_context(context);
+                    }
+                    faces += 1;
+                    let mut corners = Vec::with_capacity(args.len());
+                    for arg in &args {
+                        let (p, t, n) = face_vertex(arg, positions.len(), uvs.len(), normals.len())
+                            .with_context(context)?;
+                        let normal_source = match (n, smoothing_group) {
+                            (Some(_), _) => 0,
+                            (None, 0) => -faces,
+                            (None, group) => group,
+                        };
+                        let key = (p, t, n, normal_source, material);
+                        let index = *vertex_of_key.entry(key).or_insert_with(|| {
+                            keys.push(key);
+                            keys.len() as u32 - 1
+                        });
+                        corners.push(index);
+                    }
+                    let points: Vec<Vector3<f32>> = corners
+                        .iter()
+                        .map(|&c| positions[keys[c as usize].0].into())
+                        .collect();
+                    for [a, b, c] in triangulate(&points) {
+                        model
+                            .mesh
+                            .indices
+                            .extend([corners[a], corners[b], corners[c]]);
+                        model.triangle_materials.push(material);
+                    }
+                }
+                "s" => {
+                    smoothing_group = match args.first() {
+                        Some(&"off") | None => 0,
+                        Some(group) => group
+                            .parse()
+                            .map_err(|e| anyhow!("smoothing group {:?}: {}", group, e))
+                            .with_context(context)?,
+                    };
+                }
+                "usemtl" => {
+                    let name = args.join(" ");
+                    material = model.materials.iter().position(|m| m.name == name);
+                    if material.is_none() {
+                        log::warn!("line {}: unknown material {:?}", i + 1, name);
+                    }
+                }
+                "mtllib" => {
+                    for file in &args {
+                        let path = dir.join(file);
+                        match std::fs::read_to_string(&path) {
+                            Ok(text) => model.materials.extend(
+                                parse_mtl(&text, dir)
+                                    .with_context(|| format!("cannot load {}", path.display()))?,
+                            ),
+                            Err(e) => log::warn!("cannot read {}: {}", path.display(), e),
+                        }
+                    }
+                }
+                // groups, objects, lines and points
+                _ => {}
+            }
+        }
+
+        model.mesh.vertices = keys
+            .iter()
+            .map(|&(p, t, n, _, m)| MeshVertex {
+                position: positions[p],
+                normal: n.map_or([0.0; 3], |n| normals[n]),
+                color: colors[p]
+                    .or_else(|| m.map(|m| model.materials[m].diffuse))
+                    .unwrap_or([1.0; 3]),
+                uv: t.map_or([0.0; 2], |t| uvs[t]),
+                scalar: 0.0,
+            })
+            .collect();
+        model.generate_missing_normals(&keys);
+        Ok(model)
+    }
+
+    /// The material used by most triangles, for pipelines that draw a model
+    /// with a single material.
+    pub fn main_material(&self) -> Option<&ObjMaterial> {
+        let mut counts = vec![0; self.materials.len()];
+        for m in self.triangle_materials.iter().flatten() {
+            counts[*m] += 1;
+        }
+        let (index, count) = counts.iter().enumerate().max_by_key(|&(_, c)| *c)?;
+        (*count > 0).then(|| &self.materials[index])
+    }
+
+    // vertices of a smoothing group share the normal of their position, so
+    // the faces meeting there look smooth even if the texture coordinates
+    // differ
+    fn generate_missing_normals(&mut self, keys: &[Key]) {
+        if keys.iter().all(|k| k.2.is_some()) {
+            return;
+        }
+        // corners at the same position and in the same smoothing group share
+        // the area-weighted normal
+        let mut sums: HashMap<(usize, i64), Vector3<f32>> = HashMap::new();
+        for (n, key) in self.mesh.normal_sums().into_iter().zip(keys) {
+            *sums.entry((key.0, key.3)).or_insert_with(Vector3::zero) += n;
+        }
+        for (v, key) in self.mesh.vertices.iter_mut().zip(keys) {
+            if key.2.is_none() {
+                let n = sums
+                    .get(&(key.0, key.3))
+                    .copied()
+                    .unwrap_or_else(Vector3::zero);
+                v.normal = if n.magnitude2() > 0.0 {
+                    n.normalize().into()
+                } else {
+                    [0.0, 1.0, 0.0]
+                };
+            }
+        }
+    }
+}
+
+/// Writes `mesh` as an OBJ file with one `v`, `vt` and `vn` per vertex;
+/// colors are appended to the positions (`v x y z r g b`) and texture
+/// coordinates flipped back to the bottom-left origin of OBJ.
+pub fn write<W: Write>(mesh: &Mesh, w: &mut W) -> Result<()> {
+    writeln!(
+        w,
+        "# {} vertices, {} triangles",
+        mesh.vertices.len(),
+        mesh.indices.len() / 3
+    )?;
+    for v in &mesh.vertices {
+        let [x, y, z] = v.position;
+        let [r, g, b] = v.color;
+        writeln!(w, "v {} {} {} {} {} {}", x, y, z, r, g, b)?;
+    }
+    for v in &mesh.vertices {
+        writeln!(w, "vt {} {}", v.uv[0], 1.0 - v.uv[1])?;
+    }
+    for v in &mesh.vertices {
+        let [x, y, z] = v.normal;
+        writeln!(w, "vn {} {} {}", x, y, z)?;
+    }
+    for tri in mesh.indices.chunks_exact(3) {
+        // OBJ indices start at 1
+        let [a, b, c] = [tri[0] + 1, tri[1] + 1, tri[2] + 1];
+        writeln!(w, "f {0}/{0}/{0} {1}/{1}/{1} {2}/{2}/{2}", a, b, c)?;
+    }
+    Ok(())
+}
+
+/// Parses the materials of an MTL file; texture paths are joined to `dir`.
+pub fn parse_mtl(text: &str, dir: &Path) -> Result<Vec<ObjMaterial>> {
+    let mut materials: Vec<ObjMaterial> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let args: Vec<&str> = tokens.collect();
+        let context = || format!("line {}: {}", i + 1, line.trim());
+        if keyword == "newmtl" {
+            materials.push(ObjMaterial::new(&args.join(" ")));
+            continue;
+        }
+        let Some(material) = materials.last_mut() else {
+            bail!("line {}: {} before the first newmtl", i + 1, keyword);
+        };
+        match keyword {
+            "Ka" | "Kd" | "Ks" => {
+                let v = floats(&args, 1).with_context(context)?;
+                // a single value is a gray
+                let color = if v.len() >= 3 {
+                    [v[0], v[1], v[2]]
+                } else {
+                    [v[0]; 3]
+                };
+                match keyword {
+                    "Ka" => material.ambient = color,
+                    "Kd" => material.diffuse = color,
+                    _ => material.specular = color,
+                }
+            }
+            "Ns" => material.shininess = floats(&args, 1).with_context(context)?[0],
+            "d" => material.opacity = floats(&args, 1).with_context(context)?[0],
+            "Tr" => material.opacity = 1.0 - floats(&args, 1).with_context(context)?[0],
+            // the file name comes after options like `-s 1 1 1`
+            "map_Kd" => match args.last() {
+                Some(file) => material.diffuse_texture = Some(dir.join(file)),
+                None => bail!("line {}: map_Kd without a file", i + 1),
+            },
+            _ => {}
+        }
+    }
+    Ok(materials)
+}
+
+fn floats(args: &[&str], min: usize) -> Result<Vec<f32>> {
+    if args.len() < min {
+        bail!("expected at least {} numbers", min);
+    }
+    args.iter()
+        .map(|a| a.parse::<f32>().map_err(|e| anyhow!("{:?}: {}", a, e)))
+        .collect()
+}
+
+/// Resolves a `v`, `v/vt`, `v//vn` or `v/vt/vn` face vertex to zero-based
+/// indices.
+fn face_vertex(
+    arg: &str,
+    positions: usize,
+    uvs: usize,
+    normals: usize,
+) -> Result<(usize, Option<usize>, Option<usize>)> {
+    let mut parts = arg.split('/');
+    let p = index(parts.next().unwrap_or_default(), positions)?
+        .ok_or_else(|| anyhow!("face vertex {:?} has no position", arg))?;
+    let t = index(parts.next().unwrap_or_default(), uvs)?;
+    let n = index(parts.next().unwrap_or_default(), normals)?;
+    Ok((p, t, n))
+}
+
+/// One-based index, or negative counting back from the end of a list of
+/// `len` elements; empty means none.
+fn index(s: &str, len: usize) -> Result<Option<usize>> {
+    if s.is_empty() {
+        return Ok(None);
+    }
+    let i: i64 = s.parse().map_err(|e| anyhow!("index {:?}: {}", s, e))?;
+    let resolved = if i > 0 { i - 1 } else { len as i64 + i };
+    if i == 0 || resolved < 0 || resolved >= len as i64 {
+        bail!("index {} is out of range for {} elements", i, len);
+    }
+    Ok(Some(resolved as usize))
+}
+
+/// Splits a polygon into triangles by ear clipping in the plane of its
+/// (Newell) normal, so concave faces come out right. Returns indices into
+/// `points`; what cannot be clipped, like a degenerate or self-intersecting
+/// rest, is fanned.
+fn triangulate(points: &[Vector3<f32>]) -> Vec<[usize; 3]> {
+    let n = points.len();
+    let mut normal = Vector3::zero();
+    for i in 0..n {
+        let (a, b) = (points[i], points[(i + 1) % n]);
+        normal += Vector3::new(
+            (a.y - b.y) * (a.z + b.z),
+            (a.z - b.z) * (a.x + b.x),
+            (a.x - b.x) * (a.y + b.y),
+        );
+    }
+    let turns_left = |a: usize, b: usize, c: usize| {
+        (points[b] - points[a])
+            .cross(points[c] - points[b])
+            .dot(normal)
+            > 0.0
+    };
+
+    let mut remaining: Vec<usize> = (0..n).collect();
+    let mut triangles = Vec::with_capacity(n - 2);
+    while remaining.len() > 3 {
+        let m = remaining.len();
+        let corner = |i: usize| {
+            (
+                remaining[(i + m - 1) % m],
+                remaining[i],
+                remaining[(i + 1) % m],
+            )
+        };
+        let ear = (0..m).find(|&i| {
+            let (a, b, c) = corner(i);
+            turns_left(a, b, c)
+                && remaining.iter().all(|&p| {
+                    p == a
+                        || p == b
+                        || p == c
+                        || !(turns_left(a, b, p) && turns_left(b, c, p) && turns_left(c, a, p))
+                })
+        });
+        let Some(i) = ear else {
+            break;
+        };
+        let (a, b, c) = corner(i);
+        triangles.push([a, b, c]);
+        remaining.remove(i);
+    }
+    for i in 1..remaining.len() - 1 {
+        triangles.push([remaining[0], remaining[i], remaining[i + 1]]);
+    }
+    triangles
+}
@@ -0,0 +1,266 @@
+//! An orbit camera for the 3D chapters.
+//!
+//! [`OrbitCamera`] looks at a target point from some distance, and
+//! [`OrbitController`] moves it with the mouse:
+//!
+//! - left drag: rotate around the target, turntable-style (yaw around the
+//!   world y axis, pitch stopping short of the poles) or as an arcball, which
+//!   rotates freely about any axis
+//! - right or middle drag: pan the target in the view plane
+//! - mouse wheel: zoom towards or away from the target
+//! - `m`: switch between turntable and arcball rotation
+//!
+//! The input is queued and applied gradually in
+//! [`OrbitController::update_camera`], so the camera eases out after the
+//! mouse stops instead of halting abruptly.
+
+use cgmath::*;
+use std::time::Duration;
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent},
+    keyboard::Key,
+};
+
+/// Highest elevation of a turntable camera above or below the target.
+const MAX_ELEVATION: Rad<f32> = Rad(89.0 * std::f32::consts::PI / 180.0);
+
+pub struct OrbitCamera {
+    pub target: Point3<f32>,
+    pub distance: f32,
+    /// Orientation of the camera; the eye sits at
+    /// `target + rotation * (0, 0, distance)` and looks down its -z axis.
+    pub rotation: Quaternion<f32>,
+}
+
+impl OrbitCamera {
+    /// A camera at `eye` looking at `target`, takes the same arguments as
+    /// [`crate::transforms::create_view`].
+    pub fn new(eye: Point3<f32>, target: Point3<f32>, up: Vector3<f32>) -> Self {
+        let z = (eye - target).normalize();
+        let x = up.cross(z).normalize();
+        let y = z.cross(x);
+        Self {
+            target,
+            distance: (eye - target).magnitude(),
+            rotation: Matrix3::from_cols(x, y, z).into(),
+        }
+    }
+
+    pub fn eye_position(&self) -> Point3<f32> {
+        self.target + self.rotation * Vector3::new(0.0, 0.0, self.distance)
+    }
+
+    pub fn up(&self) -> Vector3<f32> {
+        self.rotation * Vector3::unit_y()
+    }
+
+    pub fn view_mat(&self) -> Matrix4<f32> {
+        Matrix4::look_at_rh(self.eye_position(), self.target, self.up())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RotateMode {
+    Turntable,
+    Arcball,
+}
+
+pub struct OrbitController {
+    pub mode: RotateMode,
+    /// Turntable rotation in radians per pixel dragged.
+    pub rotate_speed: f32,
+    /// Pan per window height dragged, in multiples of the distance.
+    pub pan_speed: f32,
+    /// Relative change of the distance per line scrolled.
+    pub zoom_speed: f32,
+    /// Fraction of the queued motion that is still left after 1/60 s; 0
+    /// applies the input at once.
+    pub damping: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    rotating: bool,
+    panning: bool,
+    cursor: Option<PhysicalPosition<f64>>,
+    yaw: f32,
+    pitch: f32,
+    // rotation axis in camera space, scaled by the angle
+    arcball: Vector3<f32>,
+    pan: Vector2<f32>,
+    // logarithm of the distance factor
+    zoom: f32,
+}
+
+impl Default for OrbitController {
+    fn default() -> Self {
+        Self {
+            mode: RotateMode::Turntable,
+            rotate_speed: 0.01,
+            pan_speed: 1.5,
+            zoom_speed: 0.1,
+            damping: 0.8,
+            min_distance: 0.5,
+            max_distance: 50.0,
+            rotating: false,
+            panning: false,
+            cursor: None,
+            yaw: 0.0,
+            pitch: 0.0,
+            arcball: Vector3::zero(),
+            pan: Vector2::zero(),
+            zoom: 0.0,
+        }
+    }
+}
+
+impl OrbitController {
+    pub fn with_mode(mut self, mode: RotateMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_damping(mut self, damping: f32) -> Self {
+        self.damping = damping;
+        self
+    }
+
+    /// Queues the motion of a window event; returns `true` if the event was
+    /// consumed. `size` is the size of the window.
+    pub fn process_events(&mut self, event: &WindowEvent, size: PhysicalSize<u32>) -> bool {
+        match event {
+            WindowEvent::MouseInput { state, button, .. } => {
+                let pressed = *state == ElementState::Pressed;
+                match button {
+                    MouseButton::Left => self.rotating = pressed,
+                    MouseButton::Right | MouseButton::Middle => self.panning = pressed,
+                    _ => return false,
+                }
+                true
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let last = self.cursor.replace(*position);
+                let Some(last) = last else {
+                    return false;
+                };
+                let dx = (position.x - last.x) as f32;
+                let dy = (position.y - last.y) as f32;
+                if self.rotating {
+                    match self.mode {
+                        // dragging to the right pulls the front of the scene
+                        // to the right, so the camera moves to the left
+                        RotateMode::Turntable => {
+                            self.yaw -= dx * self.rotate_speed;
+                            self.pitch -= dy * self.rotate_speed;
+                        }
+                        RotateMode::Arcball => {
+                            let from = arcball_point(last, size);
+                            let to = arcball_point(*position, size);
+                            let axis = from.cross(to);
+                            if axis.magnitude2() > 0.0 {
+                                let angle = from.dot(to).clamp(-1.0, 1.0).acos();
+                                self.arcball += axis.normalize() * angle;
+                            }
+                        }
+                    }
+                }
+                if self.panning {
+                    self.pan += Vector2::new(dx, dy) / size.height.max(1) as f32;
+                }
+                self.rotating || self.panning
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor = None;
+                false
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(p) => p.y as f32 / 40.0,
+                };
+                self.zoom -= lines * self.zoom_speed;
+                true
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        logical_key: Key::Character(c),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } if c.as_str() == "m" => {
+                self.mode = match self.mode {
+                    RotateMode::Turntable => RotateMode::Arcball,
+                    RotateMode::Arcball => RotateMode::Turntable,
+                };
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Applies part of the queued motion to `camera`; `dt` is the time since
+    /// the previous frame, as passed to [`crate::app::App::update`].
+    pub fn update_camera(&mut self, camera: &mut OrbitCamera, dt: Duration) {
+        let dt = dt.as_secs_f32();
+        let k = if self.damping <= 0.0 {
+            1.0
+        } else {
+            1.0 - self.damping.powf(dt * 60.0)
+        };
+
+        let yaw = self.yaw * k;
+        self.yaw -= yaw;
+        camera.rotation = Quaternion::from_angle_y(Rad(yaw)) * camera.rotation;
+
+        // a positive pitch about the camera's x axis lowers the eye
+        let pitch = self.pitch * k;
+        self.pitch -= pitch;
+        let elevation = Rad((camera.rotation * Vector3::unit_z())
+            .y
+            .clamp(-1.0, 1.0)
+            .asin());
+        let clamped = (elevation - Rad(pitch))
+            .0
+            .clamp(-MAX_ELEVATION.0, MAX_ELEVATION.0);
+        camera.rotation = camera.rotation * Quaternion::from_angle_x(elevation - Rad(clamped));
+
+        // the camera turns the opposite way the scene is dragged
+        let arcball = self.arcball * k;
+        self.arcball -= arcball;
+        if arcball.magnitude2() > 0.0 {
+            camera.rotation = camera.rotation
+                * Quaternion::from_axis_angle(arcball.normalize(), -Rad(arcball.magnitude()));
+        }
+        camera.rotation = camera.rotation.normalize();
+
+        // the target moves against the drag so the scene follows the cursor
+        let pan = self.pan * k;
+        self.pan -= pan;
+        let scale = self.pan_speed * camera.distance;
+        camera.target += (camera.rotation * Vector3::unit_y() * pan.y
+            - camera.rotation * Vector3::unit_x() * pan.x)
+            * scale;
+
+        let zoom = self.zoom * k;
+        self.zoom -= zoom;
+        camera.distance =
+            (camera.distance * zoom.exp()).clamp(self.min_distance, self.max_distance);
+    }
+}
+
+/// Maps a cursor position onto the arcball: a unit sphere filling the smaller
+/// window side, continued by a hyperbolic sheet outside of it.
+fn arcball_point(position: PhysicalPosition<f64>, size: PhysicalSize<u32>) -> Vector3<f32> {
+    let (w, h) = (size.width as f32, size.height as f32);
+    let r = 0.5 * w.min(h).max(1.0);
+    let x = (position.x as f32 - 0.5 * w) / r;
+    let y = (0.5 * h - position.y as f32) / r;
+    let d2 = x * x + y * y;
+    let z = if d2 <= 0.5 {
+        (1.0 - d2).sqrt()
+    } else {
+        0.5 / d2.sqrt()
+    };
+    Vector3::new(x, y, z).normalize()
+}
@@ -0,0 +1,471 @@
+//! Screen-space overlay for the surface plots: a colorbar for the active
+//! colormap and labelled axes along the plot box.
+//!
+//! [`OverlayBuilder`] generates the geometry on the CPU every frame (the axes
+//! follow the model rotation), and [`Overlay`] draws it on top of the
+//! rendered scene in a separate pass.
+
+use crate::bitmap_font;
+use crate::surface_data::PlotBox;
+use crate::texture_data::Texture;
+use bytemuck::{Pod, Zeroable};
+use cgmath::{InnerSpace, Matrix4, Vector3, Vector4};
+use std::mem;
+
+const TEXT_COLOR: [f32; 4] = [0.9, 0.9, 0.9, 1.0];
+const AXIS_COLOR: [f32; 4] = [0.75, 0.75, 0.75, 1.0];
+const TICK_COUNT: usize = 5;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct OverlayVertex {
+    /// Normalized device coordinates.
+    pub position: [f32; 2],
+    pub color: [f32; 4],
+    /// Colormap coordinate in `[0, 1]`; negative values use `color` instead.
+    pub value: f32,
+}
+
+impl OverlayVertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0=>Float32x2, 1=>Float32x4, 2=>Float32];
+
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<OverlayVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// Horizontal alignment of a text label relative to its anchor point; the
+/// anchor is always at the vertical center of the text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// Collects overlay triangles in pixel coordinates (origin at the top left)
+/// for a target of the given size.
+pub struct OverlayBuilder {
+    width: f32,
+    height: f32,
+    /// Size of a font pixel in screen pixels, also used for line widths.
+    pub scale: f32,
+    vertices: Vec<OverlayVertex>,
+}
+
+impl OverlayBuilder {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width: width as f32,
+            height: height as f32,
+            scale: (height as f32 / 300.0).round().max(1.0),
+            vertices: Vec::new(),
+        }
+    }
+
+    pub fn vertices(&self) -> &[OverlayVertex] {
+        &self.vertices
+    }
+
+    fn push(&mut self, p: [f32; 2], color: [f32; 4], value: f32) {
+        self.vertices.push(OverlayVertex {
+            position: [
+                2.0 * p[0] / self.width - 1.0,
+                1.0 - 2.0 * p[1] / self.height,
+            ],
+            color,
+            value,
+        });
+    }
+
+    fn quad(&mut self, p: [[f32; 2]; 4], color: [f32; 4], values: [f32; 4]) {
+        for i in [0, 1, 2, 2, 3, 0] {
+            self.push(p[i], color, values[i]);
+        }
+    }
+
+    pub fn rect(&mut self, min: [f32; 2], max: [f32; 2], color: [f32; 4]) {
+        let p = [min, [max[0], min[1]], max, [min[0], max[1]]];
+        self.quad(p, color, [-1.0; 4]);
+    }
+
+    /// Rectangle filled with the colormap from `bottom` at the bottom edge to
+    /// `top` at the top edge.
+    pub fn gradient(&mut self, min: [f32; 2], max: [f32; 2], bottom: f32, top: f32) {
+        let p = [min, [max[0], min[1]], max, [min[0], max[1]]];
+        self.quad(p, [1.0; 4], [top, top, bottom, bottom]);
+    }
+
+    /// Line from `a` to `b` that is `width` pixels wide.
+    pub fn line(&mut self, a: [f32; 2], b: [f32; 2], width: f32, color: [f32; 4]) {
+        let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+        let len = (dx * dx + dy * dy).sqrt();
+        if len == 0.0 {
+            return;
+        }
+        let (nx, ny) = (-dy / len * width * 0.5, dx / len * width * 0.5);
+        let p = [
+            [a[0] + nx, a[1] + ny],
+            [b[0] + nx, b[1] + ny],
+            [b[0] - nx, b[1] - ny],
+            [a[0] - nx, a[1] - ny],
+        ];
+        self.quad(p, color, [-1.0; 4]);
+    }
+
+    pub fn text_width(&self, text: &str) -> f32 {
+        bitmap_font::text_width(text) as f32 * self.scale
+    }
+
+    /// Draws `text` with the bitmap font; glyphs are snapped to whole pixels
+    /// so they stay crisp.
+    pub fn text(&mut self, anchor: [f32; 2], text: &str, align: Align, color: [f32; 4]) {
+        let s = self.scale;
+        let width = self.text_width(text);
+        let x0 = match align {
+            Align::Left => anchor[0],
+            Align::Center => anchor[0] - 0.5 * width,
+            Align::Right => anchor[0] - width,
+        }
+        .round();
+        let y0 = (anchor[1] - 0.5 * bitmap_font::GLYPH_HEIGHT as f32 * s).round();
+        let mut pixels = Vec::new();
+        bitmap_font::for_each_pixel(text, |x, y| pixels.push((x as f32, y as f32)));
+        for (x, y) in pixels {
+            let min = [x0 + x * s, y0 + y * s];
+            self.rect(min, [min[0] + s, min[1] + s], color);
+        }
+    }
+
+    /// Vertical colorbar at the right edge for data values in `[min, max]`,
+    /// with tick marks and labels.
+    pub fn colorbar(&mut self, min: f32, max: f32) {
+        let s = self.scale;
+        let (ticks, step) = nice_ticks(min, max, TICK_COUNT);
+        let labels: Vec<String> = ticks.iter().map(|&t| format_tick(t, step)).collect();
+        let label_width = labels
+            .iter()
+            .map(|l| self.text_width(l))
+            .fold(0.0, f32::max);
+
+        let label_x = self.width - 4.0 * s - label_width;
+        let x1 = label_x - 4.0 * s;
+        let x0 = x1 - 8.0 * s;
+        let (y0, y1) = (0.2 * self.height, 0.8 * self.height);
+        self.gradient([x0, y0], [x1, y1], 0.0, 1.0);
+
+        let w = s;
+        self.line([x0, y0], [x1, y0], w, AXIS_COLOR);
+        self.line([x0, y1], [x1, y1], w, AXIS_COLOR);
+        self.line([x0, y0 - 0.5 * w], [x0, y1 + 0.5 * w], w, AXIS_COLOR);
+        self.line([x1, y0 - 0.5 * w], [x1, y1 + 0.5 * w], w, AXIS_COLOR);
+
+        for (t, label) in ticks.iter().zip(&labels) {
+            let f = if max > min {
+                (t - min) / (max - min)
+            } else {
+                0.5
+            };
+            let y = y1 - f * (y1 - y0);
+            self.line([x1, y], [x1 + 2.0 * s, y], w, AXIS_COLOR);
+            self.text([label_x, y], label, Align::Left, TEXT_COLOR);
+        }
+    }
+
+    /// Axes along three edges of `plot_box`, which is transformed to clip
+    /// space by `mvp`. The x and z axes use the bottom edges closest to the
+    /// bottom of the screen and the y axis the leftmost vertical edge.
+    pub fn axes(&mut self, plot_box: &PlotBox, mvp: Matrix4<f32>) {
+        let [x0, y0, z0] = plot_box.world_min;
+        let [x1, y1, z1] = plot_box.world_max;
+        let center = Vector3::new(x0 + x1, y0 + y1, z0 + z1) * 0.5;
+        let project = |p: Vector3<f32>| self.project(mvp, p);
+
+        // candidate edges as (start, end); start is at the minimum of the axis
+        let x_edges = [z0, z1].map(|z| (Vector3::new(x0, y0, z), Vector3::new(x1, y0, z)));
+        let z_edges = [x0, x1].map(|x| (Vector3::new(x, y0, z0), Vector3::new(x, y0, z1)));
+        let y_edges = [(x0, z0), (x0, z1), (x1, z0), (x1, z1)]
+            .map(|(x, z)| (Vector3::new(x, y0, z), Vector3::new(x, y1, z)));
+
+        let mid_screen = |e: &(Vector3<f32>, Vector3<f32>)| project((e.0 + e.1) * 0.5);
+        let lowest = |edges: &[(Vector3<f32>, Vector3<f32>)]| {
+            *edges
+                .iter()
+                .max_by(|a, b| {
+                    let (a, b) = (mid_screen(a), mid_screen(b));
+                    a.map_or(f32::MIN, |p| p[1])
+                        .total_cmp(&b.map_or(f32::MIN, |p| p[1]))
+                })
+                .unwrap()
+        };
+        let leftmost = *y_edges
+            .iter()
+            .min_by(|a, b| {
+                let (a, b) = (mid_screen(a), mid_screen(b));
+                a.map_or(f32::MAX, |p| p[0])
+                    .total_cmp(&b.map_or(f32::MAX, |p| p[0]))
+            })
+            .unwrap();
+
+        let axes = [
+            (0, "x", lowest(&x_edges)),
+            (1, "y", leftmost),
+            (2, "z", lowest(&z_edges)),
+        ];
+        for (axis, name, edge) in axes {
+            self.axis(plot_box, mvp, axis, name, edge, center);
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn axis(
+        &mut self,
+        plot_box: &PlotBox,
+        mvp: Matrix4<f32>,
+        axis: usize,
+        name: &str,
+        (start, end): (Vector3<f32>, Vector3<f32>),
+        center: Vector3<f32>,
+    ) {
+        let s = self.scale;
+        let (Some(a), Some(b)) = (self.project(mvp, start), self.project(mvp, end)) else {
+            return;
+        };
+        self.line(a, b, s, AXIS_COLOR);
+
+        // ticks point away from the box, perpendicular to the axis
+        let mut out = (start + end) * 0.5 - center;
+        out[axis] = 0.0;
+        if out.magnitude2() == 0.0 {
+            return;
+        }
+        let extent = Vector3::from(plot_box.world_max) - Vector3::from(plot_box.world_min);
+        let tick = out.normalize() * 0.04 * extent.magnitude();
+
+        let (min, max) = (plot_box.data_min[axis], plot_box.data_max[axis]);
+        let (ticks, step) = nice_ticks(min, max, TICK_COUNT);
+        for t in ticks {
+            let mut data = plot_box.data_min;
+            data[axis] = t;
+            let mut p = start;
+            p[axis] = plot_box.to_world(data)[axis];
+            let (Some(p0), Some(p1), Some(label)) = (
+                self.project(mvp, p),
+                self.project(mvp, p + tick),
+                self.project(mvp, p + tick * 2.5),
+            ) else {
+                continue;
+            };
+            self.line(p0, p1, s, AXIS_COLOR);
+            self.text(label, &format_tick(t, step), Align::Center, TEXT_COLOR);
+        }
+
+        if let Some(label) = self.project(mvp, (start + end) * 0.5 + tick * 5.0) {
+            self.text(label, name, Align::Center, TEXT_COLOR);
+        }
+    }
+
+    /// Pixel position of a world-space point, or `None` behind the camera.
+    fn project(&self, mvp: Matrix4<f32>, p: Vector3<f32>) -> Option<[f32; 2]> {
+        let clip = mvp * Vector4::new(p.x, p.y, p.z, 1.0);
+        if clip.w <= 0.0 {
+            return None;
+        }
+        Some([
+            (clip.x / clip.w + 1.0) * 0.5 * self.width,
+            (1.0 - clip.y / clip.w) * 0.5 * self.height,
+        ])
+    }
+}
+
+/// About `count` evenly spaced round values (steps of 1, 2 or 5 times a power
+/// of ten) within `[min, max]`, and the step between them.
+pub fn nice_ticks(min: f32, max: f32, count: usize) -> (Vec<f32>, f32) {
+    let range = max - min;
+    if !range.is_finite() || range <= 0.0 {
+        return (vec![min], 1.0);
+    }
+    let rough = range / count.max(1) as f32;
+    let magnitude = 10f32.powf(rough.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|m| m * magnitude)
+        .find(|&step| step >= rough)
+        .unwrap();
+
+    let first = (min / step).ceil() as i64;
+    let last = (max / step + 1e-4).floor() as i64;
+    let ticks = (first..=last).map(|i| i as f32 * step).collect();
+    (ticks, step)
+}
+
+/// Formats a tick value with as many decimals as `step` needs; very large or
+/// small values use exponent notation.
+pub fn format_tick(value: f32, step: f32) -> String {
+    if value == 0.0 {
+        return "0".to_string();
+    }
+    if value.abs() >= 1e5 || step < 1e-4 {
+        return format!("{:.1e}", value);
+    }
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+    format!("{:.*}", decimals, value)
+}
+
+/// Draws [`OverlayVertex`] triangles over a rendered frame; the colorbar
+/// reads the same colormap texture as the surface, so swapping the colormap
+/// updates both.
+pub struct Overlay {
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    vertex_buffer: wgpu::Buffer,
+    capacity: usize,
+    vertex_count: u32,
+}
+
+impl Overlay {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        colormap_texture: &Texture,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Overlay Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("overlay.wgsl").into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("Overlay Bind Group Layout"),
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&colormap_texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&colormap_texture.sampler),
+                },
+            ],
+            label: Some("Overlay Bind Group"),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Overlay Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Overlay Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[OverlayVertex::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+        });
+
+        let capacity = 1024;
+        let vertex_buffer = Self::create_vertex_buffer(device, capacity);
+        Self {
+            pipeline,
+            bind_group,
+            vertex_buffer,
+            capacity,
+            vertex_count: 0,
+        }
+    }
+
+    fn create_vertex_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Overlay Vertex Buffer"),
+            size: (capacity * mem::size_of::<OverlayVertex>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Replaces the overlay geometry, growing the vertex buffer if needed.
+    pub fn update(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        vertices: &[OverlayVertex],
+    ) {
+        if vertices.len() > self.capacity {
+            self.capacity = vertices.len().next_power_of_two();
+            self.vertex_buffer = Self::create_vertex_buffer(device, self.capacity);
+        }
+        queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(vertices));
+        self.vertex_count = vertices.len() as u32;
+    }
+
+    /// Draws the overlay into `color_attachment`, which should load what was
+    /// rendered there before (e.g. `InitWgpu::color_attachment` with
+    /// `LoadOp::Load`).
+    pub fn render(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        color_attachment: wgpu::RenderPassColorAttachment<'_>,
+    ) {
+        if self.vertex_count == 0 {
+            return;
+        }
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Overlay Pass"),
+            color_attachments: &[Some(color_attachment)],
+            depth_stencil_attachment: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.draw(0..self.vertex_count, 0..1);
+    }
+}
@@ -0,0 +1,34 @@
+struct Input {
+    @location(0) position: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) value: f32,
+}
+
+struct Output {
+    @builtin(position) position: vec4<f32>,
+    @location(0) v_color: vec4<f32>,
+    @location(1) v_value: f32,
+};
+
+@vertex
+fn vs_main(in: Input) -> Output {
+    var output: Output;
+    output.position = vec4(in.position, 0.0, 1.0);
+    output.v_color = in.color;
+    output.v_value = in.value;
+    return output;
+}
+
+@group(0) @binding(0) var colormap_texture: texture_2d<f32>;
+@group(0) @binding(1) var colormap_sampler: sampler;
+
+// same lookup as the surface shaders: [0, 1] onto the first and last texel centers
+@fragment
+fn fs_main(in: Output) -> @location(0) vec4<f32> {
+    let u = (clamp(in.v_value, 0.0, 1.0) * 255.0 + 0.5) / 256.0;
+    let mapped = textureSample(colormap_texture, colormap_sampler, vec2(u, 0.5));
+    if (in.v_value < 0.0) {
+        return in.v_color;
+    }
+    return vec4(mapped.rgb, in.v_color.a);
+}
@@ -0,0 +1,221 @@
+//! Metallic-roughness physically based material (Cook-Torrance GGX), the
+//! model glTF and most modern authoring tools export.
+//!
+//! A [`PbrMaterial`] holds the factors and optional textures; on the GPU the
+//! factors go into a [`PbrUniform`] and the textures into a [`PbrTextures`]
+//! bind group. The shading itself is `pbr_lights` in [`PBR_WGSL`].
+
+use crate::texture_data::Texture;
+use bytemuck::{Pod, Zeroable};
+use image::{GrayImage, Rgba, RgbaImage};
+
+/// WGSL of `pbr_lights` and `perturb_normal`. It uses the light array, so the
+/// shader must start with [`crate::light::LIGHT_WGSL`].
+pub const PBR_WGSL: &str = include_str!("pbr.wgsl");
+
+/// Base color, metallic, roughness, normal and occlusion of a surface. The
+/// textures follow the glTF conventions: the base color is sRGB, roughness is
+/// read from the green and metallic from the blue channel of
+/// `metallic_roughness_texture`, occlusion from the red channel of
+/// `occlusion_texture` (so one packed ORM image can serve both), and the
+/// normal map is in tangent space. Each texture is multiplied by its factor.
+#[derive(Clone, Debug)]
+pub struct PbrMaterial {
+    pub base_color: [f32; 4],
+    pub metallic: f32,
+    pub roughness: f32,
+    /// Scales the x and y of the normal map sample.
+    pub normal_scale: f32,
+    /// 0 ignores the occlusion texture, 1 applies it fully.
+    pub occlusion_strength: f32,
+    /// Constant ambient light, multiplied by the base color and occlusion.
+    pub ambient: f32,
+    pub two_side: bool,
+    pub base_color_texture: Option<RgbaImage>,
+    pub metallic_roughness_texture: Option<RgbaImage>,
+    pub normal_texture: Option<RgbaImage>,
+    pub occlusion_texture: Option<RgbaImage>,
+}
+
+impl PbrMaterial {
+    pub fn new(base_color: [f32; 3], metallic: f32, roughness: f32) -> Self {
+        Self {
+            base_color: [base_color[0], base_color[1], base_color[2], 1.0],
+            metallic,
+            roughness,
+            normal_scale: 1.0,
+            occlusion_strength: 1.0,
+            ambient: 0.03,
+            two_side: false,
+            base_color_texture: None,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            occlusion_texture: None,
+        }
+    }
+
+    pub fn with_ambient(mut self, ambient: f32) -> Self {
+        self.ambient = ambient;
+        self
+    }
+
+    pub fn with_two_side(mut self, two_side: bool) -> Self {
+        self.two_side = two_side;
+        self
+    }
+
+    pub fn with_base_color_texture(mut self, image: RgbaImage) -> Self {
+        self.base_color_texture = Some(image);
+        self
+    }
+
+    pub fn with_metallic_roughness_texture(mut self, image: RgbaImage) -> Self {
+        self.metallic_roughness_texture = Some(image);
+        self
+    }
+
+    pub fn with_normal_texture(mut self, image: RgbaImage, scale: f32) -> Self {
+        self.normal_texture = Some(image);
+        self.normal_scale = scale;
+        self
+    }
+
+    pub fn with_occlusion_texture(mut self, image: RgbaImage, strength: f32) -> Self {
+        self.occlusion_texture = Some(image);
+        self.occlusion_strength = strength;
+        self
+    }
+
+    pub fn uniform(&self) -> PbrUniform {
+        PbrUniform {
+            base_color: self.base_color,
+            metallic: self.metallic,
+            roughness: self.roughness,
+            normal_scale: self.normal_scale,
+            occlusion_strength: self.occlusion_strength,
+            ambient: self.ambient,
+            is_two_side: self.two_side as i32,
+            _padding: [0.0; 2],
+        }
+    }
+}
+
+/// Uniform buffer contents of a [`PbrMaterial`]; the matching WGSL struct is
+/// declared by the shaders using it.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct PbrUniform {
+    base_color: [f32; 4],
+    metallic: f32,
+    roughness: f32,
+    normal_scale: f32,
+    occlusion_strength: f32,
+    ambient: f32,
+    is_two_side: i32,
+    _padding: [f32; 2],
+}
+
+/// The textures of a [`PbrMaterial`], with 1x1 neutral textures in place of
+/// the missing ones. They share the sampler of the base color texture.
+pub struct PbrTextures {
+    pub base_color: Texture,
+    pub metallic_roughness: Texture,
+    pub normal: Texture,
+    pub occlusion: Texture,
+}
+
+impl PbrTextures {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        material: &PbrMaterial,
+        u_mode: wgpu::AddressMode,
+        v_mode: wgpu::AddressMode,
+    ) -> Self {
+        let create = |image: &Option<RgbaImage>, neutral: [u8; 4], format| {
+            let solid;
+            let image = match image {
+                Some(image) => image,
+                None => {
+                    solid = RgbaImage::from_pixel(1, 1, Rgba(neutral));
+                    &solid
+                }
+            };
+            Texture::create_texture_from_image(device, queue, image, format, u_mode, v_mode)
+        };
+        let srgb = wgpu::TextureFormat::Rgba8UnormSrgb;
+        let linear = wgpu::TextureFormat::Rgba8Unorm;
+        Self {
+            base_color: create(&material.base_color_texture, [255; 4], srgb),
+            metallic_roughness: create(&material.metallic_roughness_texture, [255; 4], linear),
+            normal: create(&material.normal_texture, [128, 128, 255, 255], linear),
+            occlusion: create(&material.occlusion_texture, [255; 4], linear),
+        }
+    }
+
+    /// Bind group layout entries: the base color texture at binding 0, the
+    /// sampler at 1, then the metallic-roughness, normal and occlusion
+    /// textures at 2 to 4.
+    pub fn layout_entries() -> [wgpu::BindGroupLayoutEntry; 5] {
+        let texture = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                multisampled: false,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            },
+            count: None,
+        };
+        [
+            texture(0),
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+            texture(2),
+            texture(3),
+            texture(4),
+        ]
+    }
+
+    /// Bind group entries matching [`PbrTextures::layout_entries`].
+    pub fn bind_group_entries(&self) -> [wgpu::BindGroupEntry<'_>; 5] {
+        let texture = |binding, view| wgpu::BindGroupEntry {
+            binding,
+            resource: wgpu::BindingResource::TextureView(view),
+        };
+        [
+            texture(0, &self.base_color.view),
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&self.base_color.sampler),
+            },
+            texture(2, &self.metallic_roughness.view),
+            texture(3, &self.normal.view),
+            texture(4, &self.occlusion.view),
+        ]
+    }
+}
+
+/// Tangent-space normal map from a height map, using central differences;
+/// `strength` scales the slopes. Handy when an asset only has a bump map.
+pub fn normal_map_from_height(height: &GrayImage, strength: f32) -> RgbaImage {
+    let (width, rows) = height.dimensions();
+    let h = |x: i64, y: i64| {
+        let x = x.clamp(0, width as i64 - 1) as u32;
+        let y = y.clamp(0, rows as i64 - 1) as u32;
+        height.get_pixel(x, y)[0] as f32 / 255.0
+    };
+    RgbaImage::from_fn(width, rows, |x, y| {
+        let (x, y) = (x as i64, y as i64);
+        let dx = (h(x + 1, y) - h(x - 1, y)) * 0.5 * strength;
+        // image rows grow downwards while the green axis points up
+        let dy = (h(x, y - 1) - h(x, y + 1)) * 0.5 * strength;
+        let len = (dx * dx + dy * dy + 1.0).sqrt();
+        let encode = |c: f32| ((c / len * 0.5 + 0.5) * 255.0).round() as u8;
+        Rgba([encode(-dx), encode(-dy), encode(1.0), 255])
+    })
+}
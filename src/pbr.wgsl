@@ -0,0 +1,85 @@
+// Metallic-roughness material model, see `wgpu_book::pbr`. Needs the light
+// array of light.wgsl.
+
+const PI: f32 = 3.14159265;
+// reflectance of dielectrics at normal incidence
+const DIELECTRIC_F0: f32 = 0.04;
+
+// Trowbridge-Reitz (GGX) normal distribution.
+fn distribution_ggx(NdotH: f32, alpha: f32) -> f32 {
+    let a2 = alpha * alpha;
+    let d = NdotH * NdotH * (a2 - 1.0) + 1.0;
+    return a2 / (PI * d * d);
+}
+
+// Smith masking-shadowing with the Schlick-GGX approximation for direct light.
+fn geometry_smith(NdotV: f32, NdotL: f32, roughness: f32) -> f32 {
+    let r = roughness + 1.0;
+    let k = r * r / 8.0;
+    let gv = NdotV / (NdotV * (1.0 - k) + k);
+    let gl = NdotL / (NdotL * (1.0 - k) + k);
+    return gv * gl;
+}
+
+fn fresnel_schlick(cos_theta: f32, F0: vec3<f32>) -> vec3<f32> {
+    return F0 + (1.0 - F0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+// Cook-Torrance GGX specular plus Lambert diffuse, summed over all lights.
+// `N` must face the viewer. The light colors are multiplied by pi, so a white
+// Lambertian surface is as bright as under the Blinn-Phong diffuse term and
+// both models can share one light array.
+fn pbr_lights(
+    position: vec3<f32>,
+    N: vec3<f32>,
+    V: vec3<f32>,
+    base_color: vec3<f32>,
+    metallic: f32,
+    roughness: f32,
+) -> vec3<f32> {
+    let rough = clamp(roughness, 0.04, 1.0);
+    let alpha = rough * rough;
+    let F0 = mix(vec3(DIELECTRIC_F0), base_color, metallic);
+    let NdotV = max(dot(N, V), 0.0001);
+
+    var color = vec3(0.0);
+    for (var i: u32 = 0u; i < light_count(); i = i + 1u) {
+        let incoming = light_sample(i, position);
+        let L = incoming.L;
+        let NdotL = max(dot(N, L), 0.0);
+        let H = normalize(L + V);
+        let NdotH = max(dot(N, H), 0.0);
+
+        let F = fresnel_schlick(max(dot(H, V), 0.0), F0);
+        let D = distribution_ggx(NdotH, alpha);
+        let G = geometry_smith(NdotV, NdotL, rough);
+        let specular = D * G * F / max(4.0 * NdotV * NdotL, 0.0001);
+        // metals have no diffuse reflection
+        let kd = (vec3(1.0) - F) * (1.0 - metallic);
+
+        color = color + (kd * base_color / PI + specular) * incoming.radiance * PI * NdotL;
+    }
+    return color;
+}
+
+// Applies a tangent-space normal map sample without precomputed tangents: the
+// tangent frame comes from the screen-space derivatives of position and uv.
+// Green points up in the image (towards decreasing v), as in glTF. Take the
+// derivatives in the fragment entry point: the GL backend also compiles the
+// helper functions into the vertex stage, where dpdx does not exist.
+fn perturb_normal(
+    N: vec3<f32>,
+    dp1: vec3<f32>,
+    dp2: vec3<f32>,
+    duv1: vec2<f32>,
+    duv2: vec2<f32>,
+    tangent_normal: vec3<f32>,
+) -> vec3<f32> {
+    let dp2perp = cross(dp2, N);
+    let dp1perp = cross(N, dp1);
+    let T = dp2perp * duv1.x + dp1perp * duv2.x;
+    let B = dp2perp * duv1.y + dp1perp * duv2.y;
+    let scale = inverseSqrt(max(max(dot(T, T), dot(B, B)), 1e-12));
+    let TBN = mat3x3(T * scale, -B * scale, N);
+    return normalize(TBN * tangent_normal);
+}
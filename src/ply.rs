@@ -0,0 +1,355 @@
+//! PLY (Stanford polygon) files, binary or ASCII, which keep the shared
+//! vertices of a mesh with their normals, colors and texture coordinates.
+//!
+//! Files are written with `x y z nx ny nz red green blue s t` vertices,
+//! colors as bytes, and triangle faces. Reading accepts the common variants:
+//! ASCII and both binary byte orders, any scalar types, `u v` or
+//! `texture_u texture_v` for the texture coordinates, polygons (split into
+//! triangle fans) and other elements, which are skipped. A file without
+//! normals gets smooth ones. Like [`crate::obj`], `t` is flipped between the
+//! bottom-left origin of the file and the top-left origin of wgpu textures.
+
+use crate::export::Encoding;
+use crate::mesh::{Mesh, MeshVertex};
+use anyhow::{anyhow, bail, Context, Result};
+use std::io::Write;
+use std::path::Path;
+
+/// Writes `mesh` with its shared vertices.
+pub fn write<W: Write>(mesh: &Mesh, w: &mut W, encoding: Encoding) -> Result<()> {
+    let format = match encoding {
+        Encoding::Ascii => "ascii",
+        Encoding::Binary => "binary_little_endian",
+    };
+    writeln!(w, "ply")?;
+    writeln!(w, "format {} 1.0", format)?;
+    writeln!(w, "comment wgpu_book mesh")?;
+    writeln!(w, "element vertex {}", mesh.vertices.len())?;
+    for name in ["x", "y", "z", "nx", "ny", "nz"] {
+        writeln!(w, "property float {}", name)?;
+    }
+    for name in ["red", "green", "blue"] {
+        writeln!(w, "property uchar {}", name)?;
+    }
+    writeln!(w, "property float s")?;
+    writeln!(w, "property float t")?;
+    writeln!(w, "element face {}", mesh.indices.len() / 3)?;
+    writeln!(w, "property list uchar uint vertex_indices")?;
+    writeln!(w, "end_header")?;
+
+    let color = |c: [f32; 3]| c.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+    match encoding {
+        Encoding::Ascii => {
+            for v in &mesh.vertices {
+                let [x, y, z] = v.position;
+                let [nx, ny, nz] = v.normal;
+                let [r, g, b] = color(v.color);
+                let [s, t] = [v.uv[0], 1.0 - v.uv[1]];
+                writeln!(
+                    w,
+                    "{} {} {} {} {} {} {} {} {} {} {}",
+                    x, y, z, nx, ny, nz, r, g, b, s, t
+                )?;
+            }
+            for tri in mesh.indices.chunks_exact(3) {
+                writeln!(w, "3 {} {} {}", tri[0], tri[1], tri[2])?;
+            }
+        }
+        Encoding::Binary => {
+            for v in &mesh.vertices {
+                for x in v.position.iter().chain(&v.normal) {
+                    w.write_all(&x.to_le_bytes())?;
+                }
+                w.write_all(&color(v.color))?;
+                for x in [v.uv[0], 1.0 - v.uv[1]] {
+                    w.write_all(&x.to_le_bytes())?;
+                }
+            }
+            for tri in mesh.indices.chunks_exact(3) {
+                w.write_all(&[3])?;
+                for i in tri {
+                    w.write_all(&i.to_le_bytes())?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Mesh> {
+    let path = path.as_ref();
+    let data = std::fs::read(path).with_context(|| format!("cannot read {}", path.display()))?;
+    read(&data).with_context(|| format!("cannot load {}", path.display()))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn from_name(name: &str) -> Result<Self> {
+        Ok(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => bail!("unknown property type {:?}", name),
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    /// Largest value of an integer type, by which colors are divided.
+    fn color_scale(self) -> f64 {
+        match self {
+            Scalar::U16 => 65535.0,
+            Scalar::F32 | Scalar::F64 => 1.0,
+            _ => 255.0,
+        }
+    }
+}
+
+struct Property {
+    name: String,
+    ty: Scalar,
+    /// Type of the item count of a list property.
+    count: Option<Scalar>,
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// The values of the body, one after the other.
+enum Body<'a> {
+    Ascii(std::str::SplitAsciiWhitespace<'a>),
+    Binary { data: &'a [u8], big_endian: bool },
+}
+
+impl Body<'_> {
+    fn next(&mut self, ty: Scalar) -> Result<f64> {
+        match self {
+            Body::Ascii(tokens) => {
+                let token = tokens
+                    .next()
+                    .ok_or_else(|| anyhow!("the file ends too early"))?;
+                // floats are parsed as such so they round-trip exactly
+                let x = match ty {
+                    Scalar::F32 => token.parse::<f32>().map(f64::from),
+                    _ => token.parse::<f64>(),
+                };
+                x.map_err(|_| anyhow!("invalid number {:?}", token))
+            }
+            Body::Binary { data, big_endian } => {
+                if data.len() < ty.size() {
+                    bail!("the file ends too early");
+                }
+                let (bytes, rest) = data.split_at(ty.size());
+                *data = rest;
+                let mut b = [0u8; 8];
+                b[..bytes.len()].copy_from_slice(bytes);
+                if *big_endian {
+                    b[..bytes.len()].reverse();
+                }
+                Ok(match ty {
+                    Scalar::I8 => b[0] as i8 as f64,
+                    Scalar::U8 => b[0] as f64,
+                    Scalar::I16 => i16::from_le_bytes([b[0], b[1]]) as f64,
+                    Scalar::U16 => u16::from_le_bytes([b[0], b[1]]) as f64,
+                    Scalar::I32 => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    Scalar::U32 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    Scalar::F32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    Scalar::F64 => f64::from_le_bytes(b),
+                })
+            }
+        }
+    }
+}
+
+/// Parses a PLY file.
+pub fn read(data: &[u8]) -> Result<Mesh> {
+    const END: &[u8] = b"end_header";
+    if !data.starts_with(b"ply") {
+        bail!("not a PLY file");
+    }
+    let end = data
+        .windows(END.len())
+        .position(|w| w == END)
+        .ok_or_else(|| anyhow!("the header has no end_header"))?;
+    let header = std::str::from_utf8(&data[..end]).context("the header must be text")?;
+    // the body starts after the line break ending the header
+    let mut body_start = end + END.len();
+    if data.get(body_start) == Some(&b'\r') {
+        body_start += 1;
+    }
+    body_start += 1;
+    let body_data = data.get(body_start..).unwrap_or_default();
+
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    for (i, line) in header.lines().enumerate().skip(1) {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let context = || format!("header line {}: {}", i + 1, line.trim());
+        match tokens.as_slice() {
+            ["format", name, _] => format = Some(name.to_string()),
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| anyhow!("invalid count"))
+                    .with_context(context)?,
+                properties: Vec::new(),
+            }),
+            ["property", rest @ ..] => {
+                let element = elements
+                    .last_mut()
+                    .ok_or_else(|| anyhow!("a property comes before any element"))
+                    .with_context(context)?;
+                let property = match rest {
+                    ["list", count, ty, name] => Property {
+                        name: name.to_string(),
+                        ty: Scalar::from_name(ty).with_context(context)?,
+                        count: Some(Scalar::from_name(count).with_context(context)?),
+                    },
+                    [ty, name] => Property {
+                        name: name.to_string(),
+                        ty: Scalar::from_name(ty).with_context(context)?,
+                        count: None,
+                    },
+                    _ => bail!("{}: malformed property", context()),
+                };
+                element.properties.push(property);
+            }
+            _ => {}
+        }
+    }
+    let mut body = match format.as_deref() {
+        Some("ascii") => Body::Ascii(
+            std::str::from_utf8(body_data)
+                .context("an ASCII PLY file must be text")?
+                .split_ascii_whitespace(),
+        ),
+        Some("binary_little_endian") => Body::Binary {
+            data: body_data,
+            big_endian: false,
+        },
+        Some("binary_big_endian") => Body::Binary {
+            data: body_data,
+            big_endian: true,
+        },
+        f => bail!("unknown PLY format {:?}", f),
+    };
+
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let mut has_normals = false;
+    for element in &elements {
+        match element.name.as_str() {
+            "vertex" => {
+                has_normals = element.properties.iter().any(|p| p.name == "nx");
+                vertices.reserve(element.count);
+                for _ in 0..element.count {
+                    let mut v = MeshVertex {
+                        color: [1.0; 3],
+                        ..Default::default()
+                    };
+                    for property in &element.properties {
+                        let Some(count) = property.count else {
+                            let x = body.next(property.ty)?;
+                            set_vertex_property(&mut v, &property.name, x, property.ty);
+                            continue;
+                        };
+                        let n = body.next(count)? as usize;
+                        for _ in 0..n {
+                            body.next(property.ty)?;
+                        }
+                    }
+                    vertices.push(v);
+                }
+            }
+            "face" => {
+                indices.reserve(3 * element.count);
+                for f in 0..element.count {
+                    for property in &element.properties {
+                        let Some(count) = property.count else {
+                            body.next(property.ty)?;
+                            continue;
+                        };
+                        let n = body.next(count)? as usize;
+                        let mut polygon = Vec::with_capacity(n);
+                        for _ in 0..n {
+                            polygon.push(body.next(property.ty)? as u32);
+                        }
+                        if !matches!(property.name.as_str(), "vertex_indices" | "vertex_index") {
+                            continue;
+                        }
+                        if let Some(&i) = polygon.iter().find(|&&i| i as usize >= vertices.len()) {
+                            bail!("face {} uses vertex {} of {}", f, i, vertices.len());
+                        }
+                        for k in 1..polygon.len().saturating_sub(1) {
+                            indices.extend([polygon[0], polygon[k], polygon[k + 1]]);
+                        }
+                    }
+                }
+            }
+            _ => {
+                for _ in 0..element.count {
+                    for property in &element.properties {
+                        let n = match property.count {
+                            Some(count) => body.next(count)? as usize,
+                            None => 1,
+                        };
+                        for _ in 0..n {
+                            body.next(property.ty)?;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut mesh = Mesh { vertices, indices };
+    if !has_normals {
+        mesh.compute_smooth_normals();
+    }
+    Ok(mesh)
+}
+
+fn set_vertex_property(v: &mut MeshVertex, name: &str, x: f64, ty: Scalar) {
+    let x32 = x as f32;
+    match name {
+        "x" => v.position[0] = x32,
+        "y" => v.position[1] = x32,
+        "z" => v.position[2] = x32,
+        "nx" => v.normal[0] = x32,
+        "ny" => v.normal[1] = x32,
+        "nz" => v.normal[2] = x32,
+        "red" | "r" => v.color[0] = (x / ty.color_scale()) as f32,
+        "green" | "g" => v.color[1] = (x / ty.color_scale()) as f32,
+        "blue" | "b" => v.color[2] = (x / ty.color_scale()) as f32,
+        "s" | "u" | "texture_u" => v.uv[0] = x32,
+        "t" | "v" | "texture_v" => v.uv[1] = 1.0 - x32,
+        _ => {}
+    }
+}
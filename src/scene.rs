@@ -0,0 +1,302 @@
+//! Scene graph: a tree of nodes with local translation/rotation/scale
+//! transforms, optionally referring to a mesh and a material by index.
+//!
+//! World matrices are cached per node. Changing a node's transform or parent
+//! only marks it and its descendants dirty; the matrices are recomputed on the
+//! next [`SceneGraph::update`], [`SceneGraph::world_matrix`] or
+//! [`SceneGraph::draw_list`]. A dirty node always has dirty descendants, so
+//! marking stops at nodes that are already dirty.
+
+use crate::transforms;
+use anyhow::{bail, Result};
+use cgmath::{Matrix, Matrix4, SquareMatrix};
+
+/// Local transform of a node, applied as in
+/// [`transforms::create_transforms`]: scale, then rotation about x, y and z
+/// (in radians), then translation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub translation: [f32; 3],
+    pub rotation: [f32; 3],
+    pub scale: [f32; 3],
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            translation: [0.0; 3],
+            rotation: [0.0; 3],
+            scale: [1.0; 3],
+        }
+    }
+}
+
+impl Transform {
+    pub fn new(translation: [f32; 3], rotation: [f32; 3], scale: [f32; 3]) -> Self {
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn from_translation(translation: [f32; 3]) -> Self {
+        Self {
+            translation,
+            ..Default::default()
+        }
+    }
+
+    pub fn matrix(&self) -> Matrix4<f32> {
+        transforms::create_transforms(self.translation, self.rotation, self.scale)
+    }
+}
+
+/// Index of a node in its [`SceneGraph`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SceneNode {
+    pub name: String,
+    /// Index of the mesh drawn at this node; grouping nodes have none.
+    pub mesh: Option<usize>,
+    pub material: Option<usize>,
+    transform: Transform,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    world: Matrix4<f32>,
+    normal: Matrix4<f32>,
+    dirty: bool,
+}
+
+impl SceneNode {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            mesh: None,
+            material: None,
+            transform: Transform::default(),
+            parent: None,
+            children: Vec::new(),
+            world: Matrix4::identity(),
+            normal: Matrix4::identity(),
+            dirty: true,
+        }
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    pub fn with_mesh(mut self, mesh: usize) -> Self {
+        self.mesh = Some(mesh);
+        self
+    }
+
+    pub fn with_material(mut self, material: usize) -> Self {
+        self.material = Some(material);
+        self
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+}
+
+/// A node to draw, with its world and normal matrices.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DrawItem {
+    pub node: NodeId,
+    pub mesh: usize,
+    pub material: Option<usize>,
+    pub world: Matrix4<f32>,
+    /// Inverse transpose of `world`, for transforming normals.
+    pub normal: Matrix4<f32>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SceneGraph {
+    nodes: Vec<SceneNode>,
+    roots: Vec<NodeId>,
+}
+
+impl SceneGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `node` as the last child of `parent`, or as a new root.
+    pub fn add(&mut self, parent: Option<NodeId>, mut node: SceneNode) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        node.parent = parent;
+        node.children.clear();
+        node.dirty = true;
+        match parent {
+            Some(p) => self.nodes[p.0].children.push(id),
+            None => self.roots.push(id),
+        }
+        self.nodes.push(node);
+        id
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    pub fn node(&self, id: NodeId) -> &SceneNode {
+        &self.nodes[id.0]
+    }
+
+    /// The node for changing its mesh or material; transforms and parents
+    /// are changed through the graph so the cached matrices stay valid.
+    pub fn node_mut(&mut self, id: NodeId) -> &mut SceneNode {
+        &mut self.nodes[id.0]
+    }
+
+    /// The first node called `name`.
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.nodes.iter().position(|n| n.name == name).map(NodeId)
+    }
+
+    pub fn is_dirty(&self, id: NodeId) -> bool {
+        self.nodes[id.0].dirty
+    }
+
+    pub fn set_transform(&mut self, id: NodeId, transform: Transform) {
+        self.nodes[id.0].transform = transform;
+        self.mark_dirty(id);
+    }
+
+    /// Moves `id` under `parent`, or makes it a root; fails if `parent` is
+    /// `id` itself or one of its descendants.
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) -> Result<()> {
+        let mut ancestor = parent;
+        while let Some(a) = ancestor {
+            if a == id {
+                bail!(
+                    "cannot move node {} ({}) under its own subtree",
+                    id.0,
+                    self.nodes[id.0].name
+                );
+            }
+            ancestor = self.nodes[a.0].parent;
+        }
+        match self.nodes[id.0].parent {
+            Some(old) => self.nodes[old.0].children.retain(|&c| c != id),
+            None => self.roots.retain(|&r| r != id),
+        }
+        match parent {
+            Some(p) => self.nodes[p.0].children.push(id),
+            None => self.roots.push(id),
+        }
+        self.nodes[id.0].parent = parent;
+        self.mark_dirty(id);
+        Ok(())
+    }
+
+    fn mark_dirty(&mut self, id: NodeId) {
+        let mut stack = vec![id];
+        while let Some(i) = stack.pop() {
+            let node = &mut self.nodes[i.0];
+            // the descendants of a dirty node are dirty already
+            if node.dirty && i != id {
+                continue;
+            }
+            node.dirty = true;
+            stack.extend_from_slice(&node.children);
+        }
+    }
+
+    /// Recomputes the world matrix of a dirty node whose parent is clean.
+    fn refresh(&mut self, id: NodeId) {
+        let parent = match self.nodes[id.0].parent {
+            Some(p) => self.nodes[p.0].world,
+            None => Matrix4::identity(),
+        };
+        let node = &mut self.nodes[id.0];
+        node.world = parent * node.transform.matrix();
+        node.normal = node
+            .world
+            .invert()
+            .unwrap_or(Matrix4::identity())
+            .transpose();
+        node.dirty = false;
+    }
+
+    /// World matrix of `id`, recomputing only it and its dirty ancestors.
+    pub fn world_matrix(&mut self, id: NodeId) -> Matrix4<f32> {
+        // clean nodes have clean ancestors
+        let mut path = Vec::new();
+        let mut current = Some(id);
+        while let Some(i) = current {
+            if !self.nodes[i.0].dirty {
+                break;
+            }
+            path.push(i);
+            current = self.nodes[i.0].parent;
+        }
+        for &i in path.iter().rev() {
+            self.refresh(i);
+        }
+        self.nodes[id.0].world
+    }
+
+    /// Recomputes all dirty world matrices; returns how many there were.
+    pub fn update(&mut self) -> usize {
+        let mut updated = 0;
+        let mut stack: Vec<NodeId> = self.roots.iter().rev().copied().collect();
+        while let Some(id) = stack.pop() {
+            if self.nodes[id.0].dirty {
+                self.refresh(id);
+                updated += 1;
+            }
+            stack.extend(self.nodes[id.0].children.iter().rev());
+        }
+        updated
+    }
+
+    /// The nodes with a mesh in depth-first order, parents before children.
+    pub fn draw_list(&mut self) -> Vec<DrawItem> {
+        self.update();
+        let mut items = Vec::new();
+        let mut stack: Vec<NodeId> = self.roots.iter().rev().copied().collect();
+        while let Some(id) = stack.pop() {
+            let node = &self.nodes[id.0];
+            if let Some(mesh) = node.mesh {
+                items.push(DrawItem {
+                    node: id,
+                    mesh,
+                    material: node.material,
+                    world: node.world,
+                    normal: node.normal,
+                });
+            }
+            stack.extend(node.children.iter().rev());
+        }
+        items
+    }
+}
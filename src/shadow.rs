@@ -0,0 +1,377 @@
+//! Shadow mapping for the lit chapters.
+//!
+//! The shadow casters are rendered from the point of view of one light into a
+//! depth texture; the fragment shaders (through [`crate::light::LIGHT_WGSL`])
+//! compare against it with a comparison sampler and 3x3 PCF. Only directional
+//! and spot lights cast shadows, since a point light would need a cube map.
+
+use crate::light::{Light, LightKind};
+use crate::transforms::OPENGL_TO_WGPU_MATRIX;
+use bytemuck::{Pod, Zeroable};
+use cgmath::{ortho, perspective, InnerSpace, Matrix4, Point3, Rad, SquareMatrix, Vector3};
+use wgpu::util::DeviceExt;
+
+pub const SHADOW_MAP_SIZE: u32 = 2048;
+pub const SHADOW_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+/// Subtracted from the light-space depth before the comparison to avoid
+/// shadow acne.
+pub const SHADOW_BIAS: f32 = 0.002;
+
+/// Index of the light that casts shadows: the first directional or spot light.
+pub fn shadow_light(lights: &[Light]) -> Option<usize> {
+    lights
+        .iter()
+        .position(|l| !matches!(l.kind, LightKind::Point { .. }))
+}
+
+/// View-projection matrix of `light` that covers the shadow casters within
+/// `radius` of `center`; `None` for point lights.
+pub fn light_view_projection(
+    light: &Light,
+    center: Point3<f32>,
+    radius: f32,
+) -> Option<Matrix4<f32>> {
+    match light.kind {
+        LightKind::Directional { direction } => {
+            let direction = Vector3::from(direction).normalize();
+            let eye = center - direction * 3.0 * radius;
+            let view = Matrix4::look_at_rh(eye, center, up_vector(direction));
+            let project = ortho(-radius, radius, -radius, radius, radius, 6.0 * radius);
+            Some(OPENGL_TO_WGPU_MATRIX * project * view)
+        }
+        LightKind::Spot {
+            position,
+            direction,
+            outer,
+            ..
+        } => {
+            let direction = Vector3::from(direction).normalize();
+            let eye = Point3::from(position);
+            let view = Matrix4::look_at_rh(eye, eye + direction, up_vector(direction));
+            let far = (center - eye).magnitude() + 2.0 * radius;
+            let fov = Rad::from(outer * 2.0).0.clamp(0.1, 3.0);
+            let project = perspective(Rad(fov), 1.0, 0.1, far);
+            Some(OPENGL_TO_WGPU_MATRIX * project * view)
+        }
+        LightKind::Point { .. } => None,
+    }
+}
+
+// any up vector that is not parallel to the viewing direction
+fn up_vector(direction: Vector3<f32>) -> Vector3<f32> {
+    if direction.y.abs() > 0.99 {
+        Vector3::unit_z()
+    } else {
+        Vector3::unit_y()
+    }
+}
+
+// Matches `ShadowUniforms` in shadow.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct ShadowUniform {
+    light_view_project: [[f32; 4]; 4],
+    params: [f32; 4],
+}
+
+/// Size of the light matrix and model matrix of one caster in the depth pass.
+const CASTER_SIZE: u64 = 128;
+
+/// Depth texture and depth-only pipeline of the shadow pass.
+pub struct ShadowMap {
+    pub view: wgpu::TextureView,
+    pub sampler: wgpu::Sampler,
+    /// Light matrix and parameters read by the fragment shaders.
+    pub uniform_buffer: wgpu::Buffer,
+    pipeline: wgpu::RenderPipeline,
+    pass_uniform_buffer: wgpu::Buffer,
+    pass_bind_group_layout: wgpu::BindGroupLayout,
+    pass_bind_group: wgpu::BindGroup,
+    light_view_project: Matrix4<f32>,
+    /// Distance between the uniforms of consecutive casters.
+    caster_stride: u64,
+    casters: usize,
+}
+
+impl ShadowMap {
+    /// Creates the shadow map for the first shadow-casting light in `lights`.
+    /// `vertex_layout` is the layout of the caster's vertex buffer; only its
+    /// `Float32x4` position at location 0 is read.
+    pub fn new(
+        device: &wgpu::Device,
+        vertex_layout: wgpu::VertexBufferLayout,
+        cull_mode: Option<wgpu::Face>,
+        lights: &[Light],
+        center: Point3<f32>,
+        radius: f32,
+    ) -> Self {
+        let caster = shadow_light(lights)
+            .and_then(|i| light_view_projection(&lights[i], center, radius).map(|m| (i, m)));
+        let (index, light_view_project) = match caster {
+            Some((i, m)) => (i as f32, m),
+            None => (-1.0, Matrix4::identity()),
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Shadow Map"),
+            size: wgpu::Extent3d {
+                width: SHADOW_MAP_SIZE,
+                height: SHADOW_MAP_SIZE,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: SHADOW_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        // linear filtering blends the comparison results of neighboring texels
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Shadow Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Default::default()
+        });
+
+        let uniform = ShadowUniform {
+            light_view_project: light_view_project.into(),
+            params: [index, SHADOW_BIAS, 1.0 / SHADOW_MAP_SIZE as f32, 0.0],
+        };
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Shadow Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[uniform]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        // light_view_project and model_mat of the depth pass, selected per
+        // caster with a dynamic offset
+        let pass_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(CASTER_SIZE),
+                    },
+                    count: None,
+                }],
+                label: Some("Shadow Pass Bind Group Layout"),
+            });
+        let caster_stride = CASTER_SIZE
+            .next_multiple_of(device.limits().min_uniform_buffer_offset_alignment as u64);
+        let (pass_uniform_buffer, pass_bind_group) =
+            caster_buffer(device, &pass_bind_group_layout, caster_stride, 1);
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shadow Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shadow_depth.wgsl").into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Shadow Pipeline Layout"),
+            bind_group_layouts: &[&pass_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Shadow Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[vertex_layout],
+            },
+            fragment: None,
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: SHADOW_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState {
+                    constant: 2,
+                    slope_scale: 2.0,
+                    clamp: 0.0,
+                },
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            view,
+            sampler,
+            uniform_buffer,
+            pipeline,
+            pass_uniform_buffer,
+            pass_bind_group_layout,
+            pass_bind_group,
+            light_view_project,
+            caster_stride,
+            casters: 1,
+        }
+    }
+
+    /// Makes room for `casters` model matrices, for scenes whose shadow
+    /// casters are drawn with different transforms; see
+    /// [`ShadowMap::update_casters`] and [`ShadowMap::set_caster`].
+    pub fn with_casters(mut self, device: &wgpu::Device, casters: usize) -> Self {
+        let casters = casters.max(1);
+        (self.pass_uniform_buffer, self.pass_bind_group) = caster_buffer(
+            device,
+            &self.pass_bind_group_layout,
+            self.caster_stride,
+            casters,
+        );
+        self.casters = casters;
+        self
+    }
+
+    /// Bind group layout entries for bindings 4 to 6 of group 0, where
+    /// [`crate::light::LIGHT_WGSL`] expects the shadow map.
+    pub fn layout_entries() -> [wgpu::BindGroupLayoutEntry; 3] {
+        [
+            wgpu::BindGroupLayoutEntry {
+                binding: 4,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 5,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Depth,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 6,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                count: None,
+            },
+        ]
+    }
+
+    /// Bind group entries matching [`ShadowMap::layout_entries`].
+    pub fn bind_group_entries(&self) -> [wgpu::BindGroupEntry<'_>; 3] {
+        [
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: self.uniform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 5,
+                resource: wgpu::BindingResource::TextureView(&self.view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 6,
+                resource: wgpu::BindingResource::Sampler(&self.sampler),
+            },
+        ]
+    }
+
+    /// Sets the model matrix of the shadow casters.
+    pub fn update(&self, queue: &wgpu::Queue, model_mat: Matrix4<f32>) {
+        self.update_casters(queue, &[model_mat]);
+    }
+
+    /// Sets the model matrices of the casters selected with
+    /// [`ShadowMap::set_caster`]; there may be as many as given to
+    /// [`ShadowMap::with_casters`].
+    pub fn update_casters(&self, queue: &wgpu::Queue, model_mats: &[Matrix4<f32>]) {
+        assert!(
+            model_mats.len() <= self.casters,
+            "{} shadow casters, but room for {}",
+            model_mats.len(),
+            self.casters
+        );
+        let light_view_project: &[f32; 16] = self.light_view_project.as_ref();
+        let mut data = vec![0u8; self.caster_stride as usize * model_mats.len()];
+        for (chunk, model_mat) in data
+            .chunks_exact_mut(self.caster_stride as usize)
+            .zip(model_mats)
+        {
+            let model_ref: &[f32; 16] = model_mat.as_ref();
+            chunk[..64].copy_from_slice(bytemuck::cast_slice(light_view_project));
+            chunk[64..128].copy_from_slice(bytemuck::cast_slice(model_ref));
+        }
+        queue.write_buffer(&self.pass_uniform_buffer, 0, &data);
+    }
+
+    /// Selects the model matrix of caster `index` for the next draws of the
+    /// depth pass.
+    pub fn set_caster<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, index: usize) {
+        render_pass.set_bind_group(
+            0,
+            &self.pass_bind_group,
+            &[(index as u64 * self.caster_stride) as u32],
+        );
+    }
+
+    /// Starts the depth pass with the shadow pipeline and the first caster
+    /// bound; the caller sets the vertex (and index) buffer of the casters and
+    /// draws them.
+    pub fn begin_pass<'a>(&'a self, encoder: &'a mut wgpu::CommandEncoder) -> wgpu::RenderPass<'a> {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Shadow Pass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        self.set_caster(&mut render_pass, 0);
+        render_pass
+    }
+}
+
+fn caster_buffer(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    stride: u64,
+    casters: usize,
+) -> (wgpu::Buffer, wgpu::BindGroup) {
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Shadow Pass Uniform Buffer"),
+        size: stride * casters as u64,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                buffer: &buffer,
+                offset: 0,
+                size: wgpu::BufferSize::new(CASTER_SIZE),
+            }),
+        }],
+        label: Some("Shadow Pass Bind Group"),
+    });
+    (buffer, bind_group)
+}
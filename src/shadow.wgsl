@@ -0,0 +1,38 @@
+// Shadow map of one light, see `wgpu_book::shadow`.
+
+struct ShadowUniforms {
+    light_view_project: mat4x4<f32>,
+    // x: index of the shadow-casting light (negative if none), y: depth bias,
+    // z: size of a shadow map texel in uv units
+    params: vec4<f32>,
+};
+@group(0) @binding(4) var<uniform> shadow: ShadowUniforms;
+@group(0) @binding(5) var shadow_map: texture_depth_2d;
+@group(0) @binding(6) var shadow_sampler: sampler_comparison;
+
+// Fraction of light `light_index` reaching `position`, filtered over 3x3
+// shadow map texels (PCF). Points outside the shadow map are lit.
+fn shadow_visibility(light_index: u32, position: vec3<f32>) -> f32 {
+    if (shadow.params.x < 0.0 || u32(shadow.params.x) != light_index) {
+        return 1.0;
+    }
+    let clip = shadow.light_view_project * vec4(position, 1.0);
+    if (clip.w <= 0.0) {
+        return 1.0;
+    }
+    let ndc = clip.xyz / clip.w;
+    let uv = vec2(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+    if (any(uv < vec2(0.0)) || any(uv > vec2(1.0)) || ndc.z > 1.0) {
+        return 1.0;
+    }
+
+    let depth = ndc.z - shadow.params.y;
+    var sum: f32 = 0.0;
+    for (var y: i32 = -1; y <= 1; y = y + 1) {
+        for (var x: i32 = -1; x <= 1; x = x + 1) {
+            let offset = vec2(f32(x), f32(y)) * shadow.params.z;
+            sum = sum + textureSampleCompareLevel(shadow_map, shadow_sampler, uv + offset, depth);
+        }
+    }
+    return sum / 9.0;
+}
@@ -0,0 +1,10 @@
+struct Uniforms {
+    light_view_project: mat4x4<f32>,
+    model_mat: mat4x4<f32>,
+};
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+
+@vertex
+fn vs_main(@location(0) pos: vec4<f32>) -> @builtin(position) vec4<f32> {
+    return uniforms.light_view_project * uniforms.model_mat * pos;
+}
@@ -0,0 +1,159 @@
+//! STL files, binary or ASCII, as used for 3D printing.
+//!
+//! STL stores nothing but triangles with a facet normal each, so a written
+//! mesh loses its shared vertices, vertex normals, colors and texture
+//! coordinates; a read mesh has three white vertices per triangle that carry
+//! the facet normal.
+
+use crate::export::Encoding;
+use crate::mesh::{Mesh, MeshVertex};
+use anyhow::{anyhow, bail, Context, Result};
+use cgmath::*;
+use std::io::Write;
+use std::path::Path;
+
+/// Size of the binary header and of a binary triangle record.
+const HEADER_SIZE: usize = 80;
+const TRIANGLE_SIZE: usize = 50;
+
+/// Writes the triangles of `mesh`; the facet normals are computed from the
+/// positions.
+pub fn write<W: Write>(mesh: &Mesh, w: &mut W, encoding: Encoding) -> Result<()> {
+    let triangles = mesh.indices.chunks_exact(3).map(|tri| {
+        let p = [0, 1, 2].map(|k| mesh.vertices[tri[k] as usize].position);
+        (facet_normal(p), p)
+    });
+    match encoding {
+        Encoding::Binary => {
+            // the header must not start with "solid", which marks ASCII files
+            let mut header = [b' '; HEADER_SIZE];
+            header[..15].copy_from_slice(b"wgpu_book mesh\0");
+            w.write_all(&header)?;
+            w.write_all(&(mesh.indices.len() as u32 / 3).to_le_bytes())?;
+            for (normal, p) in triangles {
+                for v in std::iter::once(normal).chain(p).flatten() {
+                    w.write_all(&v.to_le_bytes())?;
+                }
+                // attribute byte count
+                w.write_all(&[0, 0])?;
+            }
+        }
+        Encoding::Ascii => {
+            writeln!(w, "solid wgpu_book")?;
+            for ([nx, ny, nz], p) in triangles {
+                writeln!(w, "  facet normal {} {} {}", nx, ny, nz)?;
+                writeln!(w, "    outer loop")?;
+                for [x, y, z] in p {
+                    writeln!(w, "      vertex {} {} {}", x, y, z)?;
+                }
+                writeln!(w, "    endloop")?;
+                writeln!(w, "  endfacet")?;
+            }
+            writeln!(w, "endsolid wgpu_book")?;
+        }
+    }
+    Ok(())
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Mesh> {
+    let path = path.as_ref();
+    let data = std::fs::read(path).with_context(|| format!("cannot read {}", path.display()))?;
+    read(&data).with_context(|| format!("cannot load {}", path.display()))
+}
+
+/// Parses a binary or ASCII STL file. Binary files whose header happens to
+/// start with `solid` are told apart by their size.
+pub fn read(data: &[u8]) -> Result<Mesh> {
+    let binary_size = data.get(HEADER_SIZE..HEADER_SIZE + 4).map(|n| {
+        HEADER_SIZE + 4 + TRIANGLE_SIZE * u32::from_le_bytes(n.try_into().unwrap()) as usize
+    });
+    if data.starts_with(b"solid") && binary_size != Some(data.len()) {
+        let text = std::str::from_utf8(data).context("an ASCII STL file must be text")?;
+        return read_ascii(text);
+    }
+    let Some(size) = binary_size else {
+        bail!("the file is too short for a binary STL file");
+    };
+    if data.len() < size {
+        bail!("the binary STL file is truncated");
+    }
+    let mut facets = Vec::new();
+    for record in data[HEADER_SIZE + 4..size].chunks_exact(TRIANGLE_SIZE) {
+        let f = |k: usize| f32::from_le_bytes(record[4 * k..4 * k + 4].try_into().unwrap());
+        facets.push((
+            [f(0), f(1), f(2)],
+            [[f(3), f(4), f(5)], [f(6), f(7), f(8)], [f(9), f(10), f(11)]],
+        ));
+    }
+    Ok(mesh(facets))
+}
+
+fn read_ascii(text: &str) -> Result<Mesh> {
+    let mut facets = Vec::new();
+    let mut normal = [0.0; 3];
+    let mut corners = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let mut tokens = line.split_whitespace();
+        let context = || format!("line {}: {}", i + 1, line.trim());
+        match tokens.next() {
+            Some("facet") => {
+                // "facet normal nx ny nz"
+                normal = floats(tokens.skip(1)).with_context(context)?;
+                corners.clear();
+            }
+            Some("vertex") => corners.push(floats(tokens).with_context(context)?),
+            Some("endfacet") => {
+                let corners: [[f32; 3]; 3] = corners
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| anyhow!("a facet has {} vertices", corners.len()))
+                    .with_context(context)?;
+                facets.push((normal, corners));
+            }
+            _ => {}
+        }
+    }
+    Ok(mesh(facets))
+}
+
+fn floats<'a>(tokens: impl Iterator<Item = &'a str>) -> Result<[f32; 3]> {
+    let v = tokens
+        .map(|t| {
+            t.parse::<f32>()
+                .map_err(|_| anyhow!("invalid number {:?}", t))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    v.as_slice()
+        .try_into()
+        .map_err(|_| anyhow!("expected 3 numbers, found {}", v.len()))
+}
+
+/// Triangles with their own vertices; a missing facet normal is computed.
+fn mesh(facets: Vec<([f32; 3], [[f32; 3]; 3])>) -> Mesh {
+    let mut vertices = Vec::with_capacity(3 * facets.len());
+    for (normal, p) in facets {
+        let normal = if Vector3::from(normal).magnitude2() > 0.0 {
+            normal
+        } else {
+            facet_normal(p)
+        };
+        vertices.extend(p.map(|position| MeshVertex {
+            position,
+            normal,
+            color: [1.0; 3],
+            ..Default::default()
+        }));
+    }
+    let indices = (0..vertices.len() as u32).collect();
+    Mesh { vertices, indices }
+}
+
+fn facet_normal(p: [[f32; 3]; 3]) -> [f32; 3] {
+    let [p0, p1, p2] = p.map(Vector3::from);
+    let n = (p1 - p0).cross(p2 - p0);
+    if n.magnitude2() > 0.0 {
+        n.normalize().into()
+    } else {
+        [0.0; 3]
+    }
+}
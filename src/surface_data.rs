@@ -0,0 +1,560 @@
+use crate::colormap::Colormap;
+use crate::mesh::{Mesh, MeshVertex, Shading};
+use cgmath::*;
+
+#[allow(clippy::too_many_arguments)]
+fn normalize_point(
+    pt: [f32; 3],
+    xmin: f32,
+    xmax: f32,
+    ymin: f32,
+    ymax: f32,
+    zmin: f32,
+    zmax: f32,
+    scale: f32,
+) -> [f32; 3] {
+    let px = scale * (-1.0 + 2.0 * (pt[0] - xmin) / (xmax - xmin));
+    let py = scale * (-1.0 + 2.0 * (pt[1] - ymin) / (ymax - ymin));
+    let pz = scale * (-1.0 + 2.0 * (pt[2] - zmin) / (zmax - zmin));
+    [px, py, pz]
+}
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+fn create_quad(
+    p0: [f32; 3],
+    p1: [f32; 3],
+    p2: [f32; 3],
+    p3: [f32; 3],
+    t: [[f32; 2]; 4],
+    ymin: f32,
+    ymax: f32,
+    colormap: &Colormap,
+) -> (
+    Vec<[f32; 3]>,
+    Vec<[f32; 3]>,
+    Vec<[f32; 3]>,
+    Vec<[f32; 2]>,
+    Vec<[f32; 2]>,
+) {
+    // position
+    let position: Vec<[f32; 3]> = vec![p0, p1, p2, p2, p3, p0];
+
+    // normal
+    let ca = Vector3::new(p2[0] - p0[0], p2[1] - p0[1], p2[2] - p0[2]);
+    let db = Vector3::new(p3[0] - p1[0], p3[1] - p1[1], p3[2] - p1[2]);
+    let cp = (ca.cross(db)).normalize();
+
+    let normal: Vec<[f32; 3]> = vec![[cp[0], cp[1], cp[2]]; 6];
+
+    // color
+
+    let c0 = colormap.color_lerp(ymin, ymax, p0[1]);
+    let c1 = colormap.color_lerp(ymin, ymax, p1[1]);
+    let c2 = colormap.color_lerp(ymin, ymax, p2[1]);
+    let c3 = colormap.color_lerp(ymin, ymax, p3[1]);
+
+    let color: Vec<[f32; 3]> = vec![c0, c1, c2, c2, c3, c0];
+
+    // uv: normalized grid coordinates
+    let uv: Vec<[f32; 2]> = vec![t[0], t[1], t[2], t[2], t[3], t[0]];
+
+    // uv1: position along the colormap, sampled in the middle of a colormap strip texture
+    let [t0, t1, t2, t3] = [p0, p1, p2, p3].map(|p| [colormap_coord(ymin, ymax, p[1]), 0.5]);
+    let uv1: Vec<[f32; 2]> = vec![t0, t1, t2, t2, t3, t0];
+
+    (position, normal, color, uv, uv1)
+}
+
+/// Maps `y` in `[ymin, ymax]` to the `[0, 1]` coordinate of a colormap lookup.
+fn colormap_coord(ymin: f32, ymax: f32, y: f32) -> f32 {
+    if ymax > ymin {
+        ((y - ymin) / (ymax - ymin)).clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+/// The data ranges of a surface plot and the world-space box they are drawn
+/// in, e.g. for labelling its axes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlotBox {
+    pub data_min: [f32; 3],
+    pub data_max: [f32; 3],
+    pub world_min: [f32; 3],
+    pub world_max: [f32; 3],
+}
+
+impl PlotBox {
+    /// Maps a data point linearly into the world-space box.
+    pub fn to_world(&self, p: [f32; 3]) -> [f32; 3] {
+        std::array::from_fn(|i| {
+            let range = self.data_max[i] - self.data_min[i];
+            let t = if range != 0.0 {
+                (p[i] - self.data_min[i]) / range
+            } else {
+                0.5
+            };
+            self.world_min[i] + t * (self.world_max[i] - self.world_min[i])
+        })
+    }
+}
+
+// Samples `f` on the x/z grid and maps the points into the `[-scale, scale]`
+// cube; also returns the plot box, whose y range is the one used for the
+// colormap.
+#[allow(clippy::too_many_arguments)]
+fn surface_points(
+    f: &dyn Fn(f32, f32) -> [f32; 3],
+    xmin: f32,
+    xmax: f32,
+    zmin: f32,
+    zmax: f32,
+    nx: usize,
+    nz: usize,
+    scale: f32,
+    scaley: f32,
+) -> (Vec<Vec<[f32; 3]>>, PlotBox) {
+    let dx = (xmax - xmin) / (nx as f32 - 1.0);
+    let dz = (zmax - zmin) / (nz as f32 - 1.0);
+    let mut ymin1: f32 = 0.0;
+    let mut ymax1: f32 = 0.0;
+    let mut pts: Vec<Vec<[f32; 3]>> = vec![vec![Default::default(); nz]; nx];
+    for (i, row) in pts.iter_mut().enumerate() {
+        let x = xmin + i as f32 * dx;
+        let mut pt1: Vec<[f32; 3]> = Vec::with_capacity(nz);
+        for j in 0..nz {
+            let z = zmin + j as f32 * dz;
+            let pt = f(x, z);
+            pt1.push(pt);
+            ymin1 = if pt[1] < ymin1 { pt[1] } else { ymin1 };
+            ymax1 = if pt[1] > ymax1 { pt[1] } else { ymax1 };
+        }
+        *row = pt1;
+    }
+
+    let ymin = ymin1 - scaley * (ymax1 - ymin1);
+    let ymax = ymax1 + scaley * (ymax1 - ymin1);
+    for row in pts.iter_mut() {
+        for pt in row.iter_mut() {
+            *pt = normalize_point(*pt, xmin, xmax, ymin, ymax, zmin, zmax, scale);
+        }
+    }
+
+    let cmin = normalize_point([0.0, ymin1, 0.0], xmin, xmax, ymin, ymax, zmin, zmax, scale)[1];
+    let cmax = normalize_point([0.0, ymax1, 0.0], xmin, xmax, ymin, ymax, zmin, zmax, scale)[1];
+    let plot_box = PlotBox {
+        data_min: [xmin, ymin1, zmin],
+        data_max: [xmax, ymax1, zmax],
+        world_min: [-scale, cmin, -scale],
+        world_max: [scale, cmax, scale],
+    };
+    (pts, plot_box)
+}
+
+/// Plot box of the surface built by [`simple_surface_data`] or
+/// [`surface_mesh`] with the same arguments; its y range is the range covered
+/// by the colormap.
+#[allow(clippy::too_many_arguments)]
+pub fn surface_plot_box(
+    f: &dyn Fn(f32, f32) -> [f32; 3],
+    xmin: f32,
+    xmax: f32,
+    zmin: f32,
+    zmax: f32,
+    nx: usize,
+    nz: usize,
+    scale: f32,
+    scaley: f32,
+) -> PlotBox {
+    surface_points(f, xmin, xmax, zmin, zmax, nx, nz, scale, scaley).1
+}
+
+/// Samples the height field `f(x, z)` on an `nx` x `nz` grid and returns the
+/// positions, normals, colors, uvs and colormap coordinates of a flat-shaded
+/// triangle list.
+///
+/// The uvs are the grid coordinates normalized to `[0, 1]`, so a texture
+/// covers the whole surface once. The colormap coordinates hold the height
+/// mapped to `[0, 1]` in `u` and `0.5` in `v`, for looking the color up in a
+/// colormap texture instead of using the baked colors.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn simple_surface_data(
+    f: &dyn Fn(f32, f32) -> [f32; 3],
+    colormap: &Colormap,
+    xmin: f32,
+    xmax: f32,
+    zmin: f32,
+    zmax: f32,
+    nx: usize,
+    nz: usize,
+    scale: f32,
+    scaley: f32,
+) -> (
+    Vec<[f32; 3]>,
+    Vec<[f32; 3]>,
+    Vec<[f32; 3]>,
+    Vec<[f32; 2]>,
+    Vec<[f32; 2]>,
+) {
+    let (pts, plot_box) = surface_points(f, xmin, xmax, zmin, zmax, nx, nz, scale, scaley);
+    let (cmin, cmax) = (plot_box.world_min[1], plot_box.world_max[1]);
+
+    let mut positions: Vec<[f32; 3]> = Vec::with_capacity(6 * (nx - 1) * (nz - 1));
+    let mut normals: Vec<[f32; 3]> = Vec::with_capacity(6 * (nx - 1) * (nz - 1));
+    let mut colors: Vec<[f32; 3]> = Vec::with_capacity(6 * (nx - 1) * (nz - 1));
+    let mut uvs: Vec<[f32; 2]> = Vec::with_capacity(6 * (nx - 1) * (nz - 1));
+    let mut uv1: Vec<[f32; 2]> = Vec::with_capacity(6 * (nx - 1) * (nz - 1));
+
+    let grid_uv = |i: usize, j: usize| [i as f32 / (nx as f32 - 1.0), j as f32 / (nz as f32 - 1.0)];
+
+    for i in 0..nx - 1 {
+        for j in 0..nz - 1 {
+            let p0 = pts[i][j];
+            let p1 = pts[i][j + 1];
+            let p2 = pts[i + 1][j + 1];
+            let p3 = pts[i + 1][j];
+            let t = [
+                grid_uv(i, j),
+                grid_uv(i, j + 1),
+                grid_uv(i + 1, j + 1),
+                grid_uv(i + 1, j),
+            ];
+            let (mut pos, mut norm, mut col, mut uv, mut uv_1) =
+                create_quad(p0, p1, p2, p3, t, cmin, cmax, colormap);
+            // positions
+            positions.append(&mut pos);
+            // normals
+            normals.append(&mut norm);
+            // colors
+            colors.append(&mut col);
+            // texture coordinates
+            uvs.append(&mut uv);
+            uv1.append(&mut uv_1);
+        }
+    }
+    (positions, normals, colors, uvs, uv1)
+}
+
+/// Same surface as [`simple_surface_data`], but as an indexed [`Mesh`] whose
+/// grid points are shared by the neighboring cells.
+#[allow(clippy::too_many_arguments)]
+pub fn surface_mesh(
+    f: &dyn Fn(f32, f32) -> [f32; 3],
+    colormap: &Colormap,
+    xmin: f32,
+    xmax: f32,
+    zmin: f32,
+    zmax: f32,
+    nx: usize,
+    nz: usize,
+    scale: f32,
+    scaley: f32,
+    shading: Shading,
+) -> Mesh {
+    let (pts, plot_box) = surface_points(f, xmin, xmax, zmin, zmax, nx, nz, scale, scaley);
+    let (cmin, cmax) = (plot_box.world_min[1], plot_box.world_max[1]);
+
+    let mut vertices: Vec<MeshVertex> = Vec::with_capacity(nx * nz);
+    for (i, row) in pts.iter().enumerate() {
+        for (j, &position) in row.iter().enumerate() {
+            vertices.push(MeshVertex {
+                position,
+                color: colormap.color_lerp(cmin, cmax, position[1]),
+                uv: [i as f32 / (nx as f32 - 1.0), j as f32 / (nz as f32 - 1.0)],
+                scalar: colormap_coord(cmin, cmax, position[1]),
+                ..Default::default()
+            });
+        }
+    }
+
+    // same winding as create_quad: p0 = (i, j), p1 = (i, j + 1), p2 = (i + 1, j + 1), p3 = (i + 1, j)
+    let mut indices: Vec<u32> = Vec::with_capacity(6 * (nx - 1) * (nz - 1));
+    for i in 0..nx - 1 {
+        for j in 0..nz - 1 {
+            let p0 = (i * nz + j) as u32;
+            let p1 = p0 + 1;
+            let p2 = p1 + nz as u32;
+            let p3 = p0 + nz as u32;
+            indices.extend_from_slice(&[p0, p1, p2, p2, p3, p0]);
+        }
+    }
+
+    Mesh { vertices, indices }.with_shading(shading)
+}
+
+/// Scalar that colors a [`parametric_surface`] through the colormap.
+#[derive(Clone, Copy)]
+pub enum ColorBy<'a> {
+    X,
+    Y,
+    Z,
+    U,
+    V,
+    /// Any function of the parameters and the surface point.
+    Scalar(&'a dyn Fn(f32, f32, [f32; 3]) -> f32),
+}
+
+impl ColorBy<'_> {
+    fn value(&self, u: f32, v: f32, position: [f32; 3]) -> f32 {
+        match self {
+            ColorBy::X => position[0],
+            ColorBy::Y => position[1],
+            ColorBy::Z => position[2],
+            ColorBy::U => u,
+            ColorBy::V => v,
+            ColorBy::Scalar(s) => s(u, v, position),
+        }
+    }
+}
+
+/// How a [`parametric_surface`] continues past the end of a parameter range.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Seam {
+    #[default]
+    Open,
+    /// The surface wraps around, e.g. the longitude of a sphere: the last row
+    /// of samples lies on the first.
+    Closed,
+    /// The surface wraps around with a half twist, as a Möbius strip: the last
+    /// row lies on the first with the other parameter reversed, and the
+    /// normals flip across the seam.
+    Twisted,
+}
+
+/// Options for [`parametric_surface`]; the defaults give an open surface with
+/// finite-difference normals, colored by height with the `jet` colormap.
+#[derive(Clone)]
+pub struct SurfaceOptions<'a> {
+    pub seam_u: Seam,
+    pub seam_v: Seam,
+    /// Analytic normal `n(u, v)`; finite differences of `f` are used if `None`.
+    pub normal: Option<&'a dyn Fn(f32, f32) -> [f32; 3]>,
+    pub colormap: Colormap,
+    pub color_by: ColorBy<'a>,
+    /// Centers the surface and scales it uniformly to fit in `[-scale, scale]^3`.
+    pub scale: Option<f32>,
+}
+
+impl Default for SurfaceOptions<'_> {
+    fn default() -> Self {
+        Self {
+            seam_u: Seam::Open,
+            seam_v: Seam::Open,
+            normal: None,
+            colormap: Colormap::from_name("jet").unwrap(),
+            color_by: ColorBy::Y,
+            scale: None,
+        }
+    }
+}
+
+/// Builds an indexed mesh of the surface `f(u, v)` sampled on an `nu` x `nv`
+/// grid over `u_range` x `v_range`, with uvs normalized to `[0, 1]`.
+///
+/// Closed and twisted directions still duplicate the seam vertices so the
+/// texture coordinates run from 0 to 1, but their normals are computed across
+/// the seam so the shading has no visible crease; `f` is evaluated slightly
+/// past the end of their ranges for that.
+///
+/// Panics if `nu` or `nv` is less than 2.
+pub fn parametric_surface(
+    f: &dyn Fn(f32, f32) -> [f32; 3],
+    u_range: [f32; 2],
+    v_range: [f32; 2],
+    nu: usize,
+    nv: usize,
+    options: &SurfaceOptions,
+) -> Mesh {
+    assert!(
+        nu >= 2 && nv >= 2,
+        "a parametric surface needs at least 2 x 2 samples, got {} x {}",
+        nu,
+        nv
+    );
+    let du = (u_range[1] - u_range[0]) / (nu as f32 - 1.0);
+    let dv = (v_range[1] - v_range[0]) / (nv as f32 - 1.0);
+
+    let mut vertices: Vec<MeshVertex> = Vec::with_capacity(nu * nv);
+    let mut scalars: Vec<f32> = Vec::with_capacity(nu * nv);
+    for i in 0..nu {
+        for j in 0..nv {
+            let u = u_range[0] + i as f32 * du;
+            let v = v_range[0] + j as f32 * dv;
+            let position = f(u, v);
+            let normal = match options.normal {
+                Some(n) => n(u, v),
+                None => {
+                    let fu = difference(&|t| f(t, v), u, u_range, du, options.seam_u);
+                    let fv = difference(&|t| f(u, t), v, v_range, dv, options.seam_v);
+                    fu.cross(fv).into()
+                }
+            };
+            scalars.push(options.color_by.value(u, v, position));
+            vertices.push(MeshVertex {
+                position,
+                normal,
+                uv: [i as f32 / (nu as f32 - 1.0), j as f32 / (nv as f32 - 1.0)],
+                ..Default::default()
+            });
+        }
+    }
+
+    let smin = scalars.iter().cloned().fold(f32::INFINITY, f32::min);
+    let smax = scalars.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+    for (vertex, s) in vertices.iter_mut().zip(scalars) {
+        vertex.color = options.colormap.color_lerp(smin, smax, s);
+        vertex.scalar = colormap_coord(smin, smax, s);
+    }
+
+    let mut indices: Vec<u32> = Vec::with_capacity(6 * (nu - 1) * (nv - 1));
+    for i in 0..nu - 1 {
+        for j in 0..nv - 1 {
+            let p0 = (i * nv + j) as u32;
+            let p1 = p0 + nv as u32;
+            let p2 = p1 + 1;
+            let p3 = p0 + 1;
+            indices.extend_from_slice(&[p0, p1, p2, p2, p3, p0]);
+        }
+    }
+    let mut mesh = Mesh { vertices, indices };
+
+    // derivatives vanish at poles and cusps; fall back to the averaged face
+    // normals there, with the two sides of a seam joined so both copies of a
+    // seam vertex get the same normal, or the opposite one across a twist
+    let mut sums = mesh.normal_sums();
+    for (a, b, sign) in seam_pairs(nu, nv, options.seam_u, options.seam_v) {
+        let n = sums[a] + sign * sums[b];
+        (sums[a], sums[b]) = (n, sign * n);
+    }
+    for (vertex, sum) in mesh.vertices.iter_mut().zip(sums) {
+        let n = Vector3::from(vertex.normal);
+        if n.magnitude2() > 1e-12 {
+            vertex.normal = n.normalize().into();
+        } else if sum.magnitude2() > 0.0 {
+            vertex.normal = sum.normalize().into();
+        }
+    }
+
+    if let Some(scale) = options.scale {
+        fit_to_cube(&mut mesh, scale);
+    }
+    mesh
+}
+
+/// Plot box of the surface built by [`parametric_surface`] with the same
+/// arguments. Its x and z ranges are the bounds of the surface, and like
+/// [`surface_plot_box`] its y range is the range covered by the colormap,
+/// here the range of the `color_by` scalar.
+pub fn parametric_plot_box(
+    f: &dyn Fn(f32, f32) -> [f32; 3],
+    u_range: [f32; 2],
+    v_range: [f32; 2],
+    nu: usize,
+    nv: usize,
+    options: &SurfaceOptions,
+) -> PlotBox {
+    let du = (u_range[1] - u_range[0]) / (nu.max(2) as f32 - 1.0);
+    let dv = (v_range[1] - v_range[0]) / (nv.max(2) as f32 - 1.0);
+    let mut positions: Vec<[f32; 3]> = Vec::with_capacity(nu * nv);
+    let (mut smin, mut smax) = (f32::INFINITY, f32::NEG_INFINITY);
+    for i in 0..nu {
+        for j in 0..nv {
+            let u = u_range[0] + i as f32 * du;
+            let v = v_range[0] + j as f32 * dv;
+            let position = f(u, v);
+            let s = options.color_by.value(u, v, position);
+            smin = smin.min(s);
+            smax = smax.max(s);
+            positions.push(position);
+        }
+    }
+
+    let (min, max) = bounds(&positions);
+    let (world_min, world_max) = match (options.scale, fit_scale(min, max)) {
+        (Some(scale), Some(size)) => {
+            let s = 2.0 * scale / size;
+            let half: [f32; 3] = std::array::from_fn(|k| 0.5 * s * (max[k] - min[k]));
+            (half.map(|h| -h), half)
+        }
+        _ => (min, max),
+    };
+    PlotBox {
+        data_min: [min[0], smin, min[2]],
+        data_max: [max[0], smax, max[2]],
+        world_min,
+        world_max,
+    }
+}
+
+// Central difference of `g` at `t`; open directions switch to one-sided
+// differences at the ends so `g` is never evaluated outside `range`.
+/// The vertices of an `nu` x `nv` grid that lie on each other across its
+/// seams, with the sign of the normal of the second relative to the first; the
+/// u seam comes first, so the corners of a surface closed in both directions
+/// end up with one normal.
+pub fn seam_pairs(nu: usize, nv: usize, seam_u: Seam, seam_v: Seam) -> Vec<(usize, usize, f32)> {
+    let mut pairs = Vec::new();
+    for j in 0..nv {
+        match seam_u {
+            Seam::Open => {}
+            Seam::Closed => pairs.push((j, (nu - 1) * nv + j, 1.0)),
+            Seam::Twisted => pairs.push((nv - 1 - j, (nu - 1) * nv + j, -1.0)),
+        }
+    }
+    for i in 0..nu {
+        match seam_v {
+            Seam::Open => {}
+            Seam::Closed => pairs.push((i * nv, i * nv + nv - 1, 1.0)),
+            Seam::Twisted => pairs.push(((nu - 1 - i) * nv, i * nv + nv - 1, -1.0)),
+        }
+    }
+    pairs
+}
+
+fn difference(
+    g: &dyn Fn(f32) -> [f32; 3],
+    t: f32,
+    range: [f32; 2],
+    step: f32,
+    seam: Seam,
+) -> Vector3<f32> {
+    let h = 0.5 * step;
+    let (a, b) = if seam != Seam::Open {
+        (t - h, t + h)
+    } else {
+        ((t - h).max(range[0]), (t + h).min(range[1]))
+    };
+    (Vector3::from(g(b)) - Vector3::from(g(a))) / (b - a)
+}
+
+fn bounds(points: &[[f32; 3]]) -> ([f32; 3], [f32; 3]) {
+    let mut min = [f32::INFINITY; 3];
+    let mut max = [f32::NEG_INFINITY; 3];
+    for p in points {
+        for k in 0..3 {
+            min[k] = min[k].min(p[k]);
+            max[k] = max[k].max(p[k]);
+        }
+    }
+    (min, max)
+}
+
+// Largest extent of the bounds, if there is something to scale.
+fn fit_scale(min: [f32; 3], max: [f32; 3]) -> Option<f32> {
+    let size = (0..3).map(|k| max[k] - min[k]).fold(0.0, f32::max);
+    (size > 0.0).then_some(size)
+}
+
+fn fit_to_cube(mesh: &mut Mesh, scale: f32) {
+    let positions: Vec<[f32; 3]> = mesh.vertices.iter().map(|v| v.position).collect();
+    let (min, max) = bounds(&positions);
+    let Some(size) = fit_scale(min, max) else {
+        return;
+    };
+    let s = 2.0 * scale / size;
+    for v in mesh.vertices.iter_mut() {
+        for k in 0..3 {
+            v.position[k] = s * (v.position[k] - 0.5 * (min[k] + max[k]));
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use anyhow::*;
+use image::io::Reader as ImageReader;
+use image::RgbaImage;
+
+use crate::colormap::Colormap;
+
+/// Number of texels in a colormap lookup texture.
+pub const COLORMAP_WIDTH: u32 = 256;
+
+pub struct Texture {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub sampler: wgpu::Sampler,
+}
+
+impl Texture {
+    pub fn create_texture_data(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        img_file: &str,
+        u_mode: wgpu::AddressMode,
+        v_mode: wgpu::AddressMode,
+    ) -> Result<Self> {
+        let img = ImageReader::open(img_file)?.decode()?;
+        Ok(Self::create_texture_from_image(
+            device,
+            queue,
+            &img.to_rgba8(),
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            u_mode,
+            v_mode,
+        ))
+    }
+
+    /// Uploads an image already in memory. Color images use
+    /// `Rgba8UnormSrgb`; data such as normal or roughness maps must use
+    /// `Rgba8Unorm` so the shader reads the stored values unchanged.
+    pub fn create_texture_from_image(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        rgba: &RgbaImage,
+        format: wgpu::TextureFormat,
+        u_mode: wgpu::AddressMode,
+        v_mode: wgpu::AddressMode,
+    ) -> Self {
+        let dimensions = rgba.dimensions();
+
+        let size = wgpu::Extent3d {
+            width: dimensions.0,
+            height: dimensions.1,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Image Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            rgba,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * dimensions.0),
+                rows_per_image: Some(dimensions.1),
+            },
+            size,
+        );
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: u_mode,
+            address_mode_v: v_mode,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+        }
+    }
+
+    /// Creates a `COLORMAP_WIDTH` x 1 lookup texture for the colormap. The
+    /// colors are stored as plain `Rgba8Unorm`, the same values the vertex
+    /// colors used to carry. In the shader, sample it at
+    /// `vec2((t * (COLORMAP_WIDTH - 1) + 0.5) / COLORMAP_WIDTH, 0.5)` for
+    /// `t` in `[0, 1]` so both ends hit the texel centers.
+    pub fn create_colormap_texture(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        colormap: &Colormap,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Colormap Texture"),
+            size: wgpu::Extent3d {
+                width: COLORMAP_WIDTH,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let colormap_texture = Self {
+            texture,
+            view,
+            sampler,
+        };
+        colormap_texture.write_colormap(queue, colormap);
+        colormap_texture
+    }
+
+    /// Replaces the contents of a texture made by `create_colormap_texture`;
+    /// bind groups using it stay valid, so the colormap can change at runtime.
+    pub fn write_colormap(&self, queue: &wgpu::Queue, colormap: &Colormap) {
+        let texels = colormap.texels(COLORMAP_WIDTH);
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            bytemuck::cast_slice(&texels),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * COLORMAP_WIDTH),
+                rows_per_image: Some(1),
+            },
+            self.texture.size(),
+        );
+    }
+}
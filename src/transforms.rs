@@ -0,0 +1,387 @@
+use crate::depth::DepthConfig;
+use crate::msaa::{self, MsaaTarget};
+use anyhow::{anyhow, Result};
+use cgmath::*;
+use image::RgbaImage;
+use std::f32::consts::PI;
+use winit::window::Window;
+
+pub fn create_transforms(
+    translation: [f32; 3],
+    rotation: [f32; 3],
+    scaling: [f32; 3],
+) -> Matrix4<f32> {
+    let trans_mat =
+        Matrix4::from_translation(Vector3::new(translation[0], translation[1], translation[2]));
+    let rotate_mat_x = Matrix4::from_angle_x(Rad(rotation[0]));
+    let rotate_mat_y = Matrix4::from_angle_y(Rad(rotation[1]));
+    let rotate_mat_z = Matrix4::from_angle_z(Rad(rotation[2]));
+    let scale_mat = Matrix4::from_nonuniform_scale(scaling[0], scaling[1], scaling[2]);
+    trans_mat * rotate_mat_z * rotate_mat_y * rotate_mat_x * scale_mat
+}
+
+pub struct InitWgpu {
+    /// `None` when rendering headless into `offscreen`.
+    pub surface: Option<wgpu::Surface>,
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    pub config: wgpu::SurfaceConfiguration,
+    pub size: winit::dpi::PhysicalSize<u32>,
+    /// Render target used instead of the swapchain when there is no window.
+    pub offscreen: Option<wgpu::Texture>,
+    /// Multisampled color target selected with `WGPU_BOOK_MSAA`, see [`crate::msaa`].
+    pub msaa: MsaaTarget,
+}
+
+/// The texture a frame is rendered into: either the current swapchain
+/// texture or the offscreen texture of a headless `InitWgpu`.
+pub struct Frame {
+    pub view: wgpu::TextureView,
+    output: Option<wgpu::SurfaceTexture>,
+}
+
+impl Frame {
+    pub fn present(self) {
+        if let Some(output) = self.output {
+            output.present();
+        }
+    }
+}
+
+impl InitWgpu {
+    pub async fn init_wgpu(window: &Window) -> Self {
+        let size = window.inner_size();
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+
+        let surface = unsafe { instance.create_surface(&window) }.unwrap();
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                force_fallback_adapter: false,
+                compatible_surface: Some(&surface),
+            })
+            .await
+            .expect("Failed to find an appropriate adapter");
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    features: msaa::device_features(&adapter),
+                    limits: wgpu::Limits::default(),
+                },
+                None,
+            )
+            .await
+            .expect("Failed to create device");
+
+        let swapchain_capabilities = surface.get_capabilities(&adapter);
+        let swapchain_format = swapchain_capabilities.formats[0];
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: swapchain_format,
+            width: size.width,
+            height: size.height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: swapchain_capabilities.alpha_modes[0],
+            view_formats: vec![],
+        };
+
+        surface.configure(&device, &config);
+        let msaa = create_msaa(&adapter, &device, &config);
+
+        Self {
+            surface: Some(surface),
+            device,
+            queue,
+            config,
+            size,
+            offscreen: None,
+            msaa,
+        }
+    }
+
+    /// Creates a device without a window. Frames are rendered into an offscreen
+    /// `Rgba8UnormSrgb` texture that can be read back with `read_pixels`.
+    pub async fn init_headless(
+        width: u32,
+        height: u32,
+        force_fallback_adapter: bool,
+    ) -> Result<Self> {
+        let size = winit::dpi::PhysicalSize::new(width, height);
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                force_fallback_adapter,
+                compatible_surface: None,
+            })
+            .await
+            .ok_or_else(|| anyhow!("Failed to find an appropriate adapter"))?;
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    features: msaa::device_features(&adapter),
+                    limits: wgpu::Limits::default(),
+                },
+                None,
+            )
+            .await?;
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            width: size.width,
+            height: size.height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+        };
+
+        let offscreen = create_offscreen_texture(&device, &config);
+        let msaa = create_msaa(&adapter, &device, &config);
+
+        Ok(Self {
+            surface: None,
+            device,
+            queue,
+            config,
+            size,
+            offscreen: Some(offscreen),
+            msaa,
+        })
+    }
+
+    /// Reconfigures the surface, or recreates the offscreen texture, for `new_size`.
+    /// A zero size (minimized window) is only recorded, see `is_minimized`.
+    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+        self.size = new_size;
+        if self.is_minimized() {
+            return;
+        }
+        self.config.width = new_size.width;
+        self.config.height = new_size.height;
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device, &self.config);
+        }
+        if self.offscreen.is_some() {
+            self.offscreen = Some(create_offscreen_texture(&self.device, &self.config));
+        }
+        self.msaa
+            .resize(&self.device, new_size.width, new_size.height);
+    }
+
+    /// Whether the window has no area to draw into; rendering should pause
+    /// until the next non-zero resize.
+    pub fn is_minimized(&self) -> bool {
+        self.size.width == 0 || self.size.height == 0
+    }
+
+    /// The sample count of the color and depth targets.
+    pub fn sample_count(&self) -> u32 {
+        self.msaa.sample_count
+    }
+
+    /// The `multisample` state of the pipelines drawing into a frame.
+    pub fn multisample_state(&self) -> wgpu::MultisampleState {
+        self.msaa.multisample_state()
+    }
+
+    /// Color attachment drawing into `frame`, resolved from the multisampled
+    /// texture when MSAA is on.
+    pub fn color_attachment<'a>(
+        &'a self,
+        frame: &'a Frame,
+        load: wgpu::LoadOp<wgpu::Color>,
+    ) -> wgpu::RenderPassColorAttachment<'a> {
+        self.msaa.color_attachment(&frame.view, load)
+    }
+
+    pub fn get_current_frame(&self) -> Result<Frame, wgpu::SurfaceError> {
+        let (view, output) = match (&self.surface, &self.offscreen) {
+            (Some(surface), _) => {
+                let output = surface.get_current_texture()?;
+                let view = output
+                    .texture
+                    .create_view(&wgpu::TextureViewDescriptor::default());
+                (view, Some(output))
+            }
+            (None, Some(texture)) => (
+                texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                None,
+            ),
+            (None, None) => return Err(wgpu::SurfaceError::Lost),
+        };
+        Ok(Frame { view, output })
+    }
+
+    /// Copies the offscreen texture into a mapped buffer and returns it as an image.
+    pub fn read_pixels(&self) -> Result<RgbaImage> {
+        let texture = self
+            .offscreen
+            .as_ref()
+            .ok_or_else(|| anyhow!("read_pixels needs a headless InitWgpu"))?;
+        let (width, height) = (self.config.width, self.config.height);
+
+        // rows of a texture-to-buffer copy must be 256-byte aligned
+        let unpadded_bytes_per_row = 4 * width;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
+
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Readback Buffer"),
+            size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Readback Encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver.recv()??;
+
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+        {
+            let data = slice.get_mapped_range();
+            for row in data.chunks(padded_bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+            }
+        }
+        buffer.unmap();
+
+        // the window is presented opaque, so drop whatever alpha the shaders wrote
+        let is_bgra = matches!(
+            self.config.format,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        );
+        for pixel in pixels.chunks_mut(4) {
+            if is_bgra {
+                pixel.swap(0, 2);
+            }
+            pixel[3] = 255;
+        }
+
+        RgbaImage::from_raw(width, height, pixels)
+            .ok_or_else(|| anyhow!("Readback buffer does not match the image size"))
+    }
+
+    pub fn save_png(&self, path: &str) -> Result<()> {
+        let image = self.read_pixels()?;
+        image.save_with_format(path, image::ImageFormat::Png)?;
+        Ok(())
+    }
+}
+
+fn create_msaa(
+    adapter: &wgpu::Adapter,
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+) -> MsaaTarget {
+    let sample_count = msaa::supported_sample_count(
+        adapter,
+        device,
+        config.format,
+        &[DepthConfig::from_env().format],
+        msaa::sample_count_from_env(),
+    );
+    MsaaTarget::new(
+        device,
+        config.format,
+        config.width,
+        config.height,
+        sample_count,
+    )
+}
+
+fn create_offscreen_texture(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Offscreen Texture"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: config.format,
+        usage: config.usage,
+        view_formats: &[],
+    })
+}
+
+#[rustfmt::skip]
+pub const OPENGL_TO_WGPU_MATRIX: Matrix4<f32> = Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 0.5, 0.0,
+    0.0, 0.0, 0.5, 1.0,
+);
+
+pub fn create_view_projection(
+    camera_position: Point3<f32>,
+    look_direction: Point3<f32>,
+    up_direction: Vector3<f32>,
+    aspect: f32,
+    is_perspective: bool,
+) -> (Matrix4<f32>, Matrix4<f32>, Matrix4<f32>) {
+    let view_mat = Matrix4::look_at_rh(camera_position, look_direction, up_direction);
+    let project_mat = if is_perspective {
+        OPENGL_TO_WGPU_MATRIX * perspective(Rad(2.0 * PI / 5.0), aspect, 0.1, 100.0)
+    } else {
+        OPENGL_TO_WGPU_MATRIX * ortho(-4.0, 4.0, -3.0, 3.0, -1.0, 6.0)
+    };
+    let view_project_mat = project_mat * view_mat;
+
+    (view_mat, project_mat, view_project_mat)
+}
+
+pub fn create_view(
+    camera_position: Point3<f32>,
+    look_direction: Point3<f32>,
+    up_direction: Vector3<f32>,
+) -> Matrix4<f32> {
+    Matrix4::look_at_rh(camera_position, look_direction, up_direction)
+}
+
+pub fn create_projection(aspect: f32, is_perspective: bool) -> Matrix4<f32> {
+    if is_perspective {
+        OPENGL_TO_WGPU_MATRIX * perspective(Rad(2.0 * PI / 5.0), aspect, 0.1, 100.0)
+    } else {
+        OPENGL_TO_WGPU_MATRIX * ortho(-4.0, 4.0, -3.0, 3.0, -1.0, 6.0)
+    }
+}
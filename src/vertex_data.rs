@@ -0,0 +1,314 @@
+use crate::math_func;
+use cgmath::*;
+
+#[rustfmt::skip]
+#[allow(clippy::type_complexity)]
+pub fn cube_data() -> (Vec<[i8; 3]>, Vec<[i8; 3]>, Vec<[i8; 2]>, Vec<[i8; 3]>) {
+    let positions = [
+        // front (0, 0, 1)
+        [-1, -1, 1], [1, -1, 1], [-1, 1, 1], [-1, 1, 1], [ 1, -1, 1], [ 1, 1, 1],
+        // right (1, 0, 0)
+        [ 1, -1, 1], [1, -1, -1], [ 1, 1, 1], [ 1, 1, 1], [ 1, -1, -1], [ 1, 1, -1],
+        // back (0, 0, -1)
+        [ 1, -1, -1], [-1, -1, -1], [1, 1, -1], [ 1, 1, -1], [-1, -1, -1], [-1, 1, -1],
+        // left (-1, 0, 0)
+        [-1, -1, -1], [-1, -1, 1], [-1, 1, -1], [-1, 1, -1], [-1, -1, 1], [-1, 1, 1],
+        // top (0, 1, 0)
+        [-1, 1, 1], [ 1, 1, 1], [-1, 1, -1], [-1, 1, -1], [ 1, 1, 1], [ 1, 1, -1],
+        // bottom (0, -1, 0)
+        [-1, -1, -1], [ 1, -1, -1], [-1, -1, 1], [-1, -1, 1], [ 1, -1, -1], [ 1, -1, 1],
+    ];
+
+    let colors = [
+        // front - blue
+        [0, 0, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1],
+        // right - red
+        [1, 0, 0], [1, 0, 0], [1, 0, 0], [1, 0, 0], [1, 0, 0], [1, 0, 0],
+        // back - yellow
+        [1, 1, 0], [1, 1, 0], [1, 1, 0], [1, 1, 0], [1, 1, 0], [1, 1, 0],
+        // left - aqua
+        [0, 1, 1], [0, 1, 1], [0, 1, 1], [0, 1, 1], [0, 1, 1], [0, 1, 1],
+        // top - green
+        [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0],
+        // bottom - fuchsia
+        [1, 0, 1], [1, 0, 1], [1, 0, 1], [1, 0, 1], [1, 0, 1], [1, 0, 1],
+    ];
+
+    let uvs= [
+        // front
+        [0, 0], [1, 0], [0, 1], [0, 1], [1, 0], [1, 1],
+        // right
+        [0, 0], [1, 0], [0, 1], [0, 1], [1, 0], [1, 1],
+        // back
+        [0, 0], [1, 0], [0, 1], [0, 1], [1, 0], [1, 1],
+        // left
+        [0, 0], [1, 0], [0, 1], [0, 1], [1, 0], [1, 1],
+        // top
+        [0, 0], [1, 0], [0, 1], [0, 1], [1, 0], [1, 1],
+        // bottom
+        [0, 0], [1, 0], [0, 1], [0, 1], [1, 0], [1, 1],
+    ];
+
+    let normals = [
+        // front
+        [0, 0, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1],
+        // right
+        [1, 0, 0], [1, 0, 0], [1, 0, 0], [1, 0, 0], [1, 0, 0], [1, 0, 0],
+        // back
+        [0, 0, -1], [0, 0, -1], [0, 0, -1], [0, 0, -1], [0, 0, -1], [0, 0, -1],
+        // left
+        [-1, 0, 0], [-1, 0, 0], [-1, 0, 0], [-1, 0, 0], [-1, 0, 0], [-1, 0, 0],
+        // top
+        [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0],
+        // bottom
+        [0, -1, 0], [0, -1, 0], [0, -1, 0], [0, -1, 0], [0, -1, 0], [0, -1, 0],
+    ];
+    
+    (positions.to_vec(), colors.to_vec(), uvs.to_vec(), normals.to_vec())
+}
+
+pub fn cube_data_index() -> (Vec<[i8; 3]>, Vec<[i8; 3]>, Vec<u16>) {
+    let positions = [
+        [-1, -1, 1],  // vertex a
+        [1, -1, 1],   // vertex b
+        [1, 1, 1],    // vertex c
+        [-1, 1, 1],   // vertex d
+        [-1, -1, -1], // vertex e
+        [1, -1, -1],  // vertex f
+        [1, 1, -1],   // vertex g
+        [-1, 1, -1],  // vertex h
+    ];
+
+    let colors = [
+        [0, 0, 1], // vertex a
+        [1, 0, 1], // vertex b
+        [1, 1, 1], // vertex c
+        [0, 1, 1], // vertex d
+        [0, 0, 0], // vertex e
+        [1, 0, 0], // vertex f
+        [1, 1, 0], // vertex g
+        [0, 1, 0], // vertex h
+    ];
+
+    let indices = [
+        0, 1, 2, 2, 3, 0, // front
+        1, 5, 6, 6, 2, 1, // right
+        4, 7, 6, 6, 5, 4, // back
+        0, 3, 7, 7, 4, 0, // left
+        3, 2, 6, 6, 7, 3, // top
+        0, 4, 5, 5, 1, 0, // bottom
+    ];
+    (positions.to_vec(), colors.to_vec(), indices.to_vec())
+}
+
+#[derive(Default)]
+struct ShapeData {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+}
+
+impl ShapeData {
+    fn triangle(&mut self, p: [[f32; 3]; 3], n: [[f32; 3]; 3], t: [[f32; 2]; 3]) {
+        self.positions.extend(p);
+        self.normals.extend(n);
+        self.uvs.extend(t);
+    }
+
+    // p0-p1-p2-p3 must run counter-clockwise when seen from the front side
+    fn quad(&mut self, p: [[f32; 3]; 4], n: [[f32; 3]; 4], t: [[f32; 2]; 4]) {
+        self.triangle([p[0], p[1], p[2]], [n[0], n[1], n[2]], [t[0], t[1], t[2]]);
+        self.triangle([p[2], p[3], p[0]], [n[2], n[3], n[0]], [t[2], t[3], t[0]]);
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn into_vecs(self) -> (Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<[f32; 2]>) {
+        (self.positions, self.normals, self.uvs)
+    }
+}
+
+fn scale_point(p: [f32; 3], s: f32) -> [f32; 3] {
+    [p[0] * s, p[1] * s, p[2] * s]
+}
+
+/// Solid sphere as a triangle list of positions, normals and uvs; `u` and `v`
+/// are the numbers of grid lines along theta (pole to pole) and phi.
+#[allow(clippy::type_complexity)]
+pub fn sphere_data(r: f32, u: usize, v: usize) -> (Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<[f32; 2]>) {
+    let mut data = ShapeData::default();
+    for i in 0..u - 1 {
+        for j in 0..v - 1 {
+            let theta = i as f32 * 180.0 / (u as f32 - 1.0);
+            let phi = j as f32 * 360.0 / (v as f32 - 1.0);
+            let theta1 = (i as f32 + 1.0) * 180.0 / (u as f32 - 1.0);
+            let phi1 = (j as f32 + 1.0) * 360.0 / (v as f32 - 1.0);
+
+            let p0 = math_func::sphere_position(r, Deg(theta), Deg(phi));
+            let p1 = math_func::sphere_position(r, Deg(theta1), Deg(phi));
+            let p2 = math_func::sphere_position(r, Deg(theta1), Deg(phi1));
+            let p3 = math_func::sphere_position(r, Deg(theta), Deg(phi1));
+
+            let (u0, u1) = (phi / 360.0, phi1 / 360.0);
+            let (v0, v1) = (theta / 180.0, theta1 / 180.0);
+
+            data.quad(
+                [p0, p1, p2, p3],
+                [p0, p1, p2, p3].map(|p| scale_point(p, 1.0 / r)),
+                [[u0, v0], [u0, v1], [u1, v1], [u1, v0]],
+            );
+        }
+    }
+    data.into_vecs()
+}
+
+/// Solid (hollow if `rin > 0`) cylinder of the given height centered at the
+/// origin, with `n` grid lines around the axis.
+#[allow(clippy::type_complexity)]
+pub fn cylinder_data(
+    rin: f32,
+    rout: f32,
+    height: f32,
+    n: usize,
+) -> (Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<[f32; 2]>) {
+    let h = height / 2.0;
+    let mut data = ShapeData::default();
+    for i in 0..n - 1 {
+        let theta = i as f32 * 360.0 / (n as f32 - 1.0);
+        let theta1 = (i as f32 + 1.0) * 360.0 / (n as f32 - 1.0);
+        let p0 = math_func::cylinder_position(rout, h, Deg(theta));
+        let p1 = math_func::cylinder_position(rout, -h, Deg(theta));
+        let p2 = math_func::cylinder_position(rin, -h, Deg(theta));
+        let p3 = math_func::cylinder_position(rin, h, Deg(theta));
+        let p4 = math_func::cylinder_position(rout, h, Deg(theta1));
+        let p5 = math_func::cylinder_position(rout, -h, Deg(theta1));
+        let p6 = math_func::cylinder_position(rin, -h, Deg(theta1));
+        let p7 = math_func::cylinder_position(rin, h, Deg(theta1));
+
+        let n0 = math_func::cylinder_position(1.0, 0.0, Deg(theta));
+        let n1 = math_func::cylinder_position(1.0, 0.0, Deg(theta1));
+        let (u0, u1) = (theta / 360.0, theta1 / 360.0);
+
+        // outer side
+        data.quad(
+            [p0, p1, p5, p4],
+            [n0, n0, n1, n1],
+            [[u0, 0.0], [u0, 1.0], [u1, 1.0], [u1, 0.0]],
+        );
+        // top and bottom faces
+        data.quad(
+            [p3, p0, p4, p7],
+            [[0.0, 1.0, 0.0]; 4],
+            [[u0, 0.0], [u0, 1.0], [u1, 1.0], [u1, 0.0]],
+        );
+        data.quad(
+            [p2, p6, p5, p1],
+            [[0.0, -1.0, 0.0]; 4],
+            [[u0, 0.0], [u1, 0.0], [u1, 1.0], [u0, 1.0]],
+        );
+        // inner side
+        if rin > 0.0 {
+            data.quad(
+                [p3, p7, p6, p2],
+                [n0, n1, n1, n0].map(|n| scale_point(n, -1.0)),
+                [[u0, 0.0], [u1, 0.0], [u1, 1.0], [u0, 1.0]],
+            );
+        }
+    }
+    data.into_vecs()
+}
+
+/// Solid cone (a frustum if both radii are non-zero) of the given height
+/// centered at the origin, with `n` grid lines around the axis.
+#[allow(clippy::type_complexity)]
+pub fn cone_data(
+    rtop: f32,
+    rbottom: f32,
+    height: f32,
+    n: usize,
+) -> (Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<[f32; 2]>) {
+    let h = height / 2.0;
+    let mut data = ShapeData::default();
+    for i in 0..n - 1 {
+        let theta = i as f32 * 360.0 / (n as f32 - 1.0);
+        let theta1 = (i as f32 + 1.0) * 360.0 / (n as f32 - 1.0);
+        let p0 = math_func::cylinder_position(rtop, h, Deg(theta));
+        let p1 = math_func::cylinder_position(rbottom, -h, Deg(theta));
+        let p2 = math_func::cylinder_position(0.0, -h, Deg(theta));
+        let p3 = math_func::cylinder_position(0.0, h, Deg(theta));
+        let p4 = math_func::cylinder_position(rtop, h, Deg(theta1));
+        let p5 = math_func::cylinder_position(rbottom, -h, Deg(theta1));
+
+        // the side normal leans towards the narrow end
+        let side_normal = |theta: f32| {
+            let n = math_func::cylinder_position(height, rbottom - rtop, Deg(theta));
+            let n = Vector3::from(n).normalize();
+            [n.x, n.y, n.z]
+        };
+        let (n0, n1) = (side_normal(theta), side_normal(theta1));
+        let (u0, u1) = (theta / 360.0, theta1 / 360.0);
+
+        // side
+        data.quad(
+            [p0, p1, p5, p4],
+            [n0, n0, n1, n1],
+            [[u0, 0.0], [u0, 1.0], [u1, 1.0], [u1, 0.0]],
+        );
+        // top and bottom faces
+        if rtop > 0.0 {
+            data.triangle(
+                [p3, p0, p4],
+                [[0.0, 1.0, 0.0]; 3],
+                [[0.5, 0.0], [u0, 1.0], [u1, 1.0]],
+            );
+        }
+        if rbottom > 0.0 {
+            data.triangle(
+                [p2, p5, p1],
+                [[0.0, -1.0, 0.0]; 3],
+                [[0.5, 0.0], [u1, 1.0], [u0, 1.0]],
+            );
+        }
+    }
+    data.into_vecs()
+}
+
+/// Solid torus; `n_torus` and `n_tube` are the numbers of grid lines around
+/// the ring and around the tube.
+#[allow(clippy::type_complexity)]
+pub fn torus_data(
+    r_torus: f32,
+    r_tube: f32,
+    n_torus: usize,
+    n_tube: usize,
+) -> (Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<[f32; 2]>) {
+    let mut data = ShapeData::default();
+    for i in 0..n_torus - 1 {
+        for j in 0..n_tube - 1 {
+            let u = i as f32 * 360.0 / (n_torus as f32 - 1.0);
+            let v = j as f32 * 360.0 / (n_tube as f32 - 1.0);
+            let u1 = (i as f32 + 1.0) * 360.0 / (n_torus as f32 - 1.0);
+            let v1 = (j as f32 + 1.0) * 360.0 / (n_tube as f32 - 1.0);
+
+            let p0 = math_func::torus_position(r_torus, r_tube, Deg(u), Deg(v));
+            let p1 = math_func::torus_position(r_torus, r_tube, Deg(u1), Deg(v));
+            let p2 = math_func::torus_position(r_torus, r_tube, Deg(u1), Deg(v1));
+            let p3 = math_func::torus_position(r_torus, r_tube, Deg(u), Deg(v1));
+
+            // a torus with a zero ring radius is a unit sphere around each tube center
+            let n0 = math_func::torus_position(0.0, 1.0, Deg(u), Deg(v));
+            let n1 = math_func::torus_position(0.0, 1.0, Deg(u1), Deg(v));
+            let n2 = math_func::torus_position(0.0, 1.0, Deg(u1), Deg(v1));
+            let n3 = math_func::torus_position(0.0, 1.0, Deg(u), Deg(v1));
+
+            let (s0, s1) = (u / 360.0, u1 / 360.0);
+            let (t0, t1) = (v / 360.0, v1 / 360.0);
+
+            data.quad(
+                [p0, p1, p2, p3],
+                [n0, n1, n2, n3],
+                [[s0, t0], [s1, t0], [s1, t1], [s0, t1]],
+            );
+        }
+    }
+    data.into_vecs()
+}
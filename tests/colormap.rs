@@ -0,0 +1,181 @@
+//! Built-in colormaps, colormap construction and colormap files.
+
+use std::path::Path;
+use wgpu_book::colormap::{self, Colormap, COLORMAP_NAMES};
+
+fn assert_color_close(a: [f32; 3], b: [f32; 3]) {
+    assert!(
+        a.iter().zip(b).all(|(x, y)| (x - y).abs() < 1e-5),
+        "{:?} != {:?}",
+        a,
+        b
+    );
+}
+
+#[test]
+fn unknown_name_lists_the_built_in_colormaps() {
+    let error = Colormap::from_name("rainbow").unwrap_err().to_string();
+    assert!(error.contains("unknown colormap 'rainbow'"), "{}", error);
+    assert!(error.contains("viridis"), "{}", error);
+    assert!(Colormap::from_name("_r").is_err());
+    assert!(Colormap::from_name("jet_r_r").is_err());
+}
+
+#[test]
+fn reversed_variants_run_backwards() {
+    for name in COLORMAP_NAMES {
+        let colormap = Colormap::from_name(name).unwrap();
+        let reversed = Colormap::from_name(&format!("{}_r", name)).unwrap();
+        assert_eq!(reversed, colormap.reversed());
+        for t in [0.0, 0.1, 0.35, 0.5, 0.9, 1.0] {
+            assert_color_close(reversed.color(t), colormap.color(1.0 - t));
+        }
+    }
+    // names are case-insensitive, the suffix included
+    assert_eq!(
+        Colormap::from_name("Viridis_R").unwrap(),
+        Colormap::from_name("viridis_r").unwrap()
+    );
+}
+
+#[test]
+fn new_validates_stops() {
+    let red = [1.0, 0.0, 0.0];
+    let blue = [0.0, 0.0, 1.0];
+    assert!(Colormap::new(vec![]).is_err());
+    assert!(Colormap::new(vec![(0.5, red), (0.2, blue)]).is_err());
+    assert!(Colormap::new(vec![(0.0, red), (f32::NAN, blue)]).is_err());
+    assert!(Colormap::new(vec![(0.0, red), (f32::INFINITY, blue)]).is_err());
+    assert!(Colormap::new(vec![(0.0, red), (1.0, [0.0, 0.0, 1.5])]).is_err());
+    assert!(Colormap::new(vec![(0.0, [-0.1, 0.0, 0.0]), (1.0, blue)]).is_err());
+
+    // any increasing positions are rescaled to [0, 1]
+    let colormap = Colormap::new(vec![(-2.0, red), (0.0, [1.0; 3]), (6.0, blue)]).unwrap();
+    let positions: Vec<f32> = colormap.stops().iter().map(|s| s.0).collect();
+    assert_eq!(positions, [0.0, 0.25, 1.0]);
+    assert_color_close(colormap.color(0.125), [1.0, 0.5, 0.5]);
+    assert_eq!(colormap.color(-1.0), red);
+    assert_eq!(colormap.color(2.0), blue);
+}
+
+#[test]
+#[allow(deprecated)]
+fn name_based_functions_still_work() {
+    let jet = Colormap::from_name("jet").unwrap();
+    let hot = Colormap::from_name("hot").unwrap();
+    assert_eq!(colormap::color_lerp("hot", 2.0, 4.0, 3.0), hot.color(0.5));
+    // unknown names fall back to jet, as they always did
+    assert_eq!(
+        colormap::color_lerp("unknown", 0.0, 1.0, 0.3),
+        jet.color(0.3)
+    );
+    assert_eq!(colormap::colormap_data("jet")[10], jet.color(1.0));
+    assert_eq!(colormap::colormap_texels("jet", 16), jet.texels(16));
+}
+
+fn positions(colormap: &Colormap) -> Vec<f32> {
+    colormap.stops().iter().map(|s| s.0).collect()
+}
+
+#[test]
+fn csv_with_header_row() {
+    let text = "position,red,green,blue\n0.0,0,0,0\n0.25,1,0,0 # red\n\n1.0,1,1,1\n";
+    let colormap = Colormap::from_text(text).unwrap();
+    assert_eq!(positions(&colormap), [0.0, 0.25, 1.0]);
+    assert_eq!(colormap.stops()[1].1, [1.0, 0.0, 0.0]);
+    assert_color_close(colormap.color(0.125), [0.5, 0.0, 0.0]);
+}
+
+#[test]
+fn text_detects_8_bit_colors() {
+    // without positions the colors are evenly spaced
+    let unit = Colormap::from_text("0 0 0\n1 0.5 0.2\n").unwrap();
+    assert_eq!(positions(&unit), [0.0, 1.0]);
+    assert_eq!(unit.stops()[1].1, [1.0, 0.5, 0.2]);
+
+    // a single component above 1 switches the whole file to [0, 255]
+    let bytes = Colormap::from_text("0; 0; 0\n255; 51; 1\n").unwrap();
+    assert_color_close(bytes.stops()[1].1, [1.0, 0.2, 1.0 / 255.0]);
+
+    // positions are not colors and do not trigger the detection
+    let positioned = Colormap::from_text("0 0 0 0\n10 1 0.5 0.2\n").unwrap();
+    assert_eq!(positioned, unit);
+
+    assert!(Colormap::from_text("0 0 0\n300 0 0\n").is_err());
+}
+
+#[test]
+fn paraview_rgb_points() {
+    let json = r#"[{
+        "Name": "Cool to Warm",
+        "ColorSpace": "Diverging",
+        "RGBPoints": [-1, 0.23, 0.3, 0.75, 0, 0.87, 0.87, 0.87, 1, 0.71, 0.02, 0.15]
+    }]"#;
+    let colormap = Colormap::from_json(json).unwrap();
+    assert_eq!(positions(&colormap), [0.0, 0.5, 1.0]);
+    assert_eq!(colormap.stops()[0].1, [0.23, 0.3, 0.75]);
+    assert_eq!(colormap.stops()[2].1, [0.71, 0.02, 0.15]);
+
+    let ragged = r#"{"RGBPoints": [0, 0, 0, 0, 1, 1, 1]}"#;
+    assert!(Colormap::from_json(ragged).is_err());
+}
+
+#[test]
+fn matplotlib_segment_data_with_a_jump() {
+    // red rises to 1 at 0.5, drops to 0 and rises again
+    let json = r#"{
+        "red": [[0, 0, 0], [0.5, 1, 0], [1, 1, 1]],
+        "green": [[0, 0, 0], [1, 1, 1]],
+        "blue": [[0, 0.2, 0.2], [1, 0.2, 0.2]]
+    }"#;
+    let colormap = Colormap::from_json(json).unwrap();
+    assert_eq!(positions(&colormap), [0.0, 0.5, 0.5, 1.0]);
+    assert_color_close(colormap.stops()[1].1, [1.0, 0.5, 0.2]);
+    assert_color_close(colormap.stops()[2].1, [0.0, 0.5, 0.2]);
+    assert_color_close(colormap.color(0.25), [0.5, 0.25, 0.2]);
+    assert_color_close(colormap.color(0.75), [0.5, 0.75, 0.2]);
+
+    // listed colors, as a bare array or under `colors`
+    let listed = Colormap::from_json("[[0, 0, 0], [1, 1, 1, 1]]").unwrap();
+    assert_eq!(
+        listed,
+        Colormap::from_json(r#"{"colors": [[0, 0, 0], [1, 1, 1]]}"#).unwrap()
+    );
+}
+
+#[test]
+fn png_gradient_file() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("colormap");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("gradient.png");
+    let image = image::RgbImage::from_fn(5, 2, |x, _| image::Rgb([(x * 60) as u8, 0, 255]));
+    image.save(&path).unwrap();
+
+    let colormap = Colormap::from_file(&path).unwrap();
+    assert_eq!(positions(&colormap), [0.0, 0.25, 0.5, 0.75, 1.0]);
+    assert_color_close(colormap.color(0.0), [0.0, 0.0, 1.0]);
+    assert_color_close(colormap.color(1.0), [240.0 / 255.0, 0.0, 1.0]);
+    assert_eq!(
+        Colormap::from_name_or_file(path.to_str().unwrap()).unwrap(),
+        colormap
+    );
+}
+
+#[test]
+fn malformed_colormaps_are_rejected() {
+    assert!(Colormap::from_text("").is_err());
+    assert!(Colormap::from_text("# nothing but a comment\nr g b\n").is_err());
+    assert!(Colormap::from_json("").is_err());
+    assert!(Colormap::from_json("[]").is_err());
+    assert!(Colormap::from_json(r#"{"name": "empty"}"#).is_err());
+
+    let error = Colormap::from_text("0 0 0\n1 1\n").unwrap_err().to_string();
+    assert!(error.contains("expected 3 values per line"), "{}", error);
+    assert!(Colormap::from_text("0 0 0\n1 1 1 1 1\n").is_err());
+    assert!(Colormap::from_text("0 0 0\n0.5 x 0\n").is_err());
+    assert!(Colormap::from_json("[[0, 0, 0], [1, 1]]").is_err());
+
+    let missing = Path::new(env!("CARGO_TARGET_TMPDIR")).join("no-such-colormap.csv");
+    let error = format!("{:#}", Colormap::from_file(&missing).unwrap_err());
+    assert!(error.contains("no-such-colormap.csv"), "{}", error);
+}
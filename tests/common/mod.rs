@@ -0,0 +1,192 @@
+//! Golden-image harness shared by the integration tests.
+//!
+//! Each chapter example is rendered headless (see `wgpu_book::headless`) at a
+//! fixed size and animation time and compared against the reference PNG in
+//! `tests/golden/`. When no adapter is available the test renders the scene
+//! with the CPU rasterizer in `raster` instead, with a looser tolerance.
+//!
+//! Environment variables:
+//!
+//! - `WGPU_BOOK_BLESS=1`: overwrite the references with the GPU renderings
+//! - `WGPU_BOOK_GOLDEN_CPU=1`: use the CPU rasterizer even if an adapter exists
+//! - `WGPU_BOOK_FALLBACK=1`: passed on to the examples to force the fallback adapter
+
+#![allow(dead_code)]
+
+pub mod raster;
+
+use image::{Rgba, RgbaImage};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+use wgpu_book::headless::env_flag;
+use wgpu_book::transforms::InitWgpu;
+
+pub const WIDTH: u32 = 200;
+pub const HEIGHT: u32 = 150;
+pub const TIME: f32 = 1.0;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Tolerance {
+    /// Largest per-channel difference for a pixel to still count as equal.
+    pub channel: u8,
+    /// Fraction of pixels allowed to differ by more than `channel`.
+    pub max_mismatch: f32,
+}
+
+/// Different drivers rasterize edges slightly differently.
+pub const GPU_TOLERANCE: Tolerance = Tolerance {
+    channel: 16,
+    max_mismatch: 0.01,
+};
+
+/// The CPU rasterizer approximates the pipelines; edges and texel choices differ.
+pub const CPU_TOLERANCE: Tolerance = Tolerance {
+    channel: 32,
+    max_mismatch: 0.02,
+};
+
+pub fn adapter_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        if env_flag("WGPU_BOOK_GOLDEN_CPU") {
+            return false;
+        }
+        let fallback = env_flag("WGPU_BOOK_FALLBACK");
+        pollster::block_on(InitWgpu::init_headless(1, 1, fallback)).is_ok()
+    })
+}
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+}
+
+fn output_dir() -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// `cargo test` builds the examples next to the test binaries' `deps` directory.
+fn example_path(example: &str) -> PathBuf {
+    let exe = std::env::current_exe().unwrap();
+    let dir = exe.parent().and_then(Path::parent).unwrap();
+    dir.join("examples")
+        .join(format!("{}{}", example, std::env::consts::EXE_SUFFIX))
+}
+
+/// Runs a chapter example headless and loads the frame it wrote.
+pub fn render_example(example: &str, args: &[&str]) -> RgbaImage {
+    let path = example_path(example);
+    assert!(
+        path.exists(),
+        "{} not found; run `cargo test` or `cargo build --examples` first",
+        path.display()
+    );
+
+    let png = output_dir().join(format!("{}-frame.png", example));
+    let status = Command::new(&path)
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env("WGPU_BOOK_PNG", &png)
+        .env("WGPU_BOOK_SIZE", format!("{}x{}", WIDTH, HEIGHT))
+        .env("WGPU_BOOK_TIME", TIME.to_string())
+        .status()
+        .unwrap();
+    assert!(status.success(), "{} exited with {}", example, status);
+
+    image::open(&png).unwrap().to_rgba8()
+}
+
+pub struct Comparison {
+    pub mismatched: usize,
+    pub total: usize,
+    pub diff: RgbaImage,
+}
+
+impl Comparison {
+    pub fn fraction(&self) -> f32 {
+        self.mismatched as f32 / self.total as f32
+    }
+}
+
+/// Compares two images; the diff image shows mismatched pixels in red over a
+/// dimmed copy of the reference.
+pub fn compare(actual: &RgbaImage, reference: &RgbaImage, channel: u8) -> Comparison {
+    assert_eq!(actual.dimensions(), reference.dimensions());
+    let mut diff = RgbaImage::new(actual.width(), actual.height());
+    let mut mismatched = 0;
+    for ((a, r), d) in actual
+        .pixels()
+        .zip(reference.pixels())
+        .zip(diff.pixels_mut())
+    {
+        let max = (0..3).map(|i| a[i].abs_diff(r[i])).max().unwrap();
+        if max > channel {
+            mismatched += 1;
+            *d = Rgba([255, 0, 0, 255]);
+        } else {
+            let gray = ((r[0] as u32 + r[1] as u32 + r[2] as u32) / 9) as u8;
+            *d = Rgba([gray, gray, gray, 255]);
+        }
+    }
+    Comparison {
+        mismatched,
+        total: (actual.width() * actual.height()) as usize,
+        diff,
+    }
+}
+
+/// Renders `name` on the GPU (or with `cpu_reference` when there is no adapter)
+/// and checks it against `tests/golden/<name>.png`.
+pub fn check(name: &str, args: &[&str], cpu_reference: fn() -> RgbaImage) {
+    let reference_path = golden_dir().join(format!("{}.png", name));
+
+    let (actual, tolerance) = if adapter_available() {
+        (render_example(name, args), GPU_TOLERANCE)
+    } else {
+        (cpu_reference(), CPU_TOLERANCE)
+    };
+
+    if env_flag("WGPU_BOOK_BLESS") {
+        assert!(
+            adapter_available(),
+            "references must be blessed from a GPU rendering"
+        );
+        std::fs::create_dir_all(golden_dir()).unwrap();
+        actual.save(&reference_path).unwrap();
+        return;
+    }
+
+    let reference = image::open(&reference_path)
+        .unwrap_or_else(|e| {
+            panic!(
+                "cannot open {} ({}); run with WGPU_BOOK_BLESS=1 to create it",
+                reference_path.display(),
+                e
+            )
+        })
+        .to_rgba8();
+
+    let comparison = compare(&actual, &reference, tolerance.channel);
+    if comparison.fraction() > tolerance.max_mismatch {
+        let out = output_dir();
+        let actual_path = out.join(format!("{}-actual.png", name));
+        let diff_path = out.join(format!("{}-diff.png", name));
+        actual.save(&actual_path).unwrap();
+        comparison.diff.save(&diff_path).unwrap();
+        panic!(
+            "{}: {} of {} pixels ({:.2}%) differ by more than {} (allowed {:.2}%)\n  actual: {}\n  diff:   {}",
+            name,
+            comparison.mismatched,
+            comparison.total,
+            100.0 * comparison.fraction(),
+            tolerance.channel,
+            100.0 * tolerance.max_mismatch,
+            actual_path.display(),
+            diff_path.display(),
+        );
+    }
+}
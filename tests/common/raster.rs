@@ -0,0 +1,263 @@
+//! A small CPU rasterizer used as the golden-image reference when no wgpu
+//! adapter is available.
+//!
+//! It follows the wgpu conventions the chapter pipelines rely on: clip space
+//! with z in `[0, 1]`, counter-clockwise front faces, `LessEqual` depth test,
+//! pixel centers at `+0.5` and an `Rgba8UnormSrgb` color target. Triangles are
+//! clipped against the near plane only; the depth test discards the rest.
+
+use cgmath::Vector4;
+use image::RgbaImage;
+
+#[derive(Clone)]
+pub struct ClipVertex {
+    pub position: Vector4<f32>,
+    pub varyings: Vec<f32>,
+}
+
+pub fn clip_vertex(position: Vector4<f32>, varyings: &[f32]) -> ClipVertex {
+    ClipVertex {
+        position,
+        varyings: varyings.to_vec(),
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct PipelineState {
+    pub cull_back: bool,
+    pub depth_test: bool,
+}
+
+struct ScreenVertex<'a> {
+    x: f32,
+    y: f32,
+    z: f32,
+    inv_w: f32,
+    varyings: &'a [f32],
+}
+
+pub struct Rasterizer {
+    width: u32,
+    height: u32,
+    color: Vec<[f32; 3]>,
+    depth: Vec<f32>,
+}
+
+impl Rasterizer {
+    pub fn new(width: u32, height: u32, clear_color: [f32; 3]) -> Self {
+        let len = (width * height) as usize;
+        Self {
+            width,
+            height,
+            color: vec![clear_color; len],
+            depth: vec![1.0; len],
+        }
+    }
+
+    fn to_screen<'a>(&self, v: &'a ClipVertex) -> Option<ScreenVertex<'a>> {
+        let w = v.position.w;
+        if w <= 0.0 {
+            return None;
+        }
+        let inv_w = 1.0 / w;
+        Some(ScreenVertex {
+            x: (v.position.x * inv_w + 1.0) * 0.5 * self.width as f32,
+            y: (1.0 - v.position.y * inv_w) * 0.5 * self.height as f32,
+            z: v.position.z * inv_w,
+            inv_w,
+            varyings: &v.varyings,
+        })
+    }
+
+    fn write(&mut self, x: i64, y: i64, z: f32, state: PipelineState, color: [f32; 3]) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return;
+        }
+        if !(0.0..=1.0).contains(&z) {
+            return;
+        }
+        let index = (y as u32 * self.width + x as u32) as usize;
+        if state.depth_test {
+            if z > self.depth[index] {
+                return;
+            }
+            self.depth[index] = z;
+        }
+        self.color[index] = color;
+    }
+
+    /// Draws a `TriangleList`; `shade` is the fragment shader and receives the
+    /// perspective-correct interpolated varyings.
+    pub fn triangles(
+        &mut self,
+        vertices: &[ClipVertex],
+        state: PipelineState,
+        shade: &dyn Fn(&[f32]) -> [f32; 3],
+    ) {
+        for tri in vertices.chunks_exact(3) {
+            let polygon = clip_near(tri);
+            for i in 2..polygon.len() {
+                self.triangle([&polygon[0], &polygon[i - 1], &polygon[i]], state, shade);
+            }
+        }
+    }
+
+    fn triangle(
+        &mut self,
+        tri: [&ClipVertex; 3],
+        state: PipelineState,
+        shade: &dyn Fn(&[f32]) -> [f32; 3],
+    ) {
+        let (Some(a), Some(b), Some(c)) = (
+            self.to_screen(tri[0]),
+            self.to_screen(tri[1]),
+            self.to_screen(tri[2]),
+        ) else {
+            return;
+        };
+
+        // screen space has y pointing down, so counter-clockwise faces have a negative area
+        let area = edge(&a, &b, c.x, c.y);
+        if area == 0.0 || (state.cull_back && area > 0.0) {
+            return;
+        }
+        let (b, c, area) = if area < 0.0 {
+            (c, b, -area)
+        } else {
+            (b, c, area)
+        };
+
+        let min_x = a.x.min(b.x).min(c.x).floor().max(0.0) as i64;
+        let max_x = a.x.max(b.x).max(c.x).ceil().min(self.width as f32) as i64;
+        let min_y = a.y.min(b.y).min(c.y).floor().max(0.0) as i64;
+        let max_y = a.y.max(b.y).max(c.y).ceil().min(self.height as f32) as i64;
+
+        let mut varyings = vec![0.0; a.varyings.len()];
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let w0 = edge(&b, &c, px, py);
+                let w1 = edge(&c, &a, px, py);
+                let w2 = edge(&a, &b, px, py);
+                if !covers(w0, &b, &c) || !covers(w1, &c, &a) || !covers(w2, &a, &b) {
+                    continue;
+                }
+                let (w0, w1, w2) = (w0 / area, w1 / area, w2 / area);
+
+                let z = w0 * a.z + w1 * b.z + w2 * c.z;
+                let (p0, p1, p2) = (w0 * a.inv_w, w1 * b.inv_w, w2 * c.inv_w);
+                let sum = p0 + p1 + p2;
+                for (i, v) in varyings.iter_mut().enumerate() {
+                    *v = (p0 * a.varyings[i] + p1 * b.varyings[i] + p2 * c.varyings[i]) / sum;
+                }
+                let color = shade(&varyings);
+                self.write(x, y, z, state, color);
+            }
+        }
+    }
+
+    /// Draws a `LineList` one pixel wide.
+    pub fn lines(
+        &mut self,
+        vertices: &[ClipVertex],
+        state: PipelineState,
+        shade: &dyn Fn(&[f32]) -> [f32; 3],
+    ) {
+        for line in vertices.chunks_exact(2) {
+            let (Some(a), Some(b)) = (self.to_screen(&line[0]), self.to_screen(&line[1])) else {
+                continue;
+            };
+            // step along the major axis through every pixel center the segment crosses,
+            // which is close to the diamond-exit rule GPUs use
+            let x_major = (b.x - a.x).abs() >= (b.y - a.y).abs();
+            let (start, end) = if x_major { (a.x, b.x) } else { (a.y, b.y) };
+            let (lo, hi) = (start.min(end), start.max(end));
+            let mut varyings = vec![0.0; a.varyings.len()];
+            let mut center = (lo - 0.5).ceil() + 0.5;
+            while center < hi {
+                let t = (center - start) / (end - start);
+                let x = a.x + (b.x - a.x) * t;
+                let y = a.y + (b.y - a.y) * t;
+                let z = a.z + (b.z - a.z) * t;
+                for (i, v) in varyings.iter_mut().enumerate() {
+                    *v = a.varyings[i] + (b.varyings[i] - a.varyings[i]) * t;
+                }
+                let color = shade(&varyings);
+                self.write(x.floor() as i64, y.floor() as i64, z, state, color);
+                center += 1.0;
+            }
+        }
+    }
+
+    /// Depth buffer value at pixel `(x, y)`, 1 where nothing was drawn.
+    pub fn depth(&self, x: u32, y: u32) -> f32 {
+        self.depth[(y * self.width + x) as usize]
+    }
+
+    /// Encodes the linear colors the way an `Rgba8UnormSrgb` target stores them.
+    pub fn into_image(self) -> RgbaImage {
+        let mut image = RgbaImage::new(self.width, self.height);
+        for (pixel, color) in image.pixels_mut().zip(self.color) {
+            let [r, g, b] = color.map(linear_to_srgb);
+            *pixel = image::Rgba([r, g, b, 255]);
+        }
+        image
+    }
+}
+
+/// Clips a triangle against the near plane `z = 0` of clip space and returns
+/// the remaining convex polygon; the varyings are interpolated linearly.
+fn clip_near(tri: &[ClipVertex]) -> Vec<ClipVertex> {
+    let mut polygon = Vec::with_capacity(4);
+    for (i, a) in tri.iter().enumerate() {
+        let b = &tri[(i + 1) % tri.len()];
+        let (da, db) = (a.position.z, b.position.z);
+        if da >= 0.0 {
+            polygon.push(a.clone());
+        }
+        if (da >= 0.0) != (db >= 0.0) {
+            let t = da / (da - db);
+            let varyings: Vec<f32> = a
+                .varyings
+                .iter()
+                .zip(&b.varyings)
+                .map(|(va, vb)| va + (vb - va) * t)
+                .collect();
+            polygon.push(clip_vertex(
+                a.position + (b.position - a.position) * t,
+                &varyings,
+            ));
+        }
+    }
+    polygon
+}
+
+fn edge(a: &ScreenVertex, b: &ScreenVertex, px: f32, py: f32) -> f32 {
+    (b.x - a.x) * (py - a.y) - (b.y - a.y) * (px - a.x)
+}
+
+/// Top-left fill rule: a pixel center exactly on an edge belongs to the
+/// triangle only if the edge is a top or a left edge.
+fn covers(w: f32, a: &ScreenVertex, b: &ScreenVertex) -> bool {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    w > 0.0 || (w == 0.0 && (dy < 0.0 || (dy == 0.0 && dx > 0.0)))
+}
+
+pub fn linear_to_srgb(c: f32) -> u8 {
+    let c = c.clamp(0.0, 1.0);
+    let s = if c <= 0.0031308 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (s * 255.0).round() as u8
+}
+
+pub fn srgb_to_linear(c: u8) -> f32 {
+    let c = c as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
@@ -0,0 +1,284 @@
+//! Round trips of generated meshes through the OBJ, STL and PLY exporters
+//! and loaders.
+
+use cgmath::{InnerSpace, Vector3};
+use std::path::Path;
+use wgpu_book::colormap::Colormap;
+use wgpu_book::export::{self, Encoding};
+use wgpu_book::mesh::{Mesh, MeshVertex, Shading};
+use wgpu_book::obj::{self, ObjModel};
+use wgpu_book::{math_func, ply, stl, surface_data, vertex_data};
+
+/// The sinc surface of ch09, with colormap colors and shared vertices.
+fn sinc_mesh(shading: Shading) -> Mesh {
+    let colormap = Colormap::from_name("jet").unwrap();
+    surface_data::surface_mesh(
+        &math_func::sinc,
+        &colormap,
+        -8.0,
+        8.0,
+        -8.0,
+        8.0,
+        12,
+        10,
+        2.0,
+        0.3,
+        shading,
+    )
+}
+
+/// The sinc surface of ch10 as a triangle list with colormap colors.
+fn sinc_triangles() -> Mesh {
+    let colormap = Colormap::from_name("jet").unwrap();
+    let (positions, normals, colors, uvs, _) = surface_data::simple_surface_data(
+        &math_func::sinc,
+        &colormap,
+        -8.0,
+        8.0,
+        -8.0,
+        8.0,
+        12,
+        10,
+        2.0,
+        0.3,
+    );
+    Mesh::from_triangles(&positions, &normals, &uvs).with_colors(&colors)
+}
+
+fn sphere() -> Mesh {
+    let (positions, normals, uvs) = vertex_data::sphere_data(1.5, 8, 10);
+    Mesh::from_triangles(&positions, &normals, &uvs)
+}
+
+/// The corners of every triangle, which loaders may renumber.
+fn corners(mesh: &Mesh) -> Vec<MeshVertex> {
+    mesh.indices
+        .iter()
+        .map(|&i| mesh.vertices[i as usize])
+        .collect()
+}
+
+fn assert_close<const N: usize>(a: [f32; N], b: [f32; N], tolerance: f32) {
+    for i in 0..N {
+        assert!((a[i] - b[i]).abs() <= tolerance, "{:?} != {:?}", a, b);
+    }
+}
+
+#[test]
+fn obj_round_trip() {
+    for mesh in [
+        sinc_mesh(Shading::Smooth),
+        sinc_mesh(Shading::Flat),
+        sphere(),
+    ] {
+        let mut data = Vec::new();
+        obj::write(&mesh, &mut data).unwrap();
+        let model = ObjModel::parse(std::str::from_utf8(&data).unwrap(), Path::new("")).unwrap();
+
+        let (expected, actual) = (corners(&mesh), corners(&model.mesh));
+        assert_eq!(expected.len(), actual.len());
+        for (e, a) in expected.iter().zip(&actual) {
+            assert_eq!(e.position, a.position);
+            assert_eq!(e.normal, a.normal);
+            assert_eq!(e.color, a.color);
+            // the v coordinate is flipped twice
+            assert_close(e.uv, a.uv, 1e-6);
+        }
+        assert!(model.triangle_materials.iter().all(Option::is_none));
+    }
+}
+
+#[test]
+fn stl_round_trip() {
+    let mesh = sinc_triangles();
+    for encoding in [Encoding::Binary, Encoding::Ascii] {
+        let mut data = Vec::new();
+        stl::write(&mesh, &mut data, encoding).unwrap();
+        if encoding == Encoding::Binary {
+            assert_eq!(data.len(), 84 + 50 * mesh.indices.len() / 3);
+        } else {
+            assert!(data.starts_with(b"solid"));
+        }
+        let read = stl::read(&data).unwrap();
+
+        let (expected, actual) = (corners(&mesh), corners(&read));
+        assert_eq!(expected.len(), actual.len());
+        for (tri_e, tri_a) in expected.chunks(3).zip(actual.chunks(3)) {
+            let [p0, p1, p2] = [0, 1, 2].map(|k| Vector3::from(tri_e[k].position));
+            let facet_normal = (p1 - p0).cross(p2 - p0).normalize();
+            for (e, a) in tri_e.iter().zip(tri_a) {
+                assert_eq!(e.position, a.position);
+                assert_close(facet_normal.into(), a.normal, 1e-5);
+                assert_eq!(a.color, [1.0; 3]);
+            }
+        }
+    }
+}
+
+#[test]
+fn stl_binary_header_starting_with_solid() {
+    let mut data = Vec::new();
+    stl::write(&sphere(), &mut data, Encoding::Binary).unwrap();
+    data[..6].copy_from_slice(b"solid ");
+    let read = stl::read(&data).unwrap();
+    assert_eq!(read.indices.len(), sphere().indices.len());
+
+    assert!(stl::read(&data[..100]).is_err());
+    let error =
+        stl::read(b"solid x\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\nendloop\nendfacet\n")
+            .err()
+            .unwrap();
+    assert!(format!("{:#}", error).contains("line 6"), "{:#}", error);
+}
+
+#[test]
+fn ply_round_trip() {
+    for mesh in [sinc_mesh(Shading::Smooth), sphere()] {
+        for encoding in [Encoding::Binary, Encoding::Ascii] {
+            let mut data = Vec::new();
+            ply::write(&mesh, &mut data, encoding).unwrap();
+            let read = ply::read(&data).unwrap();
+
+            // the vertices stay shared
+            assert_eq!(read.indices, mesh.indices);
+            assert_eq!(read.vertices.len(), mesh.vertices.len());
+            for (e, a) in mesh.vertices.iter().zip(&read.vertices) {
+                assert_eq!(e.position, a.position);
+                assert_eq!(e.normal, a.normal);
+                // the t coordinate is flipped twice
+                assert_close(e.uv, a.uv, 1e-6);
+                // colors are stored as bytes
+                assert_close(e.color, a.color, 0.5 / 255.0 + 1e-6);
+            }
+        }
+
+        // the files hold the same texture coordinates as an OBJ export
+        let mut ply_data = Vec::new();
+        ply::write(&mesh, &mut ply_data, Encoding::Ascii).unwrap();
+        let ply_text = String::from_utf8(ply_data).unwrap();
+        let ply_uvs: Vec<[f32; 2]> = ply_text
+            .split("end_header\n")
+            .nth(1)
+            .unwrap()
+            .lines()
+            .take(mesh.vertices.len())
+            .map(|line| {
+                let fields: Vec<f32> = line.split(' ').map(|f| f.parse().unwrap()).collect();
+                [fields[9], fields[10]]
+            })
+            .collect();
+        let mut obj_data = Vec::new();
+        obj::write(&mesh, &mut obj_data).unwrap();
+        let obj_uvs: Vec<[f32; 2]> = String::from_utf8(obj_data)
+            .unwrap()
+            .lines()
+            .filter_map(|line| line.strip_prefix("vt "))
+            .map(|vt| {
+                let fields: Vec<f32> = vt.split(' ').map(|f| f.parse().unwrap()).collect();
+                [fields[0], fields[1]]
+            })
+            .collect();
+        assert_eq!(ply_uvs, obj_uvs);
+        assert!(mesh
+            .vertices
+            .iter()
+            .zip(&ply_uvs)
+            .any(|(v, uv)| v.uv != *uv));
+    }
+}
+
+#[test]
+fn ply_variants() {
+    // a big-endian quad with double positions, no normals, an extra
+    // property and an extra element
+    let mut data = b"ply\r\nformat binary_big_endian 1.0\r\nelement vertex 4\r\n\
+        property double x\r\nproperty double y\r\nproperty double z\r\nproperty uchar alpha\r\n\
+        element face 1\r\nproperty list uchar int vertex_index\r\n\
+        element edge 1\r\nproperty int vertex1\r\nproperty int vertex2\r\nend_header\r\n"
+        .to_vec();
+    for p in [
+        [0.0f64, 0.0, 0.0],
+        [1.0, 0.0, 0.0],
+        [1.0, 1.0, 0.0],
+        [0.0, 1.0, 0.0],
+    ] {
+        for x in p {
+            data.extend(x.to_be_bytes());
+        }
+        data.push(255);
+    }
+    data.push(4);
+    for i in [0i32, 1, 2, 3] {
+        data.extend(i.to_be_bytes());
+    }
+    data.extend([0, 0, 0, 0, 0, 0, 0, 1]);
+
+    let mesh = ply::read(&data).unwrap();
+    assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+    assert_eq!(mesh.vertices[2].position, [1.0, 1.0, 0.0]);
+    assert_eq!(mesh.vertices[3].color, [1.0; 3]);
+    for v in &mesh.vertices {
+        assert_close(v.normal, [0.0, 0.0, 1.0], 1e-6);
+    }
+
+    let ascii = "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\n\
+        property float z\nproperty float texture_u\nproperty float texture_v\n\
+        property ushort red\nproperty ushort green\nproperty ushort blue\n\
+        element face 1\nproperty list uchar uint vertex_indices\nend_header\n\
+        0 0 0 0 0 65535 0 0\n1 0 0 1 0 0 65535 0\n0 1 0 0 1 0 0 65535\n3 0 1 2\n";
+    let mesh = ply::read(ascii.as_bytes()).unwrap();
+    // flipped to the top-left origin
+    assert_eq!(mesh.vertices[1].uv, [1.0, 1.0]);
+    assert_eq!(mesh.vertices[2].uv, [0.0, 0.0]);
+    assert_eq!(mesh.vertices[2].color, [0.0, 0.0, 1.0]);
+
+    let error = ply::read(ascii.replace("3 0 1 2", "3 0 1 7").as_bytes())
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("vertex 7"), "{}", error);
+    assert!(ply::read(ascii.replace("3 0 1 2\n", "3 0").as_bytes()).is_err());
+    assert!(
+        ply::read(b"ply\nformat ascii 1.0\nelement vertex 1\nproperty half x\nend_header\n")
+            .is_err()
+    );
+}
+
+#[test]
+fn save_by_extension() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("export");
+    std::fs::create_dir_all(&dir).unwrap();
+    let mesh = sinc_mesh(Shading::Smooth);
+    let triangles = mesh.indices.len();
+
+    export::save(&mesh, dir.join("sinc.obj"), Encoding::Binary).unwrap();
+    assert_eq!(
+        ObjModel::load(dir.join("sinc.obj"))
+            .unwrap()
+            .mesh
+            .indices
+            .len(),
+        triangles
+    );
+    for encoding in [Encoding::Binary, Encoding::Ascii] {
+        export::save(&mesh, dir.join("sinc.STL"), encoding).unwrap();
+        assert_eq!(
+            stl::load(dir.join("sinc.STL")).unwrap().indices.len(),
+            triangles
+        );
+        export::save(&mesh, dir.join("sinc.ply"), encoding).unwrap();
+        assert_eq!(
+            ply::load(dir.join("sinc.ply")).unwrap().indices.len(),
+            triangles
+        );
+    }
+
+    let error = export::save(&mesh, dir.join("sinc.fbx"), Encoding::Binary)
+        .err()
+        .unwrap();
+    assert!(
+        error.to_string().contains(".obj, .stl or .ply"),
+        "{}",
+        error
+    );
+    assert!(!dir.join("sinc.fbx").exists());
+}
@@ -0,0 +1,445 @@
+//! Tests of the glTF importer against small files written by the tests
+//! themselves.
+
+use cgmath::{Matrix4, Vector3};
+use image::{Rgba, RgbaImage};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use wgpu_book::gltf::GltfScene;
+
+/// Binary buffer contents with the buffer views and accessors into it.
+#[derive(Default)]
+struct Buffer {
+    data: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl Buffer {
+    /// Appends `bytes` as a new buffer view and returns its index.
+    fn view(&mut self, bytes: &[u8]) -> usize {
+        while !self.data.len().is_multiple_of(4) {
+            self.data.push(0);
+        }
+        self.views.push(json!({
+            "buffer": 0,
+            "byteOffset": self.data.len(),
+            "byteLength": bytes.len(),
+        }));
+        self.data.extend_from_slice(bytes);
+        self.views.len() - 1
+    }
+
+    fn floats(&mut self, values: &[f32], kind: &str) -> usize {
+        let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let components = match kind {
+            "VEC2" => 2,
+            "VEC3" => 3,
+            _ => 4,
+        };
+        let view = self.view(&bytes);
+        self.accessor(json!({
+            "bufferView": view,
+            "componentType": 5126,
+            "count": values.len() / components,
+            "type": kind,
+        }))
+    }
+
+    fn indices(&mut self, indices: &[u16]) -> usize {
+        let bytes: Vec<u8> = indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+        let view = self.view(&bytes);
+        self.accessor(json!({
+            "bufferView": view,
+            "componentType": 5123,
+            "count": indices.len(),
+            "type": "SCALAR",
+        }))
+    }
+
+    fn accessor(&mut self, accessor: Value) -> usize {
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    /// Adds the buffer, views and accessors to `gltf`; `uri` is `None` for
+    /// the binary chunk of a GLB file.
+    fn finish(&self, gltf: &mut Value, uri: Option<String>) {
+        let mut buffer = json!({ "byteLength": self.data.len() });
+        if let Some(uri) = uri {
+            buffer["uri"] = json!(uri);
+        }
+        gltf["asset"] = json!({ "version": "2.0" });
+        gltf["buffers"] = json!([buffer]);
+        gltf["bufferViews"] = json!(self.views);
+        gltf["accessors"] = json!(self.accessors);
+    }
+}
+
+const TRIANGLE: [f32; 9] = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::new();
+    for chunk in data.chunks(3) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |bits, (i, &b)| bits | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(ALPHABET[(bits >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+fn data_uri(data: &[u8]) -> String {
+    format!("data:application/octet-stream;base64,{}", base64(data))
+}
+
+fn png(color: [u8; 4]) -> Vec<u8> {
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    RgbaImage::from_pixel(2, 2, Rgba(color))
+        .write_to(&mut bytes, image::ImageOutputFormat::Png)
+        .unwrap();
+    bytes.into_inner()
+}
+
+fn glb(gltf: &Value, bin: &[u8]) -> Vec<u8> {
+    let mut json = serde_json::to_vec(gltf).unwrap();
+    while !json.len().is_multiple_of(4) {
+        json.push(b' ');
+    }
+    let mut bin = bin.to_vec();
+    while !bin.len().is_multiple_of(4) {
+        bin.push(0);
+    }
+    let mut data = b"glTF".to_vec();
+    data.extend(2u32.to_le_bytes());
+    data.extend((12 + 8 + json.len() as u32 + 8 + bin.len() as u32).to_le_bytes());
+    data.extend((json.len() as u32).to_le_bytes());
+    data.extend(0x4E4F_534Au32.to_le_bytes());
+    data.extend(json);
+    data.extend((bin.len() as u32).to_le_bytes());
+    data.extend(0x004E_4942u32.to_le_bytes());
+    data.extend(bin);
+    data
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("gltf")
+        .join(name);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn parse(gltf: &Value) -> anyhow::Result<GltfScene> {
+    GltfScene::from_slice(&serde_json::to_vec(gltf).unwrap(), Path::new(""))
+}
+
+fn assert_close(a: [f32; 3], b: [f32; 3]) {
+    for i in 0..3 {
+        assert!((a[i] - b[i]).abs() < 1e-5, "{:?} != {:?}", a, b);
+    }
+}
+
+/// A single triangle mesh used by `nodes`, in a data URI buffer.
+fn triangle_scene(nodes: Value) -> Value {
+    let mut buffer = Buffer::default();
+    let position = buffer.floats(&TRIANGLE, "VEC3");
+    let normal = buffer.floats(&[0.0, 0.0, 1.0].repeat(3), "VEC3");
+    let indices = buffer.indices(&[0, 1, 2]);
+    let mut gltf = json!({
+        "meshes": [{
+            "primitives": [{
+                "attributes": { "POSITION": position, "NORMAL": normal },
+                "indices": indices,
+            }],
+        }],
+        "nodes": nodes,
+    });
+    buffer.finish(&mut gltf, Some(data_uri(&buffer.data)));
+    gltf
+}
+
+#[test]
+fn triangle_in_data_uri() {
+    let scene = parse(&triangle_scene(json!([{ "mesh": 0 }]))).unwrap();
+    assert_eq!(scene.meshes.len(), 1);
+    assert_eq!(scene.roots, vec![0]);
+    let primitive = &scene.meshes[0].primitives[0];
+    assert_eq!(primitive.mesh.indices, vec![0, 1, 2]);
+    assert_eq!(primitive.material, None);
+    assert_close(primitive.mesh.vertices[1].position, [1.0, 0.0, 0.0]);
+    assert_close(primitive.mesh.vertices[2].normal, [0.0, 0.0, 1.0]);
+    // missing colors are white
+    assert_eq!(primitive.mesh.vertices[0].color, [1.0; 3]);
+}
+
+#[test]
+fn node_hierarchy_composes_transforms() {
+    // the parent moves by 10 along x and turns 90 degrees about z, the child
+    // is scaled by 2 and moved by 1 along its parent's x axis
+    let s = std::f32::consts::FRAC_1_SQRT_2;
+    let gltf = triangle_scene(json!([
+        { "children": [1], "translation": [10.0, 0.0, 0.0], "rotation": [0.0, 0.0, s, s] },
+        { "mesh": 0, "translation": [1.0, 0.0, 0.0], "scale": [2.0, 2.0, 2.0] },
+    ]));
+    let scene = parse(&gltf).unwrap();
+    assert_eq!(scene.roots, vec![0]);
+    let instances = scene.instances();
+    assert_eq!(instances.len(), 1);
+    let expected = Matrix4::from_translation(Vector3::new(10.0, 0.0, 0.0))
+        * Matrix4::from_angle_z(cgmath::Deg(90.0))
+        * Matrix4::from_translation(Vector3::new(1.0, 0.0, 0.0))
+        * Matrix4::from_scale(2.0);
+    let m: &[f32; 16] = instances[0].1.as_ref();
+    let e: &[f32; 16] = expected.as_ref();
+    for i in 0..16 {
+        assert!((m[i] - e[i]).abs() < 1e-5, "{:?} != {:?}", m, e);
+    }
+
+    let (mesh, materials) = scene.flatten();
+    assert_eq!(materials, vec![None]);
+    assert_close(mesh.vertices[0].position, [10.0, 1.0, 0.0]);
+    assert_close(mesh.vertices[1].position, [10.0, 3.0, 0.0]);
+    assert_close(mesh.vertices[2].position, [8.0, 1.0, 0.0]);
+    assert_close(mesh.vertices[0].normal, [0.0, 0.0, 1.0]);
+}
+
+#[test]
+fn matrix_and_mirroring() {
+    // a column-major matrix mirroring x and moving by 5 along y
+    let gltf = triangle_scene(json!([{
+        "mesh": 0,
+        "matrix": [-1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 5.0, 0.0, 1.0],
+    }]));
+    let (mesh, _) = parse(&gltf).unwrap().flatten();
+    assert_close(mesh.vertices[1].position, [-1.0, 5.0, 0.0]);
+    // the winding is flipped so the front face stays in front
+    assert_eq!(mesh.indices, vec![0, 2, 1]);
+}
+
+#[test]
+fn scene_roots_and_shared_meshes() {
+    let mut gltf = triangle_scene(json!([
+        { "mesh": 0 },
+        { "mesh": 0, "translation": [0.0, 0.0, 3.0] },
+        { "mesh": 0, "translation": [0.0, 0.0, 6.0] },
+    ]));
+    let scene = parse(&gltf).unwrap();
+    // without scenes every node is a root
+    assert_eq!(scene.roots, vec![0, 1, 2]);
+    assert_eq!(scene.flatten().1.len(), 3);
+
+    gltf["scenes"] = json!([{ "nodes": [0] }, { "nodes": [1, 2] }]);
+    gltf["scene"] = json!(1);
+    let scene = parse(&gltf).unwrap();
+    assert_eq!(scene.roots, vec![1, 2]);
+    let (mesh, _) = scene.flatten();
+    assert_eq!(mesh.vertices.len(), 6);
+    assert_close(mesh.vertices[3].position, [0.0, 0.0, 6.0]);
+}
+
+#[test]
+fn glb_with_embedded_image() {
+    let mut buffer = Buffer::default();
+    let position = buffer.floats(&TRIANGLE, "VEC3");
+    let uv = buffer.floats(&[0.0, 0.0, 1.0, 0.0, 0.0, 1.0], "VEC2");
+    let image = buffer.view(&png([200, 100, 50, 255]));
+    let mut gltf = json!({
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "mesh": 0 }],
+        "meshes": [{
+            "primitives": [{ "attributes": { "POSITION": position, "TEXCOORD_0": uv }, "material": 0 }],
+        }],
+        "materials": [{
+            "pbrMetallicRoughness": {
+                "baseColorFactor": [0.5, 0.5, 0.5, 0.8],
+                "baseColorTexture": { "index": 0 },
+                "metallicFactor": 0.25,
+            },
+            "normalTexture": { "index": 0, "scale": 0.5 },
+            "doubleSided": true,
+        }],
+        "textures": [{ "source": 0 }],
+        "images": [{ "bufferView": image, "mimeType": "image/png" }],
+    });
+    buffer.finish(&mut gltf, None);
+    let data = glb(&gltf, &buffer.data);
+
+    let dir = temp_dir("glb");
+    let path = dir.join("scene.glb");
+    std::fs::write(&path, data).unwrap();
+    let scene = GltfScene::load(&path).unwrap();
+
+    let primitive = &scene.meshes[0].primitives[0];
+    // without indices the vertices are the triangles; without normals they
+    // are flat
+    assert_eq!(primitive.mesh.indices.len(), 3);
+    assert_close(primitive.mesh.vertices[0].normal, [0.0, 0.0, 1.0]);
+    assert_eq!(primitive.mesh.vertices[2].uv, [0.0, 1.0]);
+    assert_eq!(primitive.material, Some(0));
+
+    let material = &scene.materials[0];
+    assert_eq!(material.base_color, [0.5, 0.5, 0.5, 0.8]);
+    assert_eq!(material.metallic, 0.25);
+    assert_eq!(material.roughness, 1.0);
+    assert_eq!(material.normal_scale, 0.5);
+    assert!(material.two_side);
+    let texture = material.base_color_texture.as_ref().unwrap();
+    assert_eq!(texture.dimensions(), (2, 2));
+    assert_eq!(texture.get_pixel(1, 1).0, [200, 100, 50, 255]);
+    assert!(material.normal_texture.is_some());
+    assert!(material.occlusion_texture.is_none());
+}
+
+#[test]
+fn external_buffer_and_image() {
+    let mut buffer = Buffer::default();
+    let position = buffer.floats(&TRIANGLE, "VEC3");
+    let mut gltf = json!({
+        "nodes": [{ "mesh": 0 }],
+        "meshes": [{ "primitives": [{ "attributes": { "POSITION": position }, "material": 0 }] }],
+        "materials": [{
+            "pbrMetallicRoughness": { "metallicRoughnessTexture": { "index": 0 } },
+            "occlusionTexture": { "index": 0, "strength": 0.3 },
+        }],
+        "textures": [{ "source": 0 }],
+        "images": [{ "uri": "textures/occlusion.png" }],
+    });
+    buffer.finish(&mut gltf, Some("triangle.bin".to_string()));
+
+    let dir = temp_dir("external");
+    std::fs::create_dir_all(dir.join("textures")).unwrap();
+    std::fs::write(dir.join("triangle.bin"), &buffer.data).unwrap();
+    std::fs::write(dir.join("textures/occlusion.png"), png([0, 255, 128, 255])).unwrap();
+    std::fs::write(dir.join("scene.gltf"), serde_json::to_vec(&gltf).unwrap()).unwrap();
+
+    let scene = GltfScene::load(dir.join("scene.gltf")).unwrap();
+    assert_close(
+        scene.meshes[0].primitives[0].mesh.vertices[2].position,
+        [0.0, 1.0, 0.0],
+    );
+    let material = &scene.materials[0];
+    assert_eq!(material.occlusion_strength, 0.3);
+    assert_eq!(
+        material
+            .metallic_roughness_texture
+            .as_ref()
+            .unwrap()
+            .get_pixel(0, 0)
+            .0,
+        [0, 255, 128, 255]
+    );
+
+    std::fs::remove_file(dir.join("triangle.bin")).unwrap();
+    let error = GltfScene::load(dir.join("scene.gltf")).err().unwrap();
+    assert!(
+        format!("{:#}", error).contains("triangle.bin"),
+        "{:#}",
+        error
+    );
+}
+
+#[test]
+fn strips_fans_and_strided_data() {
+    // four positions with four bytes of padding after each,
+    // and normalized unsigned byte colors
+    let mut bytes = Vec::new();
+    let quad = [
+        [0.0f32, 0.0, 0.0],
+        [1.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [1.0, 1.0, 0.0],
+    ];
+    for p in quad {
+        bytes.extend(p.iter().flat_map(|v| v.to_le_bytes()));
+        bytes.extend([0u8; 4]);
+    }
+    let mut buffer = Buffer::default();
+    let view = buffer.view(&bytes);
+    buffer.views[view]["byteStride"] = json!(16);
+    let position = buffer.accessor(json!({
+        "bufferView": view, "componentType": 5126, "count": 4, "type": "VEC3",
+    }));
+    let colors = buffer.view(&[
+        255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 255, 255,
+    ]);
+    let color = buffer.accessor(json!({
+        "bufferView": colors, "componentType": 5121, "normalized": true, "count": 4, "type": "VEC4",
+    }));
+    let attributes = json!({ "POSITION": position, "COLOR_0": color });
+    // the strip order of the quad, then its fan order
+    let fan_indices = buffer.indices(&[0, 1, 3, 2]);
+    let mut gltf = json!({
+        "nodes": [{ "mesh": 0 }],
+        "meshes": [{
+            "primitives": [
+                { "attributes": attributes, "mode": 5 },
+                { "attributes": attributes, "indices": fan_indices, "mode": 6 },
+                { "attributes": attributes, "mode": 1 },
+            ],
+        }],
+    });
+    buffer.finish(&mut gltf, Some(data_uri(&buffer.data)));
+    let scene = parse(&gltf).unwrap();
+
+    // lines are skipped
+    let primitives = &scene.meshes[0].primitives;
+    assert_eq!(primitives.len(), 2);
+    let strip = &primitives[0].mesh;
+    let fan = &primitives[1].mesh;
+    // flat normals duplicate the vertices of every triangle
+    assert_eq!(strip.vertices.len(), 6);
+    assert_close(strip.vertices[4].position, [1.0, 1.0, 0.0]);
+    assert_close(strip.vertices[5].position, [0.0, 1.0, 0.0]);
+    assert_close(fan.vertices[3].position, [1.0, 1.0, 0.0]);
+    assert_close(fan.vertices[5].position, [0.0, 0.0, 0.0]);
+    // both keep the winding of the first triangle
+    for mesh in [strip, fan] {
+        for v in &mesh.vertices {
+            assert_close(v.normal, [0.0, 0.0, 1.0]);
+        }
+    }
+    assert_close(strip.vertices[1].color, [0.0, 1.0, 0.0]);
+}
+
+#[test]
+fn invalid_files() {
+    let error = |gltf: &Value| format!("{:#}", parse(gltf).err().unwrap());
+
+    let mut gltf = triangle_scene(json!([{ "mesh": 0 }]));
+    gltf["asset"]["version"] = json!("1.0");
+    assert!(error(&gltf).contains("version"));
+
+    let gltf = triangle_scene(json!([{ "mesh": 1 }]));
+    assert!(error(&gltf).contains("mesh that does not exist"));
+
+    let gltf = triangle_scene(json!([{ "children": [1] }, { "children": [0] }]));
+    assert!(error(&gltf).contains("ancestor"));
+
+    let mut gltf = triangle_scene(json!([{ "mesh": 0 }]));
+    gltf["accessors"][0]["sparse"] = json!({ "count": 1 });
+    assert!(error(&gltf).contains("sparse"));
+
+    let mut gltf = triangle_scene(json!([{ "mesh": 0 }]));
+    gltf["accessors"][0]["count"] = json!(100);
+    assert!(error(&gltf).contains("past the end"));
+
+    let mut gltf = triangle_scene(json!([{ "mesh": 0 }]));
+    gltf["bufferViews"][0]["byteLength"] = json!(1000);
+    assert!(error(&gltf).contains("out of range"));
+
+    let mut data = glb(&json!({ "asset": { "version": "2.0" } }), &[]);
+    data[4] = 1;
+    let error = GltfScene::from_slice(&data, Path::new("")).err().unwrap();
+    assert!(error.to_string().contains("GLB version"));
+    assert!(GltfScene::from_slice(b"glTF\x02\0\0\0", Path::new("")).is_err());
+}
@@ -0,0 +1,728 @@
+//! Golden-image regression tests for the chapter examples.
+//!
+//! Each test renders one example headless and compares it against
+//! `tests/golden/<example>.png`; see `common` for the knobs. The CPU versions
+//! below mirror the examples' pipelines and shaders so the tests still mean
+//! something on machines without any wgpu adapter.
+
+mod common;
+
+use cgmath::{Angle, Deg, InnerSpace, Matrix, Matrix4, Point3, SquareMatrix, Vector3, Vector4};
+use common::raster::{clip_vertex, srgb_to_linear, ClipVertex, PipelineState, Rasterizer};
+use common::{HEIGHT, TIME, WIDTH};
+use image::RgbaImage;
+use wgpu_book::light::{Light, LightKind};
+use wgpu_book::mesh::Shading;
+use wgpu_book::overlay::OverlayBuilder;
+use wgpu_book::shadow::{self, SHADOW_BIAS, SHADOW_MAP_SIZE};
+use wgpu_book::{colormap::Colormap, math_func, surface_data, transforms, vertex_data};
+
+const CLEAR_2D: [f32; 3] = [0.05, 0.062, 0.08];
+const CLEAR_3D: [f32; 3] = [0.2, 0.247, 0.314];
+
+struct Camera {
+    position: Point3<f32>,
+    view_project: Matrix4<f32>,
+}
+
+fn camera(position: (f32, f32, f32)) -> Camera {
+    let position = position.into();
+    let (_, _, view_project) = transforms::create_view_projection(
+        position,
+        (0.0, 0.0, 0.0).into(),
+        Vector3::unit_y(),
+        WIDTH as f32 / HEIGHT as f32,
+        true,
+    );
+    Camera {
+        position,
+        view_project,
+    }
+}
+
+/// The model matrix every animated example uses at `TIME`.
+fn animated_model() -> Matrix4<f32> {
+    transforms::create_transforms(
+        [0.0, 0.0, 0.0],
+        [TIME.sin(), TIME.cos(), 0.0],
+        [1.0, 1.0, 1.0],
+    )
+}
+
+fn point(p: [f32; 3]) -> Vector4<f32> {
+    Vector4::new(p[0], p[1], p[2], 1.0)
+}
+
+fn i8x3(p: [i8; 3]) -> [f32; 3] {
+    [p[0] as f32, p[1] as f32, p[2] as f32]
+}
+
+fn color_varyings(v: &[f32]) -> [f32; 3] {
+    [v[0], v[1], v[2]]
+}
+
+fn ndc_triangles(positions: &[[f32; 2]], colors: &[[f32; 3]], clear: [f32; 3]) -> RgbaImage {
+    let vertices: Vec<ClipVertex> = positions
+        .iter()
+        .zip(colors)
+        .map(|(p, c)| clip_vertex(Vector4::new(p[0], p[1], 0.0, 1.0), c))
+        .collect();
+    let mut raster = Rasterizer::new(WIDTH, HEIGHT, clear);
+    raster.triangles(&vertices, PipelineState::default(), &color_varyings);
+    raster.into_image()
+}
+
+const TRIANGLE: [[f32; 2]; 3] = [[0.0, 0.5], [-0.5, -0.5], [0.5, -0.5]];
+
+fn cpu_ch02_first_triangle() -> RgbaImage {
+    ndc_triangles(&TRIANGLE, &[[1.0, 1.0, 0.0]; 3], CLEAR_2D)
+}
+
+fn cpu_ch02_color_triangle() -> RgbaImage {
+    let colors = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    ndc_triangles(&TRIANGLE, &colors, CLEAR_2D)
+}
+
+fn cpu_ch04_square() -> RgbaImage {
+    let positions = [
+        [-0.5, -0.5],
+        [0.5, -0.5],
+        [-0.5, 0.5],
+        [-0.5, 0.5],
+        [0.5, -0.5],
+        [0.5, 0.5],
+    ];
+    let colors = [
+        [1.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [1.0, 1.0, 0.0],
+        [1.0, 1.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [0.0, 0.0, 1.0],
+    ];
+    ndc_triangles(&positions, &colors, CLEAR_3D)
+}
+
+fn colored_cube(positions: &[[f32; 3]], colors: &[[f32; 3]], model: Matrix4<f32>) -> RgbaImage {
+    let mvp = camera((3.0, 1.5, 3.0)).view_project * model;
+    let vertices: Vec<ClipVertex> = positions
+        .iter()
+        .zip(colors)
+        .map(|(p, c)| clip_vertex(mvp * point(*p), c))
+        .collect();
+    let state = PipelineState {
+        cull_back: false,
+        depth_test: true,
+    };
+    let mut raster = Rasterizer::new(WIDTH, HEIGHT, CLEAR_3D);
+    raster.triangles(&vertices, state, &color_varyings);
+    raster.into_image()
+}
+
+fn cube_face_colors() -> (Vec<[f32; 3]>, Vec<[f32; 3]>) {
+    let (pos, col, _uv, _normal) = vertex_data::cube_data();
+    (
+        pos.into_iter().map(i8x3).collect(),
+        col.into_iter().map(i8x3).collect(),
+    )
+}
+
+fn cpu_ch06_cube_face_color() -> RgbaImage {
+    let (positions, colors) = cube_face_colors();
+    colored_cube(&positions, &colors, Matrix4::identity())
+}
+
+fn cpu_ch06_rotate_cube() -> RgbaImage {
+    let (positions, colors) = cube_face_colors();
+    colored_cube(&positions, &colors, animated_model())
+}
+
+fn cpu_ch06_cube_vertex_color() -> RgbaImage {
+    let (pos, col, indices) = vertex_data::cube_data_index();
+    let positions: Vec<[f32; 3]> = indices.iter().map(|&i| i8x3(pos[i as usize])).collect();
+    let colors: Vec<[f32; 3]> = indices.iter().map(|&i| i8x3(col[i as usize])).collect();
+    colored_cube(&positions, &colors, Matrix4::identity())
+}
+
+fn wireframe(lines: &[[f32; 3]]) -> RgbaImage {
+    let mvp = camera((3.0, 1.5, 3.0)).view_project * animated_model();
+    let vertices: Vec<ClipVertex> = lines
+        .iter()
+        .map(|p| clip_vertex(mvp * point(*p), &[]))
+        .collect();
+    let mut raster = Rasterizer::new(WIDTH, HEIGHT, CLEAR_3D);
+    raster.lines(&vertices, PipelineState::default(), &|_| [1.0, 1.0, 0.0]);
+    raster.into_image()
+}
+
+fn cpu_ch07_cube() -> RgbaImage {
+    let p: [[f32; 3]; 8] = [
+        [-1.0, 1.0, 1.0],
+        [-1.0, 1.0, -1.0],
+        [1.0, 1.0, -1.0],
+        [1.0, 1.0, 1.0],
+        [-1.0, -1.0, 1.0],
+        [-1.0, -1.0, -1.0],
+        [1.0, -1.0, -1.0],
+        [1.0, -1.0, 1.0],
+    ];
+    wireframe(&[
+        p[0], p[1], p[1], p[2], p[2], p[3], p[3], p[0], p[4], p[5], p[5], p[6], p[6], p[7], p[7],
+        p[4], p[0], p[4], p[1], p[5], p[2], p[6], p[3], p[7],
+    ])
+}
+
+fn cpu_ch07_sphere() -> RgbaImage {
+    let (r, u, v) = (1.7, 15, 20);
+    let mut lines = Vec::new();
+    for i in 0..u - 1 {
+        for j in 0..v - 1 {
+            let theta = i as f32 * 180.0 / (u as f32 - 1.0);
+            let phi = j as f32 * 360.0 / (v as f32 - 1.0);
+            let theta1 = (i as f32 + 1.0) * 180.0 / (u as f32 - 1.0);
+            let phi1 = (j as f32 + 1.0) * 360.0 / (v as f32 - 1.0);
+            let p0 = math_func::sphere_position(r, Deg(theta), Deg(phi));
+            let p1 = math_func::sphere_position(r, Deg(theta1), Deg(phi));
+            let p3 = math_func::sphere_position(r, Deg(theta), Deg(phi1));
+            lines.extend([p0, p1, p0, p3]);
+        }
+    }
+    wireframe(&lines)
+}
+
+fn cpu_ch07_cylinder() -> RgbaImage {
+    let (rin, rout, h, n) = (0.5, 1.0, 2.5 / 2.0, 50);
+    let mut lines = Vec::new();
+    for i in 0..n - 1 {
+        let theta = Deg(i as f32 * 360.0 / (n as f32 - 1.0));
+        let theta1 = Deg((i as f32 + 1.0) * 360.0 / (n as f32 - 1.0));
+        let p0 = math_func::cylinder_position(rout, h, theta);
+        let p1 = math_func::cylinder_position(rout, -h, theta);
+        let p2 = math_func::cylinder_position(rin, -h, theta);
+        let p3 = math_func::cylinder_position(rin, h, theta);
+        let p4 = math_func::cylinder_position(rout, h, theta1);
+        let p5 = math_func::cylinder_position(rout, -h, theta1);
+        let p6 = math_func::cylinder_position(rin, -h, theta1);
+        let p7 = math_func::cylinder_position(rin, h, theta1);
+        lines.extend([
+            p0, p3, p3, p7, p4, p0, p1, p2, p2, p6, p5, p1, p0, p1, p3, p2,
+        ]);
+    }
+    wireframe(&lines)
+}
+
+fn cpu_ch07_cone() -> RgbaImage {
+    let (rtop, rbottom, h, n) = (0.0, 1.0, 1.0, 20);
+    let mut lines = Vec::new();
+    for i in 0..n - 1 {
+        let theta = Deg(i as f32 * 360.0 / (n as f32 - 1.0));
+        let theta1 = Deg((i as f32 + 1.0) * 360.0 / (n as f32 - 1.0));
+        let p0 = math_func::cylinder_position(rtop, h, theta);
+        let p1 = math_func::cylinder_position(rbottom, -h, theta);
+        let p2 = math_func::cylinder_position(0.0, -h, theta);
+        let p3 = math_func::cylinder_position(0.0, h, theta);
+        let p4 = math_func::cylinder_position(rtop, h, theta1);
+        let p5 = math_func::cylinder_position(rbottom, -h, theta1);
+        lines.extend([p0, p3, p4, p0, p1, p2, p5, p1, p0, p1]);
+    }
+    wireframe(&lines)
+}
+
+fn cpu_ch07_torus() -> RgbaImage {
+    let (r_torus, r_tube, n_torus, n_tube) = (1.5, 0.3, 40, 13);
+    let mut lines = Vec::new();
+    for i in 0..n_torus - 1 {
+        for j in 0..n_tube - 1 {
+            let u = i as f32 * 360.0 / (n_torus as f32 - 1.0);
+            let v = j as f32 * 360.0 / (n_tube as f32 - 1.0);
+            let u1 = (i as f32 + 1.0) * 360.0 / (n_torus as f32 - 1.0);
+            let v1 = (j as f32 + 1.0) * 360.0 / (n_tube as f32 - 1.0);
+            let p0 = math_func::torus_position(r_torus, r_tube, Deg(u), Deg(v));
+            let p1 = math_func::torus_position(r_torus, r_tube, Deg(u1), Deg(v));
+            let p3 = math_func::torus_position(r_torus, r_tube, Deg(u), Deg(v1));
+            lines.extend([p0, p1, p0, p3]);
+        }
+    }
+    wireframe(&lines)
+}
+
+struct Material {
+    specular_color: [f32; 3],
+    ambient: f32,
+    diffuse: f32,
+    specular: f32,
+    shininess: f32,
+    two_side: bool,
+}
+
+/// `default_lights` of the chapter 8-10 examples.
+fn scene_lights(camera: &Camera) -> Vec<Light> {
+    vec![
+        Light::directional([0.5, -1.0, -0.3], [1.0, 1.0, 1.0]).with_intensity(0.5),
+        Light::point(camera.position.into(), [1.0, 1.0, 1.0]).with_intensity(0.7),
+    ]
+}
+
+/// The depth pass of `wgpu_book::shadow::ShadowMap` and the PCF lookup of
+/// shadow.wgsl.
+struct ShadowMap {
+    light_index: usize,
+    light_view_project: Matrix4<f32>,
+    depth: Rasterizer,
+}
+
+impl ShadowMap {
+    /// `casters` is a triangle list in world space.
+    fn new(lights: &[Light], radius: f32, casters: &[Vector4<f32>], cull_back: bool) -> Self {
+        let light_index = shadow::shadow_light(lights).unwrap();
+        let light_view_project =
+            shadow::light_view_projection(&lights[light_index], Point3::new(0.0, 0.0, 0.0), radius)
+                .unwrap();
+        let vertices: Vec<ClipVertex> = casters
+            .iter()
+            .map(|&p| clip_vertex(light_view_project * p, &[]))
+            .collect();
+        let state = PipelineState {
+            cull_back,
+            depth_test: true,
+        };
+        let mut depth = Rasterizer::new(SHADOW_MAP_SIZE, SHADOW_MAP_SIZE, [0.0; 3]);
+        depth.triangles(&vertices, state, &|_| [0.0; 3]);
+        Self {
+            light_index,
+            light_view_project,
+            depth,
+        }
+    }
+
+    // one bilinearly filtered LessEqual comparison, clamped to the edge
+    fn compare(&self, u: f32, v: f32, depth: f32) -> f32 {
+        let size = SHADOW_MAP_SIZE as f32;
+        let (x, y) = (u * size - 0.5, v * size - 0.5);
+        let (fx, fy) = (x - x.floor(), y - y.floor());
+        let texel = |dx: f32, dy: f32| {
+            let tx = (x.floor() + dx).clamp(0.0, size - 1.0) as u32;
+            let ty = (y.floor() + dy).clamp(0.0, size - 1.0) as u32;
+            if depth <= self.depth.depth(tx, ty) {
+                1.0
+            } else {
+                0.0
+            }
+        };
+        let top = texel(0.0, 0.0) * (1.0 - fx) + texel(1.0, 0.0) * fx;
+        let bottom = texel(0.0, 1.0) * (1.0 - fx) + texel(1.0, 1.0) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+
+    fn visibility(&self, light_index: usize, position: Vector3<f32>) -> f32 {
+        if light_index != self.light_index {
+            return 1.0;
+        }
+        let clip = self.light_view_project * position.extend(1.0);
+        if clip.w <= 0.0 {
+            return 1.0;
+        }
+        let ndc = clip.truncate() / clip.w;
+        let (u, v) = (ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) || ndc.z > 1.0 {
+            return 1.0;
+        }
+        let depth = ndc.z - SHADOW_BIAS;
+        let texel = 1.0 / SHADOW_MAP_SIZE as f32;
+        let mut sum = 0.0;
+        for y in -1..=1 {
+            for x in -1..=1 {
+                sum += self.compare(u + x as f32 * texel, v + y as f32 * texel, depth);
+            }
+        }
+        sum / 9.0
+    }
+}
+
+/// WGSL `smoothstep`.
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// `blinn_phong_lights` from light.wgsl plus the ambient term of the chapter
+/// 8-10 shaders. `varyings` holds the world position followed by the normal.
+fn blinn_phong(
+    material: &Material,
+    lights: &[Light],
+    shadow_map: &ShadowMap,
+    eye: Point3<f32>,
+    varyings: &[f32],
+    base: [f32; 3],
+) -> [f32; 3] {
+    let position = Vector3::new(varyings[0], varyings[1], varyings[2]);
+    let n = Vector3::new(varyings[3], varyings[4], varyings[5]).normalize();
+    let v = (Vector3::new(eye.x, eye.y, eye.z) - position).normalize();
+
+    let mut diffuse = Vector3::new(0.0, 0.0, 0.0);
+    let mut specular = Vector3::new(0.0, 0.0, 0.0);
+    for (i, light) in lights.iter().enumerate() {
+        let mut radiance = Vector3::from(light.color) * light.intensity;
+        let l = match light.kind {
+            LightKind::Directional { direction } => -Vector3::from(direction).normalize(),
+            LightKind::Point { position: p } | LightKind::Spot { position: p, .. } => {
+                let to_light = Vector3::from(p) - position;
+                let distance = to_light.magnitude();
+                let [c, k1, k2] = light.attenuation;
+                radiance /= (c + k1 * distance + k2 * distance * distance).max(0.0001);
+                to_light / distance
+            }
+        };
+        if let LightKind::Spot {
+            direction,
+            inner,
+            outer,
+            ..
+        } = light.kind
+        {
+            // the cone cosines of `Light::raw`
+            let outer = Deg(outer.0.max(inner.0 + 0.1));
+            let cos_angle = (-l).dot(Vector3::from(direction).normalize());
+            radiance *= smoothstep(outer.cos(), inner.cos(), cos_angle);
+        }
+        radiance *= shadow_map.visibility(i, position);
+        let h = (l + v).normalize();
+
+        let mut d = material.diffuse * n.dot(l).max(0.0);
+        let mut s = material.specular * n.dot(h).max(0.0).powf(material.shininess);
+        if material.two_side {
+            d += material.diffuse * (-n).dot(l).max(0.0);
+            s += material.specular * (-n).dot(h).max(0.0).powf(material.shininess);
+        }
+        diffuse += radiance * d;
+        specular += radiance * s;
+    }
+
+    let sc = material.specular_color;
+    [0, 1, 2].map(|i| base[i] * (material.ambient + diffuse[i]) + sc[i] * specular[i])
+}
+
+/// World positions of a transformed triangle list.
+fn world_positions(positions: &[[f32; 3]]) -> Vec<Vector4<f32>> {
+    let model = animated_model();
+    positions.iter().map(|p| model * point(*p)).collect()
+}
+
+/// Transforms a lit mesh; the extra per-vertex values follow position and normal.
+fn lit_vertices(
+    camera: &Camera,
+    positions: &[[f32; 3]],
+    normals: &[[f32; 3]],
+    extra: &[Vec<f32>],
+) -> Vec<ClipVertex> {
+    let model = animated_model();
+    let normal_mat = model.invert().unwrap().transpose();
+    positions
+        .iter()
+        .zip(normals)
+        .zip(extra)
+        .map(|((p, n), e)| {
+            let world = model * point(*p);
+            let normal = normal_mat * point(*n);
+            let mut varyings = vec![world.x, world.y, world.z, normal.x, normal.y, normal.z];
+            varyings.extend_from_slice(e);
+            clip_vertex(camera.view_project * world, &varyings)
+        })
+        .collect()
+}
+
+/// The `GroundPlane` the lit chapters draw after their mesh.
+fn ground(
+    raster: &mut Rasterizer,
+    camera: &Camera,
+    lights: &[Light],
+    shadow_map: &ShadowMap,
+    height: f32,
+) {
+    let material = Material {
+        specular_color: [1.0, 1.0, 1.0],
+        ambient: 0.1,
+        diffuse: 0.8,
+        specular: 0.1,
+        shininess: 10.0,
+        two_side: false,
+    };
+    let s = 8.0;
+    let corners = [[-s, -s], [-s, s], [s, s], [s, s], [s, -s], [-s, -s]];
+    let vertices: Vec<ClipVertex> = corners
+        .iter()
+        .map(|&[x, z]| {
+            let varyings = [x, height, z, 0.0, 1.0, 0.0];
+            clip_vertex(camera.view_project * point([x, height, z]), &varyings)
+        })
+        .collect();
+    let state = PipelineState {
+        cull_back: false,
+        depth_test: true,
+    };
+    raster.triangles(&vertices, state, &|v| {
+        blinn_phong(&material, lights, shadow_map, camera.position, v, [0.5; 3])
+    });
+}
+
+/// A solid-colored mesh of the ch08 examples, standing on the ground.
+fn ch08_solid(
+    camera: &Camera,
+    lights: &[Light],
+    material: &Material,
+    positions: &[[f32; 3]],
+    normals: &[[f32; 3]],
+    base: [f32; 3],
+) -> RgbaImage {
+    let shadow_map = ShadowMap::new(lights, 2.5, &world_positions(positions), true);
+    let vertices = lit_vertices(camera, positions, normals, &vec![vec![]; positions.len()]);
+
+    let state = PipelineState {
+        cull_back: true,
+        depth_test: true,
+    };
+    let mut raster = Rasterizer::new(WIDTH, HEIGHT, CLEAR_3D);
+    raster.triangles(&vertices, state, &|v| {
+        blinn_phong(material, lights, &shadow_map, camera.position, v, base)
+    });
+    ground(&mut raster, camera, lights, &shadow_map, -2.5);
+    raster.into_image()
+}
+
+fn cpu_ch08_cube() -> RgbaImage {
+    let camera = camera((3.0, 1.5, 3.0));
+    let material = Material {
+        specular_color: [1.0, 1.0, 0.0],
+        ambient: 0.1,
+        diffuse: 0.6,
+        specular: 0.3,
+        shininess: 30.0,
+        two_side: false,
+    };
+    let (pos, _col, _uv, normal) = vertex_data::cube_data();
+    let positions: Vec<[f32; 3]> = pos.into_iter().map(i8x3).collect();
+    let normals: Vec<[f32; 3]> = normal.into_iter().map(i8x3).collect();
+    ch08_solid(
+        &camera,
+        &scene_lights(&camera),
+        &material,
+        &positions,
+        &normals,
+        [1.0, 0.0, 0.0],
+    )
+}
+
+fn cpu_ch08_lights() -> RgbaImage {
+    let camera = camera((3.0, 1.5, 3.0));
+    let lights = [
+        Light::directional([-0.3, -1.0, -0.2], [1.0, 1.0, 1.0]).with_intensity(0.2),
+        Light::point([3.0, 0.5, -1.0], [1.0, 0.2, 0.1]).with_attenuation(1.0, 0.1, 0.05),
+        Light::spot(
+            [-1.0, 1.5, 3.0],
+            [0.3, -0.4, -1.0],
+            Deg(8.0),
+            Deg(12.0),
+            [0.2, 0.4, 1.0],
+        )
+        .with_intensity(2.0),
+    ];
+    let material = Material {
+        specular_color: [1.0, 1.0, 1.0],
+        ambient: 0.05,
+        diffuse: 0.8,
+        specular: 0.4,
+        shininess: 30.0,
+        two_side: false,
+    };
+    let (positions, normals, _uv) = vertex_data::torus_data(1.5, 0.4, 40, 15);
+    ch08_solid(&camera, &lights, &material, &positions, &normals, [1.0; 3])
+}
+
+fn cpu_ch09_sinc() -> RgbaImage {
+    let camera = camera((3.5, 1.75, 3.5));
+    let lights = scene_lights(&camera);
+    let material = Material {
+        specular_color: [1.0, 1.0, 1.0],
+        ambient: 0.1,
+        diffuse: 0.8,
+        specular: 0.4,
+        shininess: 30.0,
+        two_side: true,
+    };
+    let jet = Colormap::from_name("jet").unwrap();
+    let mesh = surface_data::surface_mesh(
+        &math_func::sinc,
+        &jet,
+        -8.0,
+        8.0,
+        -8.0,
+        8.0,
+        30,
+        30,
+        2.0,
+        0.3,
+        Shading::Smooth,
+    );
+    let corners = mesh.indices.iter().map(|&i| mesh.vertices[i as usize]);
+    let positions: Vec<[f32; 3]> = corners.clone().map(|v| v.position).collect();
+    let normals: Vec<[f32; 3]> = corners.clone().map(|v| v.normal).collect();
+    let values: Vec<Vec<f32>> = corners.map(|v| vec![v.scalar]).collect();
+    let shadow_map = ShadowMap::new(&lights, 3.0, &world_positions(&positions), false);
+    let vertices = lit_vertices(&camera, &positions, &normals, &values);
+
+    let state = PipelineState {
+        cull_back: false,
+        depth_test: true,
+    };
+    let mut raster = Rasterizer::new(WIDTH, HEIGHT, CLEAR_3D);
+    raster.triangles(&vertices, state, &|v| {
+        let color = jet.color(v[6]);
+        blinn_phong(&material, &lights, &shadow_map, camera.position, v, color)
+    });
+    ground(&mut raster, &camera, &lights, &shadow_map, -3.0);
+
+    // colorbar and axes; the overlay vertices are already in NDC
+    let plot_box =
+        surface_data::surface_plot_box(&math_func::sinc, -8.0, 8.0, -8.0, 8.0, 30, 30, 2.0, 0.3);
+    let mut overlay = OverlayBuilder::new(WIDTH, HEIGHT);
+    overlay.axes(&plot_box, camera.view_project * animated_model());
+    overlay.colorbar(plot_box.data_min[1], plot_box.data_max[1]);
+    let vertices: Vec<ClipVertex> = overlay
+        .vertices()
+        .iter()
+        .map(|v| {
+            let [x, y] = v.position;
+            let [r, g, b, _] = v.color;
+            clip_vertex(Vector4::new(x, y, 0.0, 1.0), &[r, g, b, v.value])
+        })
+        .collect();
+    raster.triangles(&vertices, PipelineState::default(), &|v| {
+        if v[3] < 0.0 {
+            [v[0], v[1], v[2]]
+        } else {
+            jet.color(v[3])
+        }
+    });
+    raster.into_image()
+}
+
+fn cpu_ch10_cube() -> RgbaImage {
+    let camera = camera((2.5, 1.25, 2.5));
+    let lights = scene_lights(&camera);
+    let material = Material {
+        specular_color: [1.0, 1.0, 0.0],
+        ambient: 0.1,
+        diffuse: 0.8,
+        specular: 0.4,
+        shininess: 30.0,
+        two_side: true,
+    };
+    let texture = image::open(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/examples/ch10/assets/red-brick-wall.png"
+    ))
+    .unwrap()
+    .to_rgba8();
+
+    let (pos, _col, uv, normal) = vertex_data::cube_data();
+    let positions: Vec<[f32; 3]> = pos.into_iter().map(i8x3).collect();
+    let normals: Vec<[f32; 3]> = normal.into_iter().map(i8x3).collect();
+    let uvs: Vec<Vec<f32>> = uv.iter().map(|t| vec![t[0] as f32, t[1] as f32]).collect();
+    let shadow_map = ShadowMap::new(&lights, 2.5, &world_positions(&positions), false);
+    let vertices = lit_vertices(&camera, &positions, &normals, &uvs);
+
+    // the cube is minified at this size, and the sampler uses nearest filtering for that
+    let sample = |u: f32, v: f32| {
+        let x = (u * texture.width() as f32).clamp(0.0, texture.width() as f32 - 1.0);
+        let y = (v * texture.height() as f32).clamp(0.0, texture.height() as f32 - 1.0);
+        let texel = texture.get_pixel(x as u32, y as u32);
+        [0, 1, 2].map(|i| srgb_to_linear(texel[i]))
+    };
+
+    let state = PipelineState {
+        cull_back: false,
+        depth_test: true,
+    };
+    let mut raster = Rasterizer::new(WIDTH, HEIGHT, CLEAR_3D);
+    raster.triangles(&vertices, state, &|v| {
+        let base = sample(v[6], v[7]);
+        blinn_phong(&material, &lights, &shadow_map, camera.position, v, base)
+    });
+    ground(&mut raster, &camera, &lights, &shadow_map, -2.5);
+    raster.into_image()
+}
+
+#[test]
+fn ch02_first_triangle() {
+    common::check("ch02_first_triangle", &[], cpu_ch02_first_triangle);
+}
+
+#[test]
+fn ch02_color_triangle() {
+    common::check("ch02_color_triangle", &[], cpu_ch02_color_triangle);
+}
+
+#[test]
+fn ch04_square() {
+    common::check("ch04_square", &[], cpu_ch04_square);
+}
+
+#[test]
+fn ch06_cube_face_color() {
+    common::check("ch06_cube_face_color", &[], cpu_ch06_cube_face_color);
+}
+
+#[test]
+fn ch06_cube_vertex_color() {
+    common::check("ch06_cube_vertex_color", &[], cpu_ch06_cube_vertex_color);
+}
+
+#[test]
+fn ch06_rotate_cube() {
+    common::check("ch06_rotate_cube", &[], cpu_ch06_rotate_cube);
+}
+
+#[test]
+fn ch07_cube() {
+    common::check("ch07_cube", &[], cpu_ch07_cube);
+}
+
+#[test]
+fn ch07_sphere() {
+    common::check("ch07_sphere", &[], cpu_ch07_sphere);
+}
+
+#[test]
+fn ch07_cylinder() {
+    common::check("ch07_cylinder", &[], cpu_ch07_cylinder);
+}
+
+#[test]
+fn ch07_cone() {
+    common::check("ch07_cone", &[], cpu_ch07_cone);
+}
+
+#[test]
+fn ch07_torus() {
+    common::check("ch07_torus", &[], cpu_ch07_torus);
+}
+
+#[test]
+fn ch08_cube() {
+    common::check("ch08_cube", &[], cpu_ch08_cube);
+}
+
+#[test]
+fn ch08_lights() {
+    common::check("ch08_lights", &[], cpu_ch08_lights);
+}
+
+#[test]
+fn ch09_sinc() {
+    common::check("ch09_sinc", &[], cpu_ch09_sinc);
+}
+
+#[test]
+fn ch10_cube() {
+    common::check("ch10_cube", &[], cpu_ch10_cube);
+}
@@ -0,0 +1,203 @@
+//! Layouts and per-instance data of the instanced renderer, and the pixels
+//! of its two draw modes.
+
+mod common;
+
+use cgmath::{InnerSpace, Matrix4, Vector3};
+use common::{HEIGHT, WIDTH};
+use image::RgbaImage;
+use std::{iter, mem};
+use wgpu_book::colormap::Colormap;
+use wgpu_book::depth::{DepthConfig, DepthTarget};
+use wgpu_book::headless;
+use wgpu_book::instance::{self, DrawMode, Instance, InstanceRaw, InstanceRenderer, Layout};
+use wgpu_book::scene::Transform;
+use wgpu_book::transforms::{self, InitWgpu};
+use wgpu_book::vertex_data;
+
+fn jet() -> Colormap {
+    Colormap::from_name("jet").unwrap()
+}
+
+fn translation(instance: &Instance) -> Vector3<f32> {
+    instance.transform.translation.into()
+}
+
+#[test]
+fn raw_instance_matches_shader_layout() {
+    // four matrix columns and a color, at locations 2 to 6
+    assert_eq!(mem::size_of::<InstanceRaw>(), 80);
+    let desc = InstanceRaw::desc();
+    assert_eq!(desc.step_mode, wgpu::VertexStepMode::Instance);
+    assert_eq!(desc.array_stride, 80);
+    let locations: Vec<u32> = desc.attributes.iter().map(|a| a.shader_location).collect();
+    assert_eq!(locations, [2, 3, 4, 5, 6]);
+
+    let instance = Instance {
+        transform: Transform::new([1.0, 2.0, 3.0], [0.0, 0.5, 0.0], [0.5; 3]),
+        color: [0.1, 0.2, 0.3],
+    };
+    let raw = instance.to_raw();
+    assert_eq!(Matrix4::from(raw.model), instance.transform.matrix());
+    assert_eq!(raw.model[3], [1.0, 2.0, 3.0, 1.0]);
+    assert_eq!(raw.color, [0.1, 0.2, 0.3, 1.0]);
+}
+
+#[test]
+fn grid_fills_cube_from_the_bottom() {
+    let instances = instance::grid(10, 2.0, &jet());
+    assert_eq!(instances.len(), 10);
+    // a 3x3x3 grid with a spacing of 4/3, and one object on the second layer
+    let spacing = 4.0 / 3.0;
+    assert_eq!(
+        translation(&instances[0]),
+        Vector3::new(
+            -2.0 + spacing / 2.0,
+            -2.0 + spacing / 2.0,
+            -2.0 + spacing / 2.0
+        )
+    );
+    assert!(instances[..9]
+        .iter()
+        .all(|i| i.transform.translation[1] == instances[0].transform.translation[1]));
+    assert!(
+        (instances[9].transform.translation[1] - instances[0].transform.translation[1] - spacing)
+            .abs()
+            < 1e-6
+    );
+    assert_eq!(instances[0].color, jet().color(0.0));
+    assert_eq!(instances[9].color, jet().color(0.5));
+
+    // the objects do not overlap and stay inside the cube
+    for i in &instances {
+        let scale = i.transform.scale[0];
+        assert!(2.0 * scale < spacing);
+        assert!(i
+            .transform
+            .translation
+            .iter()
+            .all(|x| x.abs() + scale <= 2.0));
+    }
+
+    let full = instance::grid(64, 1.0, &jet());
+    let center: Vector3<f32> = full.iter().map(translation).sum::<Vector3<f32>>() / 64.0;
+    assert!(center.magnitude() < 1e-5);
+}
+
+#[test]
+fn particle_cloud_is_deterministic_and_inside_the_ball() {
+    let cloud = instance::particle_cloud(500, 3.0, 7, &jet());
+    assert_eq!(cloud.len(), 500);
+    assert_eq!(cloud, instance::particle_cloud(500, 3.0, 7, &jet()));
+    assert_ne!(cloud, instance::particle_cloud(500, 3.0, 8, &jet()));
+    for i in &cloud {
+        // a unit cube reaches sqrt(3) times its scale from its center
+        let reach = translation(i).magnitude() + 3f32.sqrt() * i.transform.scale[0];
+        assert!(reach <= 3.0 + 1e-5, "{}", reach);
+    }
+    // spread over the whole ball rather than a shell
+    let near = cloud
+        .iter()
+        .filter(|i| translation(i).magnitude() < 1.5)
+        .count();
+    assert!((30..100).contains(&near), "{}", near);
+}
+
+#[test]
+fn parse_layout_and_draw_mode() {
+    assert_eq!("grid".parse::<Layout>().unwrap(), Layout::Grid);
+    assert_eq!("cloud".parse::<Layout>().unwrap(), Layout::Cloud);
+    assert!("spiral".parse::<Layout>().is_err());
+    assert_eq!(
+        "instanced".parse::<DrawMode>().unwrap(),
+        DrawMode::Instanced
+    );
+    assert_eq!(
+        "per-object".parse::<DrawMode>().unwrap(),
+        DrawMode::PerObject
+    );
+    assert!("batched".parse::<DrawMode>().is_err());
+
+    assert_eq!(Layout::Grid.instances(1000, 4.0, &jet()).len(), 1000);
+    assert_eq!(Layout::Cloud.instances(1000, 4.0, &jet()).len(), 1000);
+}
+
+/// Renders a grid of cubes as ch06_instances does and reads the frame back.
+fn render_grid(init: &InitWgpu, count: usize, mode: DrawMode) -> RgbaImage {
+    let (pos, _col, _uv, normal) = vertex_data::cube_data();
+    let to_f32 = |v: &[i8; 3]| v.map(|x| x as f32);
+    let positions: Vec<[f32; 3]> = pos.iter().map(to_f32).collect();
+    let normals: Vec<[f32; 3]> = normal.iter().map(to_f32).collect();
+    let instances = Layout::Grid.instances(count, 4.0, &jet());
+
+    let depth = DepthTarget::new(
+        &init.device,
+        WIDTH,
+        HEIGHT,
+        init.sample_count(),
+        DepthConfig::default(),
+    );
+    let renderer = InstanceRenderer::new(
+        &init.device,
+        init.config.format,
+        depth.config,
+        init.sample_count(),
+        &positions,
+        &normals,
+        &instances,
+    );
+    let (_, _, view_project) = transforms::create_view_projection(
+        (3.5, 2.2, 3.5).into(),
+        (0.0, 0.0, 0.0).into(),
+        Vector3::unit_y(),
+        WIDTH as f32 / HEIGHT as f32,
+        true,
+    );
+    let model = transforms::create_transforms([0.0; 3], [0.0, 0.7, 0.3], [1.0; 3]);
+    renderer.update(&init.queue, view_project, model);
+
+    let frame = init.get_current_frame().unwrap();
+    let mut encoder = init
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Render Encoder"),
+        });
+    {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(
+                init.color_attachment(&frame, wgpu::LoadOp::Clear(wgpu::Color::BLACK)),
+            )],
+            depth_stencil_attachment: Some(depth.attachment()),
+        });
+        renderer.draw(&mut render_pass, mode);
+    }
+    init.queue.submit(iter::once(encoder.finish()));
+    init.read_pixels().unwrap()
+}
+
+// there is no CPU version of the renderer to fall back to
+#[test]
+#[ignore = "needs a GPU adapter"]
+fn draw_modes_render_the_same_pixels() {
+    let fallback = headless::env_flag("WGPU_BOOK_FALLBACK");
+    let init = pollster::block_on(InitWgpu::init_headless(WIDTH, HEIGHT, fallback))
+        .expect("no wgpu adapter to compare the draw modes on");
+    let instanced = render_grid(&init, 27, DrawMode::Instanced);
+    let per_object = render_grid(&init, 27, DrawMode::PerObject);
+
+    let background = instanced.pixels().filter(|p| p.0 == [0, 0, 0, 255]).count();
+    let total = (WIDTH * HEIGHT) as usize;
+    assert!(
+        background < total * 9 / 10,
+        "only {} of {} pixels show cubes",
+        total - background,
+        total
+    );
+    let differing = instanced
+        .pixels()
+        .zip(per_object.pixels())
+        .filter(|(a, b)| a != b)
+        .count();
+    assert_eq!(differing, 0, "{} of {} pixels differ", differing, total);
+}
@@ -0,0 +1,74 @@
+//! Key bindings of the ch06 fly camera and their JSON files.
+
+use std::path::Path;
+use wgpu_book::keybindings::{Action, KeyBindings};
+use winit::keyboard::{Key, NamedKey};
+
+fn character(c: &str) -> Key {
+    Key::Character(c.into())
+}
+
+#[test]
+fn defaults_are_wasd() {
+    let bindings = KeyBindings::default();
+    assert_eq!(bindings.action(&character("w")), Some(Action::Forward));
+    assert_eq!(bindings.action(&character("D")), Some(Action::Right));
+    assert_eq!(
+        bindings.action(&Key::Named(NamedKey::Shift)),
+        Some(Action::Boost)
+    );
+    assert_eq!(bindings.action(&character("x")), None);
+}
+
+#[test]
+fn bindings_file() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/ch06/keys.json");
+    let bindings = KeyBindings::from_file(path).unwrap();
+    assert_eq!(bindings.action(&character("w")), Some(Action::Forward));
+    assert_eq!(
+        bindings.action(&Key::Named(NamedKey::ArrowUp)),
+        Some(Action::Forward)
+    );
+    assert_eq!(
+        bindings.action(&Key::Named(NamedKey::PageDown)),
+        Some(Action::Down)
+    );
+}
+
+#[test]
+fn listed_actions_replace_their_default_keys() {
+    let bindings = KeyBindings::from_json(r#"{"forward": "i", "boost": "Control"}"#).unwrap();
+    assert_eq!(bindings.action(&character("i")), Some(Action::Forward));
+    assert_eq!(bindings.action(&character("w")), None);
+    assert_eq!(
+        bindings.action(&Key::Named(NamedKey::Control)),
+        Some(Action::Boost)
+    );
+    assert_eq!(bindings.action(&Key::Named(NamedKey::Shift)), None);
+    // actions that are left out keep their keys
+    assert_eq!(bindings.action(&character("s")), Some(Action::Backward));
+}
+
+#[test]
+fn unknown_action_lists_the_actions() {
+    let error = KeyBindings::from_json(r#"{"jump": "Space"}"#)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("unknown action \"jump\""), "{}", error);
+    assert!(error.contains("forward"), "{}", error);
+}
+
+#[test]
+fn malformed_bindings() {
+    assert!(KeyBindings::from_json(r#"["w"]"#).is_err());
+    assert!(KeyBindings::from_json(r#"{"forward": 1}"#).is_err());
+    assert!(KeyBindings::from_json(r#"{"forward": ["w", 1]}"#).is_err());
+    assert!(KeyBindings::from_json("{").is_err());
+}
+
+#[test]
+fn missing_file_names_the_path() {
+    let missing = Path::new(env!("CARGO_TARGET_TMPDIR")).join("no-such-keys.json");
+    let error = format!("{:#}", KeyBindings::from_file(&missing).unwrap_err());
+    assert!(error.contains("no-such-keys.json"), "{}", error);
+}
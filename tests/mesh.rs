@@ -0,0 +1,116 @@
+//! Normals and vertex sharing of indexed meshes.
+
+use cgmath::{InnerSpace, Vector3};
+use wgpu_book::colormap::Colormap;
+use wgpu_book::math_func;
+use wgpu_book::mesh::{Mesh, MeshVertex, Shading};
+use wgpu_book::surface_data;
+
+fn vertex(position: [f32; 3]) -> MeshVertex {
+    MeshVertex {
+        position,
+        ..Default::default()
+    }
+}
+
+fn assert_close(a: [f32; 3], b: [f32; 3]) {
+    assert!(
+        (Vector3::from(a) - Vector3::from(b)).magnitude() < 1e-5,
+        "{:?} != {:?}",
+        a,
+        b
+    );
+}
+
+/// A large triangle facing +z and a small one facing +y that share vertex 0.
+fn two_triangles() -> Mesh {
+    Mesh {
+        vertices: vec![
+            vertex([0.0, 0.0, 0.0]),
+            vertex([2.0, 0.0, 0.0]),
+            vertex([0.0, 2.0, 0.0]),
+            vertex([0.0, 0.0, 1.0]),
+            vertex([1.0, 0.0, 0.0]),
+        ],
+        indices: vec![0, 1, 2, 0, 3, 4],
+    }
+}
+
+#[test]
+fn smooth_normals_are_area_weighted() {
+    let mut mesh = two_triangles();
+    mesh.compute_smooth_normals();
+    // the areas are 2 and 0.5, so the large triangle counts four times as much
+    let expected = Vector3::new(0.0, 1.0, 4.0).normalize();
+    assert_close(mesh.vertices[0].normal, expected.into());
+    assert_close(mesh.vertices[1].normal, [0.0, 0.0, 1.0]);
+    assert_close(mesh.vertices[3].normal, [0.0, 1.0, 0.0]);
+}
+
+#[test]
+fn flat_copy_has_one_vertex_per_corner() {
+    let mesh = two_triangles().with_shading(Shading::Smooth);
+    let flat = mesh.to_flat();
+    assert_eq!(flat.vertices.len(), mesh.indices.len());
+    assert_eq!(flat.indices, (0..6).collect::<Vec<u32>>());
+    for (corner, &i) in flat.vertices.iter().zip(&mesh.indices) {
+        assert_eq!(corner.position, mesh.vertices[i as usize].position);
+    }
+    for v in &flat.vertices[..3] {
+        assert_close(v.normal, [0.0, 0.0, 1.0]);
+    }
+    for v in &flat.vertices[3..] {
+        assert_close(v.normal, [0.0, 1.0, 0.0]);
+    }
+
+    // `with_shading` gives the same copy
+    let shaded = two_triangles().with_shading(Shading::Flat);
+    assert_eq!(shaded.vertices, flat.vertices);
+}
+
+#[test]
+fn indexed_sinc_surface_shares_grid_points() {
+    let colormap = Colormap::from_name("jet").unwrap();
+    let (n, scale, scaley) = (30, 2.0, 0.3);
+    let (positions, ..) = surface_data::simple_surface_data(
+        &math_func::sinc,
+        &colormap,
+        -8.0,
+        8.0,
+        -8.0,
+        8.0,
+        n,
+        n,
+        scale,
+        scaley,
+    );
+    let mesh = surface_data::surface_mesh(
+        &math_func::sinc,
+        &colormap,
+        -8.0,
+        8.0,
+        -8.0,
+        8.0,
+        n,
+        n,
+        scale,
+        scaley,
+        Shading::Smooth,
+    );
+
+    // the triangle list stores six vertices per cell, the mesh one per grid point
+    assert_eq!(positions.len(), 6 * (n - 1) * (n - 1));
+    assert_eq!(mesh.vertices.len(), n * n);
+    assert_eq!(mesh.indices.len(), positions.len());
+    let ratio = positions.len() as f32 / mesh.vertices.len() as f32;
+    assert!((5.0..6.0).contains(&ratio), "{}", ratio);
+
+    // every smooth normal is a unit vector pointing up out of the height field
+    for v in &mesh.vertices {
+        assert!((Vector3::from(v.normal).magnitude() - 1.0).abs() < 1e-5);
+        assert!(v.normal[1] > 0.0);
+    }
+
+    let flat = mesh.with_shading(Shading::Flat);
+    assert_eq!(flat.vertices.len(), positions.len());
+}
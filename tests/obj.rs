@@ -0,0 +1,226 @@
+//! Parsing of OBJ and MTL text.
+
+use cgmath::{InnerSpace, Vector3};
+use std::path::{Path, PathBuf};
+use wgpu_book::mesh::Mesh;
+use wgpu_book::obj::{self, ObjModel};
+
+fn parse(text: &str) -> ObjModel {
+    ObjModel::parse(text, Path::new("")).unwrap()
+}
+
+fn temp_dir() -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("obj");
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn triangle(mesh: &Mesh, t: usize) -> [Vector3<f32>; 3] {
+    [0, 1, 2].map(|k| {
+        mesh.vertices[mesh.indices[3 * t + k] as usize]
+            .position
+            .into()
+    })
+}
+
+// signed area in the xy plane, positive if counter-clockwise
+fn signed_area(points: &[Vector3<f32>]) -> f32 {
+    let n = points.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % n]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum::<f32>()
+        / 2.0
+}
+
+// crossing number test in the xy plane
+fn inside(polygon: &[Vector3<f32>], p: Vector3<f32>) -> bool {
+    let n = polygon.len();
+    let mut crossings = 0;
+    for i in 0..n {
+        let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+        if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            crossings += 1;
+        }
+    }
+    crossings % 2 == 1
+}
+
+fn assert_close(a: [f32; 3], b: [f32; 3]) {
+    assert!(
+        (Vector3::from(a) - Vector3::from(b)).magnitude() < 1e-5,
+        "{:?} != {:?}",
+        a,
+        b
+    );
+}
+
+#[test]
+fn negative_indices_count_back_from_the_end() {
+    let model = parse(
+        "v 0 0 0\nv 1 0 0\nv 0 1 0\n\
+         vt 0 0\nvt 1 0\nvt 0 1\nvn 0 0 1\n\
+         f -3/-3/-1 -2/-2/-1 -1/-1/-1\n\
+         v 5 5 5\nf 1 2 -2\n",
+    );
+    assert_eq!(model.mesh.indices.len(), 6);
+    let first = triangle(&model.mesh, 0);
+    assert_eq!(first[1], Vector3::new(1.0, 0.0, 0.0));
+    // the second face refers to the third vertex again, before the new one
+    assert_eq!(triangle(&model.mesh, 1)[2], Vector3::new(0.0, 1.0, 0.0));
+    // texture coordinates are flipped to the top-left origin
+    let uvs: Vec<[f32; 2]> = model.mesh.vertices[..3].iter().map(|v| v.uv).collect();
+    assert_eq!(uvs, [[0.0, 1.0], [1.0, 1.0], [0.0, 0.0]]);
+    assert_eq!(model.mesh.vertices[0].normal, [0.0, 0.0, 1.0]);
+}
+
+#[test]
+fn out_of_range_index_is_an_error() {
+    for face in ["f 1 2 4", "f -4 1 2", "f 0 1 2", "f 1/2 2 3", "f 1//2 2 3"] {
+        let text = format!("v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvn 0 0 1\n{}\n", face);
+        let error = format!("{:#}", ObjModel::parse(&text, Path::new("")).unwrap_err());
+        assert!(error.contains("line 6"), "{}", error);
+        assert!(error.contains("out of range"), "{}", error);
+    }
+    assert!(ObjModel::parse("v 0 0 0\nv 1 0 0\nf 1 2\n", Path::new("")).is_err());
+}
+
+#[test]
+fn concave_polygon_is_ear_clipped() {
+    // a pentagon with a notch at (1, 0.5)
+    let corners = [[0.0, 0.0], [2.0, 0.0], [2.0, 2.0], [1.0, 0.5], [0.0, 2.0]];
+    let mut text = String::new();
+    for [x, y] in corners {
+        text += &format!("v {} {} 0\n", x, y);
+    }
+    text += "f 1 2 3 4 5\n";
+    let model = parse(&text);
+
+    let polygon: Vec<Vector3<f32>> = corners
+        .iter()
+        .map(|c| Vector3::new(c[0], c[1], 0.0))
+        .collect();
+    assert_eq!(model.mesh.indices.len(), 9);
+    let mut area = 0.0;
+    for t in 0..3 {
+        let tri = triangle(&model.mesh, t);
+        // every triangle keeps the winding of the face...
+        let a = signed_area(&tri);
+        assert!(a > 0.0, "{:?}", tri);
+        area += a;
+        // ...and lies inside it, which the notch rules out for a fan from 1
+        let center = (tri[0] + tri[1] + tri[2]) / 3.0;
+        assert!(inside(&polygon, center), "{:?}", tri);
+    }
+    // triangles inside the polygon that do not overlap cover exactly its area
+    assert!((area - signed_area(&polygon)).abs() < 1e-5, "{}", area);
+    assert_close(model.mesh.vertices[0].normal, [0.0, 0.0, 1.0]);
+}
+
+// two faces of a roof meeting at a ridge along z
+const ROOF: &str = "v -1 0 1\nv 0 1 1\nv 0 1 -1\nv -1 0 -1\nv 1 0 1\nv 1 0 -1\n";
+
+#[test]
+fn flat_faces_get_face_normals() {
+    let model = parse(&format!("{}f 1 2 3 4\nf 2 5 6 3\n", ROOF));
+    // the ridge vertices are not shared between the faces
+    assert_eq!(model.mesh.vertices.len(), 8);
+    let left = Vector3::new(-1.0, 1.0, 0.0).normalize();
+    let right = Vector3::new(1.0, 1.0, 0.0).normalize();
+    for (t, normal) in [left, left, right, right].iter().enumerate() {
+        for k in 0..3 {
+            let v = model.mesh.vertices[model.mesh.indices[3 * t + k] as usize];
+            assert_close(v.normal, (*normal).into());
+        }
+    }
+}
+
+#[test]
+fn smoothing_group_averages_normals() {
+    // triangles split so that every ridge vertex has as many on either side
+    let model = parse(&format!(
+        "{}s 1\nf 1 2 3\nf 1 3 4\nf 5 6 3\nf 5 3 2\n",
+        ROOF
+    ));
+    assert_eq!(model.mesh.vertices.len(), 6);
+    for v in &model.mesh.vertices {
+        let expected = match v.position[0] {
+            x if x < 0.0 => Vector3::new(-1.0, 1.0, 0.0).normalize(),
+            x if x > 0.0 => Vector3::new(1.0, 1.0, 0.0).normalize(),
+            _ => Vector3::unit_y(),
+        };
+        assert_close(v.normal, expected.into());
+    }
+
+    // `s off` ends the group
+    let model = parse(&format!("{}s 1\nf 1 2 3 4\ns off\nf 2 5 6 3\n", ROOF));
+    assert_eq!(model.mesh.vertices.len(), 8);
+}
+
+#[test]
+fn materials_from_mtllib() {
+    let dir = temp_dir();
+    std::fs::write(
+        dir.join("materials.mtl"),
+        "# two materials\n\
+         newmtl red\nKa 0.2 0 0\nKd 1 0 0\nKs 0.5\nNs 40\nTr 0.25\n\n\
+         newmtl gray\nKd 0.5\nd 0.5\nmap_Kd -s 1 1 1 -o 0 0 0 bricks.png\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("model.obj"),
+        "mtllib materials.mtl missing.mtl\n\
+         v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nvt 0 0\nvn 0 0 1\n\
+         f 1/1/1 2/1/1 3/1/1\n\
+         usemtl red\nf 2/1/1 4/1/1 3/1/1\n\
+         usemtl gray\nf 3/1/1 4/1/1 1/1/1\n\
+         usemtl red\nf 1/1/1 2/1/1 4/1/1\n",
+    )
+    .unwrap();
+    let model = ObjModel::load(dir.join("model.obj")).unwrap();
+
+    let [red, gray] = &model.materials[..] else {
+        panic!("{:?}", model.materials);
+    };
+    assert_eq!(red.name, "red");
+    assert_eq!(red.ambient, [0.2, 0.0, 0.0]);
+    assert_eq!(red.diffuse, [1.0, 0.0, 0.0]);
+    assert_eq!(red.specular, [0.5; 3]);
+    assert_eq!(red.shininess, 40.0);
+    assert_eq!(red.opacity, 0.75);
+    assert_eq!(red.diffuse_texture, None);
+    // a single Kd value is a gray, and the texture is the last argument
+    assert_eq!(gray.diffuse, [0.5; 3]);
+    assert_eq!(gray.opacity, 0.5);
+    assert_eq!(gray.diffuse_texture, Some(dir.join("bricks.png")));
+
+    assert_eq!(model.triangle_materials, [None, Some(0), Some(1), Some(0)]);
+    assert_eq!(model.main_material(), Some(red));
+
+    // corners shared by faces of different materials keep their own colors
+    let colors = |t: usize| -> Vec<[f32; 3]> {
+        (0..3)
+            .map(|k| model.mesh.vertices[model.mesh.indices[3 * t + k] as usize].color)
+            .collect()
+    };
+    assert_eq!(colors(0), [[1.0; 3]; 3]);
+    assert_eq!(colors(1), [[1.0, 0.0, 0.0]; 3]);
+    assert_eq!(colors(2), [[0.5; 3]; 3]);
+    assert_eq!(colors(3), [[1.0, 0.0, 0.0]; 3]);
+    assert_eq!(model.mesh.vertices.len(), 10);
+}
+
+#[test]
+fn mtl_errors() {
+    assert!(obj::parse_mtl("Kd 1 0 0\n", Path::new("")).is_err());
+    assert!(obj::parse_mtl("newmtl a\nKd red\n", Path::new("")).is_err());
+    assert!(obj::parse_mtl("newmtl a\nmap_Kd\n", Path::new("")).is_err());
+    let materials = obj::parse_mtl("newmtl a b\nmap_Kd wall.png\n", Path::new("assets")).unwrap();
+    assert_eq!(materials[0].name, "a b");
+    assert_eq!(
+        materials[0].diffuse_texture,
+        Some(Path::new("assets").join("wall.png"))
+    );
+}
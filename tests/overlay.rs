@@ -0,0 +1,44 @@
+//! Tick placement and labels of the plot overlay.
+
+use wgpu_book::overlay::{format_tick, nice_ticks};
+
+fn assert_ticks(min: f32, max: f32, count: usize, expected: &[f32], expected_step: f32) {
+    let (ticks, step) = nice_ticks(min, max, count);
+    assert_eq!(step, expected_step, "{}..{}", min, max);
+    assert_eq!(ticks.len(), expected.len(), "{:?}", ticks);
+    for (t, e) in ticks.iter().zip(expected) {
+        assert!((t - e).abs() < 1e-5 * step, "{:?} != {:?}", ticks, expected);
+    }
+}
+
+#[test]
+fn ticks_use_round_steps() {
+    assert_ticks(0.0, 1.0, 5, &[0.0, 0.2, 0.4, 0.6, 0.8, 1.0], 0.2);
+    assert_ticks(0.0, 10.0, 5, &[0.0, 2.0, 4.0, 6.0, 8.0, 10.0], 2.0);
+    assert_ticks(-8.0, 8.0, 5, &[-5.0, 0.0, 5.0], 5.0);
+    assert_ticks(-0.217, 1.0, 5, &[0.0, 0.5, 1.0], 0.5);
+    assert_ticks(3.0, 4.0, 1, &[3.0, 4.0], 1.0);
+    assert_ticks(0.0, 1234.0, 4, &[0.0, 500.0, 1000.0], 500.0);
+    // the end points count even when rounding leaves them slightly outside
+    assert_ticks(0.0, 0.3, 3, &[0.0, 0.1, 0.2, 0.3], 0.1);
+}
+
+#[test]
+fn ticks_of_an_empty_range() {
+    assert_eq!(nice_ticks(2.0, 2.0, 5), (vec![2.0], 1.0));
+    assert_eq!(nice_ticks(3.0, 1.0, 5), (vec![3.0], 1.0));
+    assert_eq!(nice_ticks(0.0, f32::INFINITY, 5).0, [0.0]);
+}
+
+#[test]
+fn tick_labels_have_the_decimals_of_the_step() {
+    assert_eq!(format_tick(0.0, 0.2), "0");
+    assert_eq!(format_tick(-0.0, 0.2), "0");
+    assert_eq!(format_tick(5.0, 5.0), "5");
+    assert_eq!(format_tick(-10.0, 5.0), "-10");
+    assert_eq!(format_tick(0.4, 0.2), "0.4");
+    assert_eq!(format_tick(0.25, 0.05), "0.25");
+    assert_eq!(format_tick(1.5, 0.5), "1.5");
+    assert_eq!(format_tick(250000.0, 50000.0), "2.5e5");
+    assert_eq!(format_tick(0.00002, 0.00001), "2.0e-5");
+}
@@ -0,0 +1,197 @@
+//! Normals and texture coordinates of parametric surfaces.
+
+use cgmath::{InnerSpace, Vector3};
+use std::f32::consts::{PI, TAU};
+use wgpu_book::math_func;
+use wgpu_book::mesh::Mesh;
+use wgpu_book::surface_data::{self, ColorBy, Seam, SurfaceOptions};
+
+const NU: usize = 17;
+const NV: usize = 9;
+
+fn torus(u: f32, v: f32) -> [f32; 3] {
+    let r = 2.0 + 0.5 * v.cos();
+    [r * u.cos(), 0.5 * v.sin(), r * u.sin()]
+}
+
+fn sphere(u: f32, v: f32) -> [f32; 3] {
+    [u.sin() * v.cos(), u.cos(), u.sin() * v.sin()]
+}
+
+fn normal(mesh: &Mesh, i: usize, j: usize) -> Vector3<f32> {
+    mesh.vertices[i * NV + j].normal.into()
+}
+
+fn assert_seam_normals_match(mesh: &Mesh) {
+    for j in 0..NV {
+        let (first, last) = (normal(mesh, 0, j), normal(mesh, NU - 1, j));
+        assert!(
+            (first - last).magnitude() < 1e-4,
+            "{:?} != {:?}",
+            first,
+            last
+        );
+        assert!((first.magnitude() - 1.0).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn closed_seam_has_matching_normals() {
+    let options = SurfaceOptions {
+        seam_u: Seam::Closed,
+        seam_v: Seam::Closed,
+        ..Default::default()
+    };
+    let mesh = surface_data::parametric_surface(&torus, [0.0, TAU], [0.0, TAU], NU, NV, &options);
+    assert_seam_normals_match(&mesh);
+
+    // the averaged face normals of the fallback are welded across the seam too
+    let zero = |_: f32, _: f32| [0.0; 3];
+    let options = SurfaceOptions {
+        normal: Some(&zero),
+        ..options
+    };
+    let mesh = surface_data::parametric_surface(&torus, [0.0, TAU], [0.0, TAU], NU, NV, &options);
+    assert_seam_normals_match(&mesh);
+}
+
+fn mobius(u: f32, v: f32) -> [f32; 3] {
+    math_func::mobius_strip(u, v)
+}
+
+fn assert_seam_pairs_match(mesh: &Mesh, seam_u: Seam, seam_v: Seam) {
+    let pairs = surface_data::seam_pairs(NU, NV, seam_u, seam_v);
+    assert!(!pairs.is_empty());
+    for (a, b, sign) in pairs {
+        let (a, b) = (&mesh.vertices[a], &mesh.vertices[b]);
+        let distance = (Vector3::from(a.position) - Vector3::from(b.position)).magnitude();
+        assert!(distance < 1e-5, "{:?} != {:?}", a.position, b.position);
+        let flipped = sign * Vector3::from(b.normal);
+        assert!(
+            (Vector3::from(a.normal) - flipped).magnitude() < 1e-4,
+            "{:?} != {:?}",
+            a.normal,
+            flipped
+        );
+    }
+}
+
+#[test]
+fn seam_pairs_lie_on_each_other() {
+    let zero = |_: f32, _: f32| [0.0f32; 3];
+    for normal in [None, Some(&zero as &dyn Fn(f32, f32) -> [f32; 3])] {
+        let options = SurfaceOptions {
+            seam_u: Seam::Closed,
+            seam_v: Seam::Closed,
+            normal,
+            ..Default::default()
+        };
+        let mesh =
+            surface_data::parametric_surface(&torus, [0.0, TAU], [0.0, TAU], NU, NV, &options);
+        assert_seam_pairs_match(&mesh, Seam::Closed, Seam::Closed);
+
+        // the half twist joins v to -v, and the normals flip across the seam
+        let options = SurfaceOptions {
+            seam_u: Seam::Twisted,
+            seam_v: Seam::Open,
+            ..options
+        };
+        let mesh =
+            surface_data::parametric_surface(&mobius, [0.0, TAU], [-1.0, 1.0], NU, NV, &options);
+        assert_seam_pairs_match(&mesh, Seam::Twisted, Seam::Open);
+
+        let options = SurfaceOptions {
+            seam_u: Seam::Open,
+            seam_v: Seam::Twisted,
+            ..options
+        };
+        let swapped = |u: f32, v: f32| mobius(v, u);
+        let mesh =
+            surface_data::parametric_surface(&swapped, [-1.0, 1.0], [0.0, TAU], NU, NV, &options);
+        assert_seam_pairs_match(&mesh, Seam::Open, Seam::Twisted);
+    }
+}
+
+#[test]
+fn poles_fall_back_to_face_normals() {
+    let options = SurfaceOptions {
+        seam_v: Seam::Closed,
+        ..Default::default()
+    };
+    // the v derivative vanishes on the first and last rows
+    let mesh = surface_data::parametric_surface(&sphere, [0.0, PI], [0.0, TAU], NU, NV, &options);
+    for i in [0, NU - 1] {
+        for j in 0..NV {
+            let n = normal(&mesh, i, j);
+            assert!((n.magnitude() - 1.0).abs() < 1e-5, "{:?}", n);
+            assert!(n.y.abs() > 0.5, "{:?}", n);
+        }
+    }
+}
+
+#[test]
+fn uvs_cover_the_unit_square() {
+    let mesh = surface_data::parametric_surface(
+        &torus,
+        [0.0, TAU],
+        [-1.0, 1.0],
+        NU,
+        NV,
+        &SurfaceOptions::default(),
+    );
+    assert_eq!(mesh.vertices.len(), NU * NV);
+    assert_eq!(mesh.indices.len(), 6 * (NU - 1) * (NV - 1));
+    for v in &mesh.vertices {
+        assert!(v.uv.iter().all(|t| (0.0..=1.0).contains(t)), "{:?}", v.uv);
+    }
+    assert_eq!(mesh.vertices[0].uv, [0.0, 0.0]);
+    assert_eq!(mesh.vertices[NV - 1].uv, [0.0, 1.0]);
+    assert_eq!(mesh.vertices[(NU - 1) * NV].uv, [1.0, 0.0]);
+    assert_eq!(mesh.vertices[NU * NV - 1].uv, [1.0, 1.0]);
+}
+
+#[test]
+fn plot_box_matches_fitted_surface() {
+    let options = SurfaceOptions {
+        seam_u: Seam::Closed,
+        seam_v: Seam::Closed,
+        color_by: ColorBy::U,
+        scale: Some(1.8),
+        ..Default::default()
+    };
+    let mesh = surface_data::parametric_surface(&torus, [0.0, TAU], [0.0, TAU], NU, NV, &options);
+    let plot_box =
+        surface_data::parametric_plot_box(&torus, [0.0, TAU], [0.0, TAU], NU, NV, &options);
+
+    for k in 0..3 {
+        let min = mesh
+            .vertices
+            .iter()
+            .map(|v| v.position[k])
+            .fold(f32::INFINITY, f32::min);
+        let max = mesh
+            .vertices
+            .iter()
+            .map(|v| v.position[k])
+            .fold(f32::NEG_INFINITY, f32::max);
+        assert!((plot_box.world_min[k] - min).abs() < 1e-5, "{:?}", plot_box);
+        assert!((plot_box.world_max[k] - max).abs() < 1e-5, "{:?}", plot_box);
+    }
+    // x and z are the unscaled bounds, y the range of the colormap scalar
+    assert!((plot_box.data_min[0] + 2.5).abs() < 1e-5);
+    assert!((plot_box.data_max[2] - 2.5).abs() < 1e-2);
+    assert_eq!([plot_box.data_min[1], plot_box.data_max[1]], [0.0, TAU]);
+}
+
+#[test]
+#[should_panic(expected = "at least 2 x 2 samples")]
+fn single_row_is_rejected() {
+    surface_data::parametric_surface(
+        &torus,
+        [0.0, TAU],
+        [0.0, TAU],
+        1,
+        NV,
+        &SurfaceOptions::default(),
+    );
+}
@@ -0,0 +1,131 @@
+//! Transform propagation and world-matrix caching of the scene graph.
+
+use cgmath::{Matrix, Matrix4, SquareMatrix, Vector4};
+use wgpu_book::scene::{SceneGraph, SceneNode, Transform};
+use wgpu_book::transforms;
+
+fn assert_matrix_close(a: Matrix4<f32>, b: Matrix4<f32>) {
+    let (a, b): (&[f32; 16], &[f32; 16]) = (a.as_ref(), b.as_ref());
+    for i in 0..16 {
+        assert!((a[i] - b[i]).abs() < 1e-5, "{:?} != {:?}", a, b);
+    }
+}
+
+fn transform(translation: [f32; 3], rotation: [f32; 3], scale: [f32; 3]) -> Transform {
+    Transform::new(translation, rotation, scale)
+}
+
+/// root -> arm -> hand, plus a second root.
+fn arm() -> SceneGraph {
+    let mut graph = SceneGraph::new();
+    let root = graph.add(
+        None,
+        SceneNode::new("root").with_transform(transform(
+            [1.0, 0.0, 0.0],
+            [0.0, 0.5, 0.0],
+            [2.0; 3],
+        )),
+    );
+    let arm = graph.add(
+        Some(root),
+        SceneNode::new("arm")
+            .with_transform(transform([0.0, 1.0, 0.0], [0.3, 0.0, 0.0], [1.0; 3]))
+            .with_mesh(0),
+    );
+    graph.add(
+        Some(arm),
+        SceneNode::new("hand")
+            .with_transform(Transform::from_translation([0.0, 0.0, 0.5]))
+            .with_mesh(1)
+            .with_material(2),
+    );
+    graph.add(None, SceneNode::new("lamp").with_mesh(2));
+    graph
+}
+
+#[test]
+fn world_matrices_compose_parents() {
+    let mut graph = arm();
+    let [root, arm, hand] = ["root", "arm", "hand"].map(|n| graph.find(n).unwrap());
+    let expected = graph.node(root).transform().matrix()
+        * graph.node(arm).transform().matrix()
+        * graph.node(hand).transform().matrix();
+    assert_matrix_close(graph.world_matrix(hand), expected);
+    // the translation matches create_transforms
+    assert_matrix_close(
+        graph.node(hand).transform().matrix(),
+        transforms::create_transforms([0.0, 0.0, 0.5], [0.0; 3], [1.0; 3]),
+    );
+    assert_eq!(graph.node(hand).parent(), Some(arm));
+    assert_eq!(graph.node(root).children(), &[arm]);
+}
+
+#[test]
+fn dirty_flags_limit_recomputation() {
+    let mut graph = arm();
+    let [root, arm, hand, lamp] = ["root", "arm", "hand", "lamp"].map(|n| graph.find(n).unwrap());
+    assert_eq!(graph.update(), 4);
+    assert_eq!(graph.update(), 0);
+
+    // only the changed subtree is recomputed
+    graph.set_transform(arm, Transform::from_translation([0.0, 2.0, 0.0]));
+    assert!(!graph.is_dirty(root));
+    assert!(graph.is_dirty(arm) && graph.is_dirty(hand));
+    assert!(!graph.is_dirty(lamp));
+    assert_eq!(graph.update(), 2);
+
+    // asking for one world matrix leaves unrelated nodes dirty
+    graph.set_transform(root, Transform::default());
+    let world = graph.world_matrix(arm);
+    assert_matrix_close(world, Matrix4::from_translation([0.0, 2.0, 0.0].into()));
+    assert!(!graph.is_dirty(root) && !graph.is_dirty(arm));
+    assert!(graph.is_dirty(hand));
+    assert_eq!(graph.update(), 1);
+
+    let p = graph.world_matrix(hand) * Vector4::new(0.0, 0.0, 0.0, 1.0);
+    assert_eq!(p, Vector4::new(0.0, 2.0, 0.5, 1.0));
+}
+
+#[test]
+fn reparenting() {
+    let mut graph = arm();
+    let [root, arm, hand, lamp] = ["root", "arm", "hand", "lamp"].map(|n| graph.find(n).unwrap());
+    graph.update();
+
+    assert!(graph.set_parent(root, Some(hand)).is_err());
+    assert!(graph.set_parent(arm, Some(arm)).is_err());
+
+    graph.set_parent(hand, Some(lamp)).unwrap();
+    assert!(graph.node(arm).children().is_empty());
+    assert_eq!(graph.node(lamp).children(), &[hand]);
+    assert!(graph.is_dirty(hand) && !graph.is_dirty(arm));
+    assert_matrix_close(
+        graph.world_matrix(hand),
+        Matrix4::from_translation([0.0, 0.0, 0.5].into()),
+    );
+
+    graph.set_parent(arm, None).unwrap();
+    assert_eq!(graph.roots(), &[root, lamp, arm]);
+    assert_matrix_close(
+        graph.world_matrix(arm),
+        graph.node(arm).transform().matrix(),
+    );
+}
+
+#[test]
+fn draw_list_in_depth_first_order() {
+    let mut graph = arm();
+    let items = graph.draw_list();
+    let names: Vec<&str> = items
+        .iter()
+        .map(|item| graph.node(item.node).name.as_str())
+        .collect();
+    assert_eq!(names, ["arm", "hand", "lamp"]);
+    assert_eq!(items.iter().map(|i| i.mesh).collect::<Vec<_>>(), [0, 1, 2]);
+    assert_eq!(items[1].material, Some(2));
+    assert_eq!(items[0].material, None);
+    for item in &items {
+        assert_matrix_close(item.world, graph.world_matrix(item.node));
+        assert_matrix_close(item.normal, item.world.invert().unwrap().transpose());
+    }
+}